@@ -0,0 +1,88 @@
+// animation.rs — Keyframed model transform the EE samples once per frame.
+// Maps to: the game-side animation playback a PS2 title runs on the EE before building
+// each frame's matrices; the result only reaches VU1 as the MVP upload.
+//
+// Keys hold translation, Euler rotation (degrees, applied Y then X then Z) and a
+// uniform scale at a time in nominal frames. Between two keys every component is
+// interpolated with the animation's easing; before the first key and after the last
+// the end key holds, unless the animation loops over the span of its keys.
+
+/// A model pose at `time` (in nominal 60 Hz frames).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub time:        f32,
+    pub translation: [f32; 3],
+    pub rotation:    [f32; 3], // degrees about X, Y, Z
+    pub scale:       f32,
+}
+
+/// How the blend factor between two keys follows time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Smoothstep: starts and ends each segment at rest.
+    EaseInOut,
+}
+
+impl Easing {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear    => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Animation {
+    keys:    Vec<Keyframe>,
+    easing:  Easing,
+    looping: bool,
+}
+
+impl Animation {
+    /// Keys must be non-empty and in strictly increasing time order.
+    pub fn new(keys: Vec<Keyframe>, easing: Easing, looping: bool) -> Result<Self, String> {
+        if keys.is_empty() {
+            return Err("animation needs at least one keyframe".into());
+        }
+        if let Some(w) = keys.windows(2).find(|w| w[1].time <= w[0].time) {
+            return Err(format!(
+                "keyframe times must increase, got {} after {}", w[1].time, w[0].time,
+            ));
+        }
+        Ok(Animation { keys, easing, looping })
+    }
+
+    /// Pose at `time`: the keys around it blended by the easing. The returned key's
+    /// `time` is the (possibly wrapped) sample time.
+    pub fn sample(&self, time: f32) -> Keyframe {
+        let first = self.keys[0];
+        let last  = self.keys[self.keys.len() - 1];
+        let span  = last.time - first.time;
+        let t = if self.looping && span > 0.0 {
+            first.time + (time - first.time).rem_euclid(span)
+        } else {
+            time.clamp(first.time, last.time)
+        };
+
+        // First key at or after t; the segment is [i - 1, i]
+        let i = self.keys.partition_point(|k| k.time < t);
+        if i == 0 {
+            return Keyframe { time: t, ..first };
+        }
+        if i == self.keys.len() {
+            return Keyframe { time: t, ..last };
+        }
+        let (a, b) = (self.keys[i - 1], self.keys[i]);
+        let s = self.easing.apply((t - a.time) / (b.time - a.time));
+        let lerp = |x: f32, y: f32| x + (y - x) * s;
+        Keyframe {
+            time:        t,
+            translation: [0, 1, 2].map(|c| lerp(a.translation[c], b.translation[c])),
+            rotation:    [0, 1, 2].map(|c| lerp(a.rotation[c], b.rotation[c])),
+            scale:       lerp(a.scale, b.scale),
+        }
+    }
+}
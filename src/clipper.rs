@@ -0,0 +1,151 @@
+// clipper.rs — Clip-space frustum clipper.
+// Maps to: the clipping stage a PS2 VU1 micro-program runs before the perspective
+// divide (the GS itself has no clipper, only a scissor rectangle). Here the EE runs it
+// on each mesh before the upload, so VU1 only ever divides by a positive w.
+//
+// Sutherland–Hodgman against the six planes -w ≤ x,y ≤ w and near ≤ z ≤ w, where the
+// near plane is z = -w or z = 0 for the two depth ranges `perspective` in ee.rs can
+// build. A triangle clips to a convex polygon of at most 9 vertices, which is re-emitted
+// as a triangle fan around its first vertex.
+
+/// A clip-space vertex: homogeneous position plus its barycentric weights over the
+/// source triangle's corners, from which the caller interpolates any attribute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClipVertex {
+    pub pos:     [f32; 4],
+    pub weights: [f32; 3],
+}
+
+/// Plane coefficients: a vertex is inside when `dot(plane, pos) >= 0`.
+fn planes(near_at_zero: bool) -> [[f32; 4]; 6] {
+    [
+        [-1.0,  0.0,  0.0, 1.0], // right   x ≤  w
+        [ 1.0,  0.0,  0.0, 1.0], // left    x ≥ -w
+        [ 0.0, -1.0,  0.0, 1.0], // top     y ≤  w
+        [ 0.0,  1.0,  0.0, 1.0], // bottom  y ≥ -w
+        [ 0.0,  0.0, -1.0, 1.0], // far     z ≤  w
+        // near    z ≥ -w, or z ≥ 0 for a 0..1 depth range
+        [ 0.0,  0.0,  1.0, if near_at_zero { 0.0 } else { 1.0 }],
+    ]
+}
+
+fn plane_dist(v: &ClipVertex, plane: &[f32; 4]) -> f32 {
+    (0..4).map(|i| plane[i] * v.pos[i]).sum()
+}
+
+fn lerp(a: &ClipVertex, b: &ClipVertex, t: f32) -> ClipVertex {
+    ClipVertex {
+        pos:     std::array::from_fn(|i| a.pos[i] + (b.pos[i] - a.pos[i]) * t),
+        weights: std::array::from_fn(|i| a.weights[i] + (b.weights[i] - a.weights[i]) * t),
+    }
+}
+
+/// Clip a convex polygon against one plane.
+fn clip_against(poly: &[ClipVertex], plane: &[f32; 4]) -> Vec<ClipVertex> {
+    let mut out = Vec::with_capacity(poly.len() + 1);
+    for (i, cur) in poly.iter().enumerate() {
+        let next = &poly[(i + 1) % poly.len()];
+        let dc = plane_dist(cur, plane);
+        let dn = plane_dist(next, plane);
+
+        if dc >= 0.0 {
+            out.push(*cur);
+        }
+        // Edge crosses the plane: emit the intersection point. An endpoint on the plane
+        // is emitted as itself, not again as an intersection
+        if (dc > 0.0 && dn < 0.0) || (dc < 0.0 && dn > 0.0) {
+            out.push(lerp(cur, next, dc / (dc - dn)));
+        }
+    }
+    out
+}
+
+/// Clip a convex polygon against all six frustum planes.
+pub fn clip_polygon(poly: &[ClipVertex], near_at_zero: bool) -> Vec<ClipVertex> {
+    let mut cur = poly.to_vec();
+    for plane in &planes(near_at_zero) {
+        if cur.is_empty() {
+            break;
+        }
+        cur = clip_against(&cur, plane);
+    }
+    cur
+}
+
+/// True if `pos` is inside (or on) all six frustum planes.
+pub fn inside_frustum(pos: &[f32; 4], near_at_zero: bool) -> bool {
+    let v = ClipVertex { pos: *pos, weights: [0.0; 3] };
+    planes(near_at_zero).iter().all(|plane| plane_dist(&v, plane) >= 0.0)
+}
+
+/// Clip the triangle with clip-space corners `pos` and return the surviving part as a
+/// fan of triangles, each vertex weighting the corners. Fully-inside triangles come back
+/// unchanged (unit weights); fully-outside ones yield nothing.
+pub fn clip_triangle(pos: &[[f32; 4]; 3], near_at_zero: bool) -> Vec<[ClipVertex; 3]> {
+    let tri: [ClipVertex; 3] = std::array::from_fn(|i| {
+        let mut weights = [0.0; 3];
+        weights[i] = 1.0;
+        ClipVertex { pos: pos[i], weights }
+    });
+    if pos.iter().all(|p| inside_frustum(p, near_at_zero)) {
+        return vec![tri];
+    }
+
+    let poly = clip_polygon(&tri, near_at_zero);
+    if poly.len() < 3 {
+        return vec![];
+    }
+    (1..poly.len() - 1)
+        .map(|i| [poly[0], poly[i], poly[i + 1]])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangle_poking_out_right_and_top_is_cut_at_both() {
+        // The corner at (2, 2) pokes past x = w and y = w (w = 1)
+        let pos = [[0.0, 0.0, 0.0, 1.0], [2.0, 0.0, 0.0, 1.0], [0.0, 2.0, 0.0, 1.0]];
+        let fan = clip_triangle(&pos, false);
+        let mut corners: Vec<[f32; 2]> = Vec::new();
+        for v in fan.iter().flatten() {
+            assert!(inside_frustum(&v.pos, false));
+            let xy = [v.pos[0], v.pos[1]];
+            if !corners.contains(&xy) {
+                corners.push(xy);
+            }
+        }
+        // The clipped polygon: the origin, (1, 0), (1, 1) and (0, 1) — a unit square
+        corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(corners, [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]]);
+        assert_eq!(fan.len(), 2);
+
+        // Weights reproduce each position from the corners
+        for v in fan.iter().flatten() {
+            for (axis, &p) in v.pos.iter().enumerate() {
+                let weighed: f32 = (0..3).map(|c| v.weights[c] * pos[c][axis]).sum();
+                assert!((weighed - p).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn inside_and_outside_triangles() {
+        let inside = [[0.0, 0.0, 0.0, 1.0], [0.5, 0.0, 0.0, 1.0], [0.0, 0.5, 0.0, 1.0]];
+        let fan = clip_triangle(&inside, false);
+        assert_eq!(fan.len(), 1);
+        assert_eq!(fan[0].map(|v| v.pos), inside);
+
+        let behind = [[0.0, 0.0, 0.0, -1.0], [0.5, 0.0, 0.0, -1.0], [0.0, 0.5, 0.0, -1.0]];
+        assert!(clip_triangle(&behind, false).is_empty());
+    }
+
+    #[test]
+    fn near_plane_follows_the_depth_range() {
+        let pos = [0.0, 0.0, -0.5, 1.0];
+        assert!(inside_frustum(&pos, false));
+        assert!(!inside_frustum(&pos, true));
+    }
+}
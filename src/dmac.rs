@@ -8,11 +8,12 @@ pub struct Dmac {
     pub d1_madr: u32,   // PS2 MMIO: 0x10009010 — DMA source address
     pub d1_qwc:  u32,   // PS2 MMIO: 0x10009020 — quadword count
     pub d1_chcr: u32,   // PS2 MMIO: 0x10009000 — channel control (bit 8 = STR)
+    pub log:     Vec<String>,   // diagnostics for EmulatorCore::drain_log
 }
 
 impl Dmac {
     pub fn new() -> Self {
-        Dmac { d1_madr: 0, d1_qwc: 0, d1_chcr: 0 }
+        Dmac { d1_madr: 0, d1_qwc: 0, d1_chcr: 0, log: Vec::new() }
     }
 
     /// Kick DMA channel 1: set MADR, QWC, and STR bit.
@@ -34,6 +35,10 @@ impl Dmac {
         let end  = base + qwc * 16;
 
         if end > ram.len() {
+            self.log.push(format!(
+                "DMAC: transfer MADR=0x{base:X} QWC={qwc} overruns EE RAM (0x{:X} bytes); dropped",
+                ram.len(),
+            ));
             self.d1_chcr &= !0x100;
             return;
         }
@@ -3,7 +3,7 @@
 // Instead of interpreting MIPS, we build the exact byte layout that real MIPS code
 // would produce — a valid PS2 VIF1 DMA packet — directly in Rust.
 //
-// Packet layout (125 QWs = 2000 bytes from EE RAM offset 0x00100000):
+// Packet layout (125 QWs = 2000 bytes from the current packet slot in EE RAM):
 //
 //  QW  0     STCYCL(wl=1,cl=1)
 //  QW  1     UNPACK V4-32 num=1 addr=108   → GIF tag pre-load
@@ -19,35 +19,108 @@
 //  QW 119    UNPACK V4-32 num=1 addr=186   → light dir + ambient
 //  QW 120    [0.577, 0.577, 0.577, 0.2]
 //  QW 121    UNPACK V4-32 num=1 addr=187   → viewport scale
-//  QW 122    [5120.0, 3584.0, 0.0, 0.0]
+//  QW 122    [w/2, h/2, 0.0, 1/255] — [320, 224] at 640×448
 //  QW 123    MSCAL execaddr=0
 //  QW 124    FLUSH
 //  Total: 125 QWs
+//
+// With packed colors, QW 77 is UNPACK V4-8 (USN) and the 36 colors take 9 QWs of RGBA
+// bytes instead of 36, so everything after moves up 27 QWs (98 in total) and MSCAL
+// targets the micro-program's 8-bit color entry.
+//
+// With packed normals, QW 40 is UNPACK V4-16 (signed) and the 36 normals take 18 QWs of
+// 1.15 fixed-point i16 lanes, saving another 18 QWs; MSCAL targets a 16-bit normal entry.
+//
+// With a scene set, QW 1..124 (a "batch") repeat after the STCYCL once per 36-vertex
+// chunk of each mesh, in draw order, each with its own MVP and ending in its own
+// MSCAL/FLUSH. VIF1 stalls at every MSCAL until VU1 has kicked that batch's GIF packet.
+// The ground grid, when shown, is sent the same way ahead of everything.
+//
+// With lighting off, QW 40..76 (the normals) are left out and MSCAL targets the
+// micro-program's transform-only routine; colors then always go as f32, since the 8-bit
+// conversion routine continues into the lit program. Linear lighting likewise MSCALs its
+// own routine and sends f32 normals and colors. Debug normals go unlit too, with each
+// vertex's color replaced by its world-space normal.
+//
+// With tangents on, each batch also carries UNPACK V4-32 num=36 addr=188 and 36 tangent
+// QWs [tx,ty,tz,w] just before the MVP upload (37 QWs more); VU1 leaves them in place.
+//
+// With indexed colors, the frame's distinct colors go once, right after the STCYCL, as a
+// palette of up to 256 f32 QWs (UNPACK V4-32 to datamem[256..], 128 entries per UNPACK).
+// Each batch then sends QW 77 as UNPACK S-8 (USN) with one palette index byte per vertex,
+// 3 QWs for all 36 instead of 36 color QWs, and MSCAL targets the palette entry, which
+// looks the indices up before the main program runs.
+//
+// With interleaved attributes (f32 lit batches only), QW 3 is preceded by STCYCL(wl=1,
+// cl=3) and the normal and color UNPACKs target addr 1 and 2: skipping write lands each
+// vertex's position, normal and color back to back at datamem[3i..3i+2], the same 108
+// QWs in another order. STCYCL(wl=1, cl=1) follows the colors, 2 QWs more in all, and
+// MSCAL targets the interleaved entry, which walks them with a single pointer.
+//
+// With fog on (f32 lit batches only, taking precedence over interleaving), the GIF tag
+// sets PRIM.FGE and names XYZF2 instead of XYZ2, each batch ends its constants with
+// UNPACK V4-32 num=1 addr=224 and [0, 255, 255/(end-start), end] (2 QWs more), and MSCAL
+// targets the fog entry, which writes each vertex's fog coefficient into XYZF2.
+//
+// With face colors on, a batch whose vertices come in 6 runs of 6 sharing one color (a
+// face of two triangles each, as on the cube and ground) sends QW 77 as UNPACK V4-32
+// num=6 addr=225 and just the 6 face colors, 30 QWs fewer, and MSCAL targets the face
+// color entry, which repeats each over its face's vertices before the main program.
+// Other batches, and formats other than lit f32 without fog or interleaving, are
+// unaffected.
 
+use std::collections::HashMap;
 use std::f32::consts::PI;
+use std::rc::Rc;
 
-const PACKET_BASE: usize = 0x0010_0000;
-const PACKET_QWC:  u32   = 125;
+use crate::animation::{Animation, Keyframe};
+use crate::clipper;
+use crate::gif;
+use crate::scene::{self, Mesh, MeshVertex, Scene, SceneObject, BATCH_VERTS};
+use crate::vu1_program::{
+    KICK_ENTRY, S16_NORMAL_ENTRY, S16_NORMAL_U8_COLOR_ENTRY, U8_COLOR_ENTRY, UNLIT_ENTRY,
+    LINEAR_LIGHT_ENTRY, PALETTE_ADDR, PALETTE_ENTRY, S16_NORMAL_PALETTE_ENTRY, INTERLEAVED_ENTRY,
+    FOG_ADDR, FOG_ENTRY, FACE_COLOR_ADDR, FACE_COLOR_ENTRY,
+};
 
-// PS2 MMIO addresses for DMAC channel 1 (VIF1) — stored in fields for DMAC to read
-pub const D1_MADR: u32 = PACKET_BASE as u32;
-pub const D1_QWC:  u32 = PACKET_QWC;
+// Two packet slots in EE RAM, alternated per frame: the EE can build frame N+1 in one
+// while the DMAC is still reading frame N from the other. The slot base becomes D1_MADR.
+// A third slot after them takes out-of-band packets (`build_packet_for_frame`), so
+// building one never disturbs the frame loop's pair.
+const PACKET_BASE:      usize = 0x0010_0000;
+const PACKET_SLOT_SIZE: usize = 0x0004_0000; // 256 KB per slot
+const OOB_PACKET_BASE:  usize = PACKET_BASE + 2 * PACKET_SLOT_SIZE;
+const PACKET_QWC:       u32   = 125;
 
 // ---- Cube geometry (36 vertices: 6 faces × 2 tri × 3 verts) ----
 
-struct Vert {
-    pos:    [f32; 3],
-    normal: [f32; 3],
-    color:  [f32; 3],
+const fn v(pos: [f32; 3], normal: [f32; 3], color: [f32; 3]) -> MeshVertex {
+    MeshVertex { pos, normal, color, uv: face_uv(pos, normal) }
 }
 
-const fn v(pos: [f32; 3], normal: [f32; 3], color: [f32; 3]) -> Vert {
-    Vert { pos, normal, color }
+/// Planar UV of an axis-aligned face: [-1,1] across the face maps to [0,1], with +u and
+/// +v chosen so cross(+u, +v) points along the outward normal (e.g. +X, +Y on the +Z face).
+const fn face_uv(p: [f32; 3], n: [f32; 3]) -> [f32; 2] {
+    let [x, y, z] = p;
+    let (u, v) = if n[0] > 0.5 {
+        (-z, y)
+    } else if n[0] < -0.5 {
+        (z, y)
+    } else if n[1] > 0.5 {
+        (x, -z)
+    } else if n[1] < -0.5 {
+        (x, z)
+    } else if n[2] > 0.5 {
+        (x, y)
+    } else {
+        (-x, y)
+    };
+    [(u + 1.0) / 2.0, (v + 1.0) / 2.0]
 }
 
 // Face colors: +X=Red, -X=Cyan, +Y=Green, -Y=Magenta, +Z=Blue, -Z=Yellow
 // 2 triangles per face × 3 vertices, CCW winding
-static CUBE: [Vert; 36] = [
+static CUBE: [MeshVertex; 36] = [
     // +X face (normal [1,0,0], color red)
     v([ 1.,-1., 1.], [1.,0.,0.], [1.,0.1,0.1]),
     v([ 1., 1., 1.], [1.,0.,0.], [1.,0.1,0.1]),
@@ -92,6 +165,39 @@ static CUBE: [Vert; 36] = [
     v([ 1., 1.,-1.], [0.,0.,-1.], [1.,1.,0.1]),
 ];
 
+// ---- Ground grid: 8×6 unit cells in the y=0 plane, x -4..4, z -4.5..1.5 ----
+// Checkered so the plane reads as a grid; 48 cells × 2 tris × 3 verts = 8 full batches.
+// Placed 1.5 below the cube's centre by GROUND_Y; the near edge stays in front of the
+// default camera (z = 3), as nothing on the VU1 path clips against the near plane.
+
+const GROUND_CELLS_X: usize = 8;
+const GROUND_CELLS_Z: usize = 6;
+const GROUND_Y:       f32   = -1.5;
+const GROUND_BATCHES: usize = GROUND_CELLS_X * GROUND_CELLS_Z * 6 / BATCH_VERTS;
+
+fn ground_mesh() -> Mesh {
+    let mut verts = Vec::with_capacity(GROUND_CELLS_X * GROUND_CELLS_Z * 6);
+    for cz in 0..GROUND_CELLS_Z {
+        for cx in 0..GROUND_CELLS_X {
+            let x0 = cx as f32 - GROUND_CELLS_X as f32 / 2.0;
+            let z0 = cz as f32 - 4.5;
+            let (x1, z1) = (x0 + 1.0, z0 + 1.0);
+            let color = if (cx + cz) % 2 == 0 { [0.45; 3] } else { [0.3; 3] };
+            let up = [0.0, 1.0, 0.0];
+            // CCW seen from above
+            for [x, z] in [[x0, z0], [x0, z1], [x1, z1], [x0, z0], [x1, z1], [x1, z0]] {
+                verts.push(v([x, 0.0, z], up, color));
+            }
+        }
+    }
+    Mesh::new(verts).expect("ground is a triangle list")
+}
+
+/// The demo cube (2×2×2, one color per face) as a scene mesh.
+pub fn cube_mesh() -> Mesh {
+    Mesh::new(CUBE.to_vec()).expect("cube is a triangle list")
+}
+
 // ---- GIF tag constant (128-bit literal) ----
 // See plan §6:
 //   NLOOP=36, EOP=1, PRE=1, PRIM=0x00B (TRIANGLE|IIP), FLG=0 (PACKED), NREG=2
@@ -218,9 +324,10 @@ const fn gif_tag() -> [u32; 4] {
     // 0x0005_C000 | 0x2000_0000 = 0x2005_C000
 
     // word2,word3: hi64 — REGS field
-    // VU program stores: QW0=XYZ2 (coords), QW1=RGBAQ (color)
-    // So reg0=XYZ2(0x05), reg1=RGBAQ(0x01) to match memory order
-    let word2: u32 = 0x05 | (0x01 << 4);  // 0x0000_0015
+    // VU program stores: QW0=RGBAQ (color), QW1=XYZ2 (coords)
+    // So reg0=RGBAQ(0x01), reg1=XYZ2(0x05): the GS kicks the vertex on the XYZ2 write,
+    // so its color must already be in RGBAQ.
+    let word2: u32 = 0x01 | (0x05 << 4);  // 0x0000_0051
     let word3: u32 = 0x0000_0000;
 
     [word0, word1, word2, word3]
@@ -255,6 +362,21 @@ fn rotate_x(rad: f32) -> [[f32;4];4] {
      [0., 0.,  0., 1.]]
 }
 
+fn rotate_z(rad: f32) -> [[f32;4];4] {
+    let (s, c) = rad.sin_cos();
+    [[ c,  s, 0., 0.],
+     [-s,  c, 0., 0.],
+     [0., 0., 1., 0.],
+     [0., 0., 0., 1.]]
+}
+
+/// Model matrix of an animation pose: scale, rotate Y then X then Z, then translate.
+fn pose_matrix(pose: &Keyframe) -> [[f32;4];4] {
+    let [rx, ry, rz] = pose.rotation.map(|d| d * (PI / 180.0));
+    let rot = mat_mul(rotate_z(rz), mat_mul(rotate_x(rx), rotate_y(ry)));
+    mat_mul(translate_scale(pose.translation, 1.0), mat_mul(rot, translate_scale([0.0; 3], pose.scale)))
+}
+
 fn translate_z(tz: f32) -> [[f32;4];4] {
     [[1., 0., 0., 0.],
      [0., 1., 0., 0.],
@@ -262,148 +384,1246 @@ fn translate_z(tz: f32) -> [[f32;4];4] {
      [0., 0., tz, 1.]]
 }
 
-fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> [[f32;4];4] {
+/// Column-major model matrix: uniform `scale`, then a move to `offset`.
+pub fn translate_scale(offset: [f32; 3], scale: f32) -> [[f32;4];4] {
+    [[scale, 0., 0., 0.],
+     [0., scale, 0., 0.],
+     [0., 0., scale, 0.],
+     [offset[0], offset[1], offset[2], 1.]]
+}
+
+pub fn sub3(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn dot3(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+
+pub fn cross3(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1]*b[2] - a[2]*b[1],
+     a[2]*b[0] - a[0]*b[2],
+     a[0]*b[1] - a[1]*b[0]]
+}
+
+pub fn normalize3(v: [f32; 3]) -> Option<[f32; 3]> {
+    let len = dot3(v, v).sqrt();
+    (len > 1e-6).then(|| [v[0] / len, v[1] / len, v[2] / len])
+}
+
+/// Right-handed view matrix looking from `eye` at `target` (GL `gluLookAt`).
+/// None if eye == target or `up` is parallel to the view direction.
+fn look_at(eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> Option<[[f32;4];4]> {
+    let f = normalize3(sub3(target, eye))?;
+    let s = normalize3(cross3(f, up))?;
+    let u = cross3(s, f);
+    Some([[s[0], u[0], -f[0], 0.],
+          [s[1], u[1], -f[1], 0.],
+          [s[2], u[2], -f[2], 0.],
+          [-dot3(s, eye), -dot3(u, eye), dot3(f, eye), 1.]])
+}
+
+/// Clip-space depth convention of the projection: where the near and far planes land
+/// in NDC z after the divide.
+///
+/// Nothing downstream tests depth yet (the GS rasterizer has no Z buffer and XYZ2 only
+/// carries x/y), so the choice is invisible on screen today. A future depth test should
+/// read NDC z in the configured range. The frustum clip follows it: its near plane is
+/// z = -w or z = 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthRange {
+    /// OpenGL: near → -1, far → +1.
+    #[default]
+    NegOneToOne,
+    /// WebGPU / D3D: near → 0, far → 1.
+    ZeroToOne,
+}
+
+fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32, depth: DepthRange) -> [[f32;4];4] {
     let f = 1.0 / (fov_y / 2.0).tan();
     let range = near - far;
-    [[f / aspect, 0., 0.,                      0.],
-     [0.,         f,  0.,                      0.],
-     [0.,         0., (far + near) / range,   -1.],
-     [0.,         0., 2.0*far*near / range,    0.]]
+    let (m22, m32) = match depth {
+        DepthRange::NegOneToOne => ((far + near) / range, 2.0*far*near / range),
+        DepthRange::ZeroToOne   => (far / range,          far*near / range),
+    };
+    [[f / aspect, 0., 0.,   0.],
+     [0.,         f,  0.,   0.],
+     [0.,         0., m22, -1.],
+     [0.,         0., m32,  0.]]
+}
+
+// ---- Packet writers ----
+
+// Helper: write a u32 at a byte offset
+fn w32(ram: &mut [u8], off: usize, val: u32) {
+    let b = val.to_le_bytes();
+    ram[off..off+4].copy_from_slice(&b);
+}
+
+fn write_qw(ram: &mut [u8], base: usize, qw: usize, w0: u32, w1: u32, w2: u32, w3: u32) {
+    let off = base + qw * 16;
+    w32(ram, off,    w0);
+    w32(ram, off+4,  w1);
+    w32(ram, off+8,  w2);
+    w32(ram, off+12, w3);
+}
+
+fn write_f32_qw(ram: &mut [u8], base: usize, qw: usize, x: f32, y: f32, z: f32, w: f32) {
+    write_qw(ram, base, qw,
+        f32::to_bits(x), f32::to_bits(y),
+        f32::to_bits(z), f32::to_bits(w));
+}
+
+// VIF tag helpers:
+// STCYCL(wl=1, cl=1): cmd=0x01, data = (wl<<8)|cl = 0x0101
+// UNPACK V4-32: cmd=0x6C, data = (num<<16)|addr
+// MSCAL(addr=0): cmd=0x14, data=0
+// FLUSH: cmd=0x11, data=0
+fn vif_tag(cmd: u8, data: u32) -> u32 {
+    ((cmd as u32) << 24) | (data & 0x00FF_FFFF)
+}
+
+/// A VIF tag in words, e.g. "UNPACK V4-32 num=36 addr=0", and how many data QWs follow it.
+fn describe_vif_tag(tag: u32) -> (String, usize) {
+    let cmd = tag >> 24;
+    let desc = match cmd {
+        0x00 => "NOP".to_string(),
+        0x01 => format!("STCYCL wl={} cl={}", (tag >> 8) & 0xFF, tag & 0xFF),
+        0x02 => format!("OFFSET {}", tag & 0x3FF),
+        0x03 => format!("BASE {}", tag & 0x3FF),
+        0x04 => format!("ITOP {}", tag & 0x3FF),
+        0x11 => "FLUSH".to_string(),
+        0x14 => format!("MSCAL execaddr={}", tag & 0xFFFF),
+        0x17 => "MSCNT".to_string(),
+        0x60..=0x62 | 0x6C..=0x6E => {
+            // Element size as VIF1 unpacks it: VN lanes of VL bytes
+            let (lanes, lane_bytes) = (if cmd & 0xC == 0 { 1 } else { 4 }, 4 >> (cmd & 0x3));
+            let num = ((tag >> 16) & 0xFF) as usize;
+            let desc = format!(
+                "UNPACK {}-{} num={num} addr={}{}{}",
+                if lanes == 1 { "S" } else { "V4" }, 32 >> (cmd & 0x3), tag & 0x3FF,
+                if (tag >> 14) & 1 != 0 { " USN" } else { "" },
+                if (tag >> 15) & 1 != 0 { " FLG" } else { "" },
+            );
+            return (desc, (num * lanes * lane_bytes).div_ceil(16));
+        }
+        _ => format!("unknown VIF command 0x{cmd:02X}"),
+    };
+    (desc, 0)
+}
+
+/// A PACKED GIF tag in words, e.g. "GIF tag NLOOP=36 EOP PRE PRIM=0x00B NREG=2".
+fn describe_gif_tag(w: [u32; 4]) -> String {
+    format!(
+        "GIF tag NLOOP={}{}{} PRIM=0x{:03X} NREG={} REGS=0x{:X}",
+        w[0] & 0x7FFF,
+        if (w[0] >> 15) & 1 != 0 { " EOP" } else { "" },
+        if (w[1] >> 14) & 1 != 0 { " PRE" } else { "" },
+        (w[1] >> 15) & 0x7FF,
+        match w[1] >> 28 { 0 => 16, n => n },
+        (w[3] as u64) << 32 | w[2] as u64,
+    )
+}
+
+/// PACKED-mode GIF tag words: EOP=1, PRE=1, with `nreg` register descriptors in `regs`
+/// (same bit layout as `gif_tag`).
+fn gif_tag_packed(nloop: u32, prim: u32, nreg: u32, regs: u32) -> [u32; 4] {
+    [nloop | (1 << 15), (1 << 14) | (prim << 15) | (nreg << 28), regs, 0]
+}
+
+/// Render-target resolution in pixels: the one source of both the projection's aspect
+/// ratio and the VU1 viewport scale (QW 122), so a resolution change can't stretch the
+/// image by updating only one of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub width:  u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    /// Half extents [w/2, h/2]: the NDC → pixel scale VU1 applies.
+    fn scale(self) -> [f32; 2] {
+        [self.width as f32 / 2.0, self.height as f32 / 2.0]
+    }
+
+    /// Width over height, for the perspective projection.
+    fn aspect(self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+/// A flat-colored GS SPRITE the EE can send instead of the cube.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    /// Inclusive pixel corners [x0, y0, x1, y1]
+    pub rect:  [i32; 4],
+    /// RGBA 0–255
+    pub color: [u8; 4],
+}
+
+/// Vertex color quantised to the 8 bits per channel the palette stores.
+fn color8(c: [f32; 3]) -> [u8; 3] {
+    c.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// A frame's vertex colors for indexed-color packets: the first 256 distinct 8-bit colors
+/// get an entry each, and any color beyond those maps to its nearest entry.
+struct Palette {
+    colors: Vec<[u8; 3]>,
+    index:  HashMap<[u8; 3], u8>,
+}
+
+impl Palette {
+    fn new(colors: impl IntoIterator<Item = [f32; 3]>) -> Self {
+        let mut palette = Palette { colors: Vec::new(), index: HashMap::new() };
+        for c in colors.into_iter().map(color8) {
+            if palette.colors.len() < 256 && !palette.index.contains_key(&c) {
+                palette.index.insert(c, palette.colors.len() as u8);
+                palette.colors.push(c);
+            }
+        }
+        palette
+    }
+
+    /// Palette index of `color`: its own entry, or the nearest one (squared RGB distance).
+    fn lookup(&self, color: [f32; 3]) -> u8 {
+        let c = color8(color);
+        if let Some(&i) = self.index.get(&c) {
+            return i;
+        }
+        let dist = |e: &[u8; 3]| -> i32 {
+            (0..3).map(|k| (e[k] as i32 - c[k] as i32).pow(2)).sum()
+        };
+        (0..self.colors.len()).min_by_key(|&i| dist(&self.colors[i])).unwrap_or(0) as u8
+    }
+
+    /// QWs the palette upload takes: one UNPACK per 128 entries plus the entries.
+    fn qwc(&self) -> usize {
+        self.colors.len().div_ceil(128) + self.colors.len()
+    }
+}
+
+/// Attribute encodings and viewport shared by every batch of a packet.
+#[derive(Clone, Copy)]
+struct BatchFormat<'a> {
+    lit:            bool,
+    linear:         bool,
+    tangents:       bool,
+    packed_normals: bool,
+    packed_colors:  bool,
+    palette:        Option<&'a Palette>, // colors are indices into this (uploaded once)
+    interleaved:    bool,                // skipping-write UNPACK into per-vertex order
+    fog:            Option<(f32, f32)>,  // (start, end) view-space distances
+    face_colors:    bool,                // one color per 6 vertices where they share one
+    viewport:       [f32; 2],
+}
+
+impl BatchFormat<'_> {
+    /// QWs one batch takes: QW 1..124 of the layout above, less the packed or per-face
+    /// color savings and the normals of an unlit batch.
+    fn qwc(self, face_colored: bool) -> usize {
+        PACKET_QWC as usize - 1
+            - if self.palette.is_some() { 33 } else if self.packed_colors { 27 } else { 0 }
+            - if face_colored { 30 } else { 0 }
+            - match (self.lit, self.packed_normals) {
+                (false, _)    => 37,
+                (true, true)  => 18,
+                (true, false) => 0,
+            }
+            + if self.tangents { TANGENT_QWC } else { 0 }
+            + if self.interleaved { INTERLEAVE_QWC } else { 0 }
+            + if self.fog.is_some() { FOG_QWC } else { 0 }
+    }
+}
+
+/// QWs the optional tangent upload adds to a batch: UNPACK + 36 tangents.
+const TANGENT_QWC: usize = 37;
+
+/// QWs interleaved attributes add to a batch: the two STCYCLs around the attributes.
+const INTERLEAVE_QWC: usize = 2;
+
+/// QWs fog adds to a batch: UNPACK + the fog constants.
+const FOG_QWC: usize = 2;
+
+/// Most batches a scene may take: as many worst-case (unpacked, interleaved, with
+/// tangents) batches as fit in a packet slot after the STCYCL, less those reserved for
+/// the ground. An indexed-color palette fits in the 33 QWs each indexed batch saves; a
+/// fogged batch is never interleaved and adds no more.
+pub const MAX_SCENE_BATCHES: usize = (PACKET_SLOT_SIZE / 16 - 1)
+    / (PACKET_QWC as usize - 1 + TANGENT_QWC + INTERLEAVE_QWC)
+    - GROUND_BATCHES;
+
+/// Highest `set_tessellation_level`: the cube at this level (9216 vertices) already
+/// takes more batches than a packet slot holds, so it is sent a level lower.
+pub const MAX_TESSELLATION_LEVEL: u32 = 4;
+
+/// One mesh's share of a frame: its vertices, model matrix and color tint.
+type Draw<'a> = (&'a [MeshVertex], [[f32; 4]; 4], [f32; 3]);
+
+/// `vertices` recolored by their world-space normal (the model matrix's rotation applied),
+/// mapped from [-1, 1] to [0, 1] per axis: +X shows red-ish [1, 0.5, 0.5].
+fn normal_colors(vertices: &[MeshVertex], model: [[f32; 4]; 4]) -> Vec<MeshVertex> {
+    vertices
+        .iter()
+        .map(|v| {
+            let n = [0, 1, 2].map(|r| (0..3).map(|c| model[c][r] * v.normal[c]).sum::<f32>());
+            let n = normalize3(n).unwrap_or([0.0; 3]);
+            MeshVertex { color: n.map(|c| c * 0.5 + 0.5), ..*v }
+        })
+        .collect()
+}
+
+/// The weighted sum of a triangle's three corner values.
+fn weigh<const N: usize>(w: [f32; 3], corners: [[f32; N]; 3]) -> [f32; N] {
+    std::array::from_fn(|i| (0..3).map(|c| w[c] * corners[c][i]).sum())
+}
+
+/// `vertices` (a triangle list) clipped to the view frustum of `mvp`, or None if every
+/// triangle is already inside. A clipped triangle's new vertices interpolate all of its
+/// attributes, position included: the MVP is linear, so the model-space point at the
+/// clipper's weights transforms to exactly the clipped clip-space one.
+fn clip_to_frustum(
+    vertices: &[MeshVertex],
+    mvp: [[f32; 4]; 4],
+    depth: DepthRange,
+) -> Option<Vec<MeshVertex>> {
+    let near_at_zero = depth == DepthRange::ZeroToOne;
+    let clip_pos = |v: &MeshVertex| -> [f32; 4] {
+        std::array::from_fn(|r| (0..3).map(|c| mvp[c][r] * v.pos[c]).sum::<f32>() + mvp[3][r])
+    };
+    let clip: Vec<[f32; 4]> = vertices.iter().map(clip_pos).collect();
+    if clip.iter().all(|p| clipper::inside_frustum(p, near_at_zero)) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(vertices.len());
+    for (tri, pos) in vertices.chunks_exact(3).zip(clip.chunks_exact(3)) {
+        for part in clipper::clip_triangle(&[pos[0], pos[1], pos[2]], near_at_zero) {
+            out.extend(part.iter().map(|cv| {
+                let w = cv.weights;
+                let normal = weigh(w, [0, 1, 2].map(|c| tri[c].normal));
+                MeshVertex {
+                    pos:    weigh(w, [0, 1, 2].map(|c| tri[c].pos)),
+                    normal: normalize3(normal).unwrap_or(normal),
+                    color:  weigh(w, [0, 1, 2].map(|c| tri[c].color)),
+                    uv:     weigh(w, [0, 1, 2].map(|c| tri[c].uv)),
+                }
+            }));
+        }
+    }
+    Some(out)
+}
+
+/// Write the palette upload (see the layout above) at `qw` and return the QW after it.
+fn write_palette(ram: &mut [u8], base: usize, mut qw: usize, palette: &Palette) -> usize {
+    let start = qw;
+    for (i, chunk) in palette.colors.chunks(128).enumerate() {
+        let addr = PALETTE_ADDR as u32 + (i * 128) as u32;
+        write_qw(ram, base, qw, vif_tag(0x6C, ((chunk.len() as u32) << 16) | addr), 0, 0, 0);
+        qw += 1;
+        for c in chunk {
+            let [r, g, b] = c.map(|v| v as f32 / 255.0);
+            write_f32_qw(ram, base, qw, r, g, b, 1.0);
+            qw += 1;
+        }
+    }
+    debug_assert_eq!(qw - start, palette.qwc());
+    qw
+}
+
+/// Write one VU1 batch (QW 1..124 of the layout above) at `qw` and return the QW after
+/// it. `verts` holds up to 36 vertices; a short chunk is padded by repeating its first
+/// vertex, giving zero-area triangles. Colors are multiplied by `tint`.
+fn write_batch(
+    ram:    &mut [u8],
+    base:   usize,
+    mut qw: usize,
+    verts:  &[MeshVertex],
+    mvp:    [[f32; 4]; 4],
+    tint:   [f32; 3],
+    format: BatchFormat,
+) -> usize {
+    let start = qw;
+    let verts: Vec<MeshVertex> = (0..BATCH_VERTS)
+        .map(|i| {
+            let mut v = *verts.get(i).unwrap_or(&verts[0]);
+            v.color = [v.color[0] * tint[0], v.color[1] * tint[1], v.color[2] * tint[2]];
+            v
+        })
+        .collect();
+    let face_colored = format.face_colors
+        && verts.chunks(6).all(|face| face.iter().all(|v| v.color == face[0].color));
+
+    // QW 1: UNPACK V4-32 num=1 addr=108
+    write_qw(ram, base, qw, vif_tag(0x6C, (1 << 16) | 108), 0, 0, 0); qw += 1;
+
+    // QW 2: GIF tag; with fog, PRIM.FGE (bit 5) set and XYZF2 (0x04) in place of XYZ2
+    let mut gt = gif_tag();
+    if format.fog.is_some() {
+        gt[1] |= 1 << (15 + 5);
+        gt[2] = 0x01 | (0x04 << 4);
+    }
+    write_qw(ram, base, qw, gt[0], gt[1], gt[2], gt[3]); qw += 1;
+
+    // Interleaved: write one QW per three, so the three attribute UNPACKs interleave
+    if format.interleaved {
+        write_qw(ram, base, qw, vif_tag(0x01, 0x0103), 0, 0, 0); qw += 1;
+    }
+
+    // QW 3: UNPACK positions num=36 addr=0
+    write_qw(ram, base, qw, vif_tag(0x6C, 36 << 16), 0, 0, 0); qw += 1;
+
+    // QW 4..39: 36 position QWs
+    for v in &verts {
+        write_f32_qw(ram, base, qw, v.pos[0], v.pos[1], v.pos[2], 1.0);
+        qw += 1;
+    }
+
+    if !format.lit {
+        // No normals: the transform-only routine doesn't read datamem[36..71]
+    } else if format.packed_normals {
+        // QW 40: UNPACK V4-16 normals num=36 addr=36, signed (USN=0)
+        write_qw(ram, base, qw, vif_tag(0x6D, (36 << 16) | 36), 0, 0, 0); qw += 1;
+
+        // QW 41..58: 36 normals as [nx,ny,nz,0] i16 in 1.15, two per QW
+        for pair in verts.chunks(2) {
+            let mut words = [0u32; 4];
+            for (half, v) in words.chunks_exact_mut(2).zip(pair) {
+                let [x, y, z] = v.normal.map(|n| (n * 32767.0).round() as i16 as u16 as u32);
+                half[0] = x | (y << 16);
+                half[1] = z;
+            }
+            write_qw(ram, base, qw, words[0], words[1], words[2], words[3]);
+            qw += 1;
+        }
+    } else {
+        // QW 40: UNPACK normals num=36 addr=36 (1 interleaved)
+        let addr = if format.interleaved { 1 } else { 36 };
+        write_qw(ram, base, qw, vif_tag(0x6C, (36 << 16) | addr), 0, 0, 0); qw += 1;
+
+        // QW 41..76: 36 normal QWs
+        for v in &verts {
+            write_f32_qw(ram, base, qw, v.normal[0], v.normal[1], v.normal[2], 0.0);
+            qw += 1;
+        }
+    }
+
+    if let Some(palette) = format.palette {
+        // QW 77: UNPACK S-8 palette indices num=36 addr=72, USN (bit 14) = zero-extend
+        write_qw(ram, base, qw, vif_tag(0x62, (36 << 16) | (1 << 14) | 72), 0, 0, 0); qw += 1;
+
+        // QW 78..80: 36 index bytes, sixteen per QW
+        for chunk in verts.chunks(16) {
+            let mut bytes = [0u8; 16];
+            for (b, v) in bytes.iter_mut().zip(chunk) {
+                *b = palette.lookup(v.color);
+            }
+            let w = |i: usize| u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap());
+            write_qw(ram, base, qw, w(0), w(1), w(2), w(3));
+            qw += 1;
+        }
+    } else if format.packed_colors {
+        // QW 77: UNPACK V4-8 colors num=36 addr=72, USN (bit 14) = zero-extend
+        write_qw(ram, base, qw, vif_tag(0x6E, (36 << 16) | (1 << 14) | 72), 0, 0, 0); qw += 1;
+
+        // QW 78..86: 36 RGBA8 colors, four per QW
+        for quad in verts.chunks(4) {
+            let mut words = [0u32; 4];
+            for (word, v) in words.iter_mut().zip(quad) {
+                let [r, g, b] = v.color.map(|c| (c * 255.0).round() as u32);
+                *word = r | (g << 8) | (b << 16) | (255 << 24);
+            }
+            write_qw(ram, base, qw, words[0], words[1], words[2], words[3]);
+            qw += 1;
+        }
+    } else if face_colored {
+        // QW 77: UNPACK face colors num=6 addr=225, then one color QW per 6 vertices
+        let addr = FACE_COLOR_ADDR as u32;
+        write_qw(ram, base, qw, vif_tag(0x6C, (6 << 16) | addr), 0, 0, 0); qw += 1;
+        for face in verts.chunks(6) {
+            let [r, g, b] = face[0].color;
+            write_f32_qw(ram, base, qw, r, g, b, 1.0);
+            qw += 1;
+        }
+    } else {
+        // QW 77: UNPACK colors num=36 addr=72 (2 interleaved)
+        let addr = if format.interleaved { 2 } else { 72 };
+        write_qw(ram, base, qw, vif_tag(0x6C, (36 << 16) | addr), 0, 0, 0); qw += 1;
+
+        // QW 78..113: 36 color QWs
+        for v in &verts {
+            write_f32_qw(ram, base, qw, v.color[0], v.color[1], v.color[2], 1.0);
+            qw += 1;
+        }
+    }
+
+    // Back to contiguous writes for everything after the attributes
+    if format.interleaved {
+        write_qw(ram, base, qw, vif_tag(0x01, 0x0101), 0, 0, 0); qw += 1;
+    }
+
+    if format.tangents {
+        // UNPACK tangents num=36 addr=188, then 36 [tx,ty,tz,w] QWs. Nothing in the
+        // micro-program reads them yet; they wait in datamem for tangent-space lighting.
+        write_qw(ram, base, qw, vif_tag(0x6C, (36 << 16) | 188), 0, 0, 0); qw += 1;
+        for t in scene::tangents(&verts) {
+            write_f32_qw(ram, base, qw, t[0], t[1], t[2], t[3]);
+            qw += 1;
+        }
+    }
+
+    // QW 114: UNPACK MVP num=4 addr=182
+    write_qw(ram, base, qw, vif_tag(0x6C, (4 << 16) | 182), 0, 0, 0); qw += 1;
+
+    // QW 115..118: 4 MVP column QWs (column-major: each column is [r0,r1,r2,r3])
+    for col in mvp {
+        write_f32_qw(ram, base, qw, col[0], col[1], col[2], col[3]);
+        qw += 1;
+    }
+
+    // QW 119: UNPACK light num=1 addr=186
+    write_qw(ram, base, qw, vif_tag(0x6C, (1 << 16) | 186), 0, 0, 0); qw += 1;
+
+    // QW 120: light direction + ambient [lx, ly, lz, ambient]
+    write_f32_qw(ram, base, qw, 0.577, 0.577, 0.577, 0.2); qw += 1;
+
+    // QW 121: UNPACK viewport num=1 addr=187
+    write_qw(ram, base, qw, vif_tag(0x6C, (1 << 16) | 187), 0, 0, 0); qw += 1;
+
+    // QW 122: viewport scale [w/2, h/2, 0.0, 1/255] — [320, 224] at the native 640×448
+    // (pixel-space half-extents; FTOI4 in VU1 will multiply by 16 → GS 12.4 format).
+    // w is the 8-bit color scale used by the packed-color entry point.
+    let [vx, vy] = format.viewport;
+    write_f32_qw(ram, base, qw, vx, vy, 0.0, 1.0 / 255.0); qw += 1;
+
+    if let Some((start, end)) = format.fog {
+        // UNPACK fog constants num=1 addr=224: [0, 255, 255/(end-start), end]
+        write_qw(ram, base, qw, vif_tag(0x6C, (1 << 16) | FOG_ADDR as u32), 0, 0, 0); qw += 1;
+        write_f32_qw(ram, base, qw, 0.0, 255.0, 255.0 / (end - start), end); qw += 1;
+    }
+
+    // QW 123: MSCAL execaddr=0 (or the entry converting the packed attributes first, or
+    // the transform-only routine)
+    let indexed = format.palette.is_some();
+    let entry = match (format.lit, format.packed_normals, format.packed_colors || indexed) {
+        (false, _,     _)                     => UNLIT_ENTRY,
+        (true,  _,     _) if format.linear    => LINEAR_LIGHT_ENTRY,
+        (true,  false, false) if face_colored => FACE_COLOR_ENTRY,
+        (true,  false, false) if format.fog.is_some() => FOG_ENTRY,
+        (true,  false, false) if format.interleaved => INTERLEAVED_ENTRY,
+        (true,  false, false)                 => 0,
+        (true,  false, true) if indexed       => PALETTE_ENTRY,
+        (true,  false, true)                  => U8_COLOR_ENTRY,
+        (true,  true,  false)                 => S16_NORMAL_ENTRY,
+        (true,  true,  true) if indexed       => S16_NORMAL_PALETTE_ENTRY,
+        (true,  true,  true)                  => S16_NORMAL_U8_COLOR_ENTRY,
+    } as u32;
+    write_qw(ram, base, qw, vif_tag(0x14, entry), 0, 0, 0); qw += 1;
+
+    // QW 124: FLUSH
+    write_qw(ram, base, qw, vif_tag(0x11, 0), 0, 0, 0); qw += 1;
+
+    debug_assert_eq!(qw - start, format.qwc(face_colored));
+    qw
 }
 
 // ---- EmotionEngine ----
 
 pub struct EmotionEngine {
     pub ee_ram: Box<[u8; 2 * 1024 * 1024]>,
-    frame:      u64,
+    frame:      u64,        // index of the next packet build_packet emits
+    // Animation phase (in nominal frames) is phase_base + (frame - frame_base) * time_scale;
+    // set_time_scale rebases it so changing speed doesn't jump the pose.
+    phase_base: f64,
+    frame_base: u64,
+    time_scale: f32,
+    packed_colors: bool,    // upload colors as V4-8 bytes instead of V4-32 floats
+    packed_normals: bool,   // upload normals as V4-16 1.15 fixed point instead of floats
+    indexed_colors: bool,   // upload colors as S-8 indices into a per-frame palette
+    lit:        bool,       // false = transform-only micro-program routine, no normals
+    linear_lighting: bool,  // shade in linear space (gamma 2.0) instead of sRGB
+    interleaved: bool,      // unpack attributes per vertex (STCYCL skipping write)
+    fog:        Option<(f32, f32)>, // (start, end) view-space fog distances; None = off
+    debug_normals: bool,    // color vertices by world-space normal, unlit
+    face_colors: bool,      // send one color per 6-vertex face where the face shares one
+    tangents:   bool,       // upload per-vertex tangents to datamem[188..223]
+    slot:       usize,      // packet slot the next packet is written to (0 or 1)
+    viewport:   Viewport,   // render target: projection aspect and QW 122 scale
+    view:       [[f32;4];4],
+    depth_range: DepthRange,
+    sprite:     Option<Sprite>,
+    scene:      Scene,      // drawn in place of the spinning cube when non-empty
+    auto_winding: bool,     // re-wind added scene meshes to face outward (CCW)
+    bake_ao:    bool,       // darken added scene meshes by baked ambient occlusion
+    tessellation: u32,      // times each cube / scene triangle is split in 4 per frame
+    last_packet: (usize, usize), // (EE RAM base, QWC) of the packet last built or loaded
+    animation:  Option<Animation>, // cube pose over time; None = the built-in spin
+    ground:     Option<Mesh>,   // drawn under the cube / scene when set
 }
 
 impl EmotionEngine {
     pub fn new() -> Self {
         EmotionEngine {
-            ee_ram: Box::new([0u8; 2 * 1024 * 1024]),
-            frame:  0,
+            // Through a Vec: Box::new of the array would build its 2 MB on the stack first
+            ee_ram:     vec![0u8; 2 * 1024 * 1024].into_boxed_slice().try_into().unwrap(),
+            frame:      0,
+            phase_base: 0.0,
+            frame_base: 0,
+            time_scale: 1.0,
+            packed_colors: false,
+            packed_normals: false,
+            indexed_colors: false,
+            lit:        true,
+            linear_lighting: false,
+            interleaved: false,
+            fog:        None,
+            debug_normals: false,
+            face_colors: false,
+            tangents:   false,
+            slot:       0,
+            viewport:   Viewport { width: 640, height: 448 },
+            // Camera 3 units back on +Z — equal to look_at([0,0,3], [0,0,0], [0,1,0])
+            view:       translate_z(-3.0),
+            depth_range: DepthRange::NegOneToOne,
+            sprite:     None,
+            scene:      Scene::new(),
+            auto_winding: false,
+            bake_ao:    false,
+            tessellation: 0,
+            last_packet: (PACKET_BASE, 0),
+            animation:  None,
+            ground:     None,
         }
     }
 
-    /// Build the VIF1 DMA packet in EE RAM and return (madr, qwc) for DMAC kick.
+    /// Set the render-target resolution (each side at least 1): the VU1 viewport
+    /// transform maps NDC onto it and the projection takes its aspect ratio.
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.viewport = Viewport { width: width.max(1), height: height.max(1) };
+    }
+
+    /// Place the camera at `eye` looking at `target`; the cube stays at the origin.
+    pub fn set_view(&mut self, eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> Result<(), String> {
+        self.view = look_at(eye, target, up)
+            .ok_or("degenerate view: eye equals target or up is parallel to the view direction")?;
+        Ok(())
+    }
+
+    /// Send `sprite` (a GS SPRITE primitive) in place of the cube, or the cube again
+    /// for None.
+    pub fn set_sprite(&mut self, sprite: Option<Sprite>) {
+        self.sprite = sprite;
+    }
+
+    /// Add an object to the scene, which replaces the spinning cube once non-empty, and
+    /// return its index. Fails if the scene would no longer fit in one packet slot.
+    pub fn add_scene_object(&mut self, mut object: SceneObject) -> Result<usize, String> {
+        let batches = self.scene.batch_count() + object.mesh.batch_count();
+        if batches > MAX_SCENE_BATCHES {
+            return Err(format!(
+                "scene of {batches} batches exceeds the {MAX_SCENE_BATCHES} that fit in a packet",
+            ));
+        }
+        if self.auto_winding {
+            Rc::make_mut(&mut object.mesh).auto_wind();
+        }
+        // After re-winding: the AO pass takes its normals from the winding
+        if self.bake_ao {
+            Rc::make_mut(&mut object.mesh).bake_ao();
+        }
+        Ok(self.scene.add(object))
+    }
+
+    /// Fix up inconsistently wound meshes as they are added to the scene: flip every
+    /// triangle wound CW as seen from outside so front faces survive back-face culling.
+    pub fn set_auto_winding(&mut self, enabled: bool) {
+        self.auto_winding = enabled;
+    }
+
+    /// Bake a per-vertex ambient-occlusion term into meshes as they are added to the scene,
+    /// so crevices of imported models stay darker.
+    pub fn set_bake_ao(&mut self, enabled: bool) {
+        self.bake_ao = enabled;
+    }
+
+    /// Draw a checkered ground plane under the cube (or scene) as a reference surface.
+    pub fn set_show_ground(&mut self, enabled: bool) {
+        self.ground = enabled.then(ground_mesh);
+    }
+
+    /// Remove every scene object, bringing the spinning cube back.
+    pub fn clear_scene(&mut self) {
+        self.scene.clear();
+    }
+
+    /// Choose the NDC depth convention of the projection matrix.
+    pub fn set_depth_range(&mut self, depth_range: DepthRange) {
+        self.depth_range = depth_range;
+    }
+
+    /// Scale the per-frame rotation increment: 0 freezes the cube, 0.5 is half speed,
+    /// 2.0 doubles the spin. Takes effect from the next packet without jumping the pose.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.phase_base = self.anim_phase(self.frame);
+        self.frame_base = self.frame;
+        self.time_scale = scale;
+    }
+
+    /// Drive the cube's model transform from keyframes (sampled at the animation phase,
+    /// so `set_time_scale` still applies) instead of the built-in spin; None restores it.
+    pub fn set_animation(&mut self, animation: Option<Animation>) {
+        self.animation = animation;
+    }
+
+    /// Animation phase, in nominal (1× speed) frames, of packet `frame`.
+    fn anim_phase(&self, frame: u64) -> f64 {
+        self.phase_base + (frame as f64 - self.frame_base as f64) * self.time_scale as f64
+    }
+
+    /// Upload vertex colors as four u8s (V4-8 UNPACK, unsigned) instead of four f32s,
+    /// quartering the color DMA; VU1 converts them back to floats.
+    pub fn set_packed_colors(&mut self, enabled: bool) {
+        self.packed_colors = enabled;
+    }
+
+    /// Upload normals as signed 16-bit 1.15 fixed point (V4-16 UNPACK), halving the
+    /// normal DMA; VU1 converts them back to floats before lighting.
+    pub fn set_packed_normals(&mut self, enabled: bool) {
+        self.packed_normals = enabled;
+    }
+
+    /// Upload vertex colors as one 8-bit palette index each (S-8 UNPACK), with the frame's
+    /// distinct colors sent once as a palette that VU1 resolves the indices against. Beyond
+    /// 256 distinct colors, the rest take the nearest palette entry. Overrides packed colors.
+    pub fn set_indexed_colors(&mut self, enabled: bool) {
+        self.indexed_colors = enabled;
+    }
+
+    /// Turn vertex lighting off to draw flat vertex colors through the micro-program's
+    /// transform-only routine, which also drops the normals from the packet.
+    pub fn set_lighting(&mut self, enabled: bool) {
+        self.lit = enabled;
+    }
+
+    /// Apply the light in linear space rather than straight to the sRGB vertex colors,
+    /// for a physically plausible falloff: VU1 scales colors by sqrt(intensity) (exact for
+    /// gamma 2.0), so partly lit faces come out brighter than with the plain multiply.
+    pub fn set_linear_lighting(&mut self, enabled: bool) {
+        self.linear_lighting = enabled;
+    }
+
+    /// Send one color per face of 6 vertices (two triangles) instead of one per vertex,
+    /// for batches whose faces are each a single color, like the cube's: VU1 repeats the
+    /// face colors over the vertices, and those batches carry a sixth of the color data.
+    /// Applies to lit f32 batches without fog or interleaving.
+    pub fn set_face_colors(&mut self, enabled: bool) {
+        self.face_colors = enabled;
+    }
+
+    /// Debug shading: color every vertex by its world-space normal (`n * 0.5 + 0.5`)
+    /// instead of its lit base color, to check the normals of imported meshes. The EE
+    /// writes the normal colors and MSCALs the transform-only routine, so lighting,
+    /// tints and packed or indexed colors don't apply meanwhile.
+    pub fn set_debug_normals(&mut self, enabled: bool) {
+        self.debug_normals = enabled;
+    }
+
+    /// Unpack each batch's positions, normals and colors interleaved per vertex in VU data
+    /// memory, using STCYCL skipping write, for a micro-program routine that reads them
+    /// through one pointer. Applies to lit f32 batches; packed, indexed, unlit and linear
+    /// formats keep the block layout.
+    pub fn set_interleaved_attributes(&mut self, enabled: bool) {
+        self.interleaved = enabled;
+    }
+
+    /// Fade vertices into the fog color between view-space distances `start` (no fog) and
+    /// `end` (all fog), or turn fog off with None. VU1 computes each vertex's coefficient
+    /// from its clip-space w and sends it in XYZF2; lit f32 batches only, and it takes
+    /// precedence over interleaved attributes.
+    pub fn set_fog(&mut self, range: Option<(f32, f32)>) -> Result<(), String> {
+        if let Some((start, end)) = range {
+            if !(start.is_finite() && end.is_finite() && start < end) {
+                return Err(format!("fog needs finite start < end, got {start}..{end}"));
+            }
+        }
+        self.fog = range;
+        Ok(())
+    }
+
+    /// Also upload per-vertex tangents (computed from positions and UVs) with each batch,
+    /// ready for a future tangent-space lighting step in the micro-program.
+    pub fn set_tangents(&mut self, enabled: bool) {
+        self.tangents = enabled;
+    }
+
+    /// Split every triangle of the cube or scene into 4 at its edge midpoints `level` times
+    /// (clamped to MAX_TESSELLATION_LEVEL) before upload, for smoother lighting and more
+    /// rasterizer load. The ground is left alone. A frame that would no longer fit in a
+    /// packet slot is sent at the highest level that does.
+    pub fn set_tessellation_level(&mut self, level: u32) {
+        self.tessellation = level.min(MAX_TESSELLATION_LEVEL);
+    }
+
+    /// Annotated hex dump of the packet last built or loaded, one line per QW: index, byte
+    /// offset, the four little-endian words (word 0 first) and what VIF1 makes of them.
+    /// Tags are decoded; UNPACK data lines name their destination address, and a QW
+    /// landing at the GIF tag address (108) is decoded as a GIF tag.
+    pub fn hexdump_packet(&self) -> String {
+        let (base, qwc) = self.last_packet;
+        let mut out = String::new();
+        let mut unpack: Option<(u32, usize, usize)> = None; // (addr, QWs left, total)
+        for qw in 0..qwc {
+            let off = base + qw * 16;
+            let w: [u32; 4] = std::array::from_fn(|i| {
+                u32::from_le_bytes(self.ee_ram[off + 4 * i..off + 4 * i + 4].try_into().unwrap())
+            });
+            let note = match unpack {
+                Some((addr, left, total)) => {
+                    let i = total - left;
+                    unpack = (left > 1).then_some((addr, left - 1, total));
+                    if addr == 108 && i == 0 {
+                        describe_gif_tag(w)
+                    } else {
+                        format!("  data {}/{total} (datamem {addr}..)", i + 1)
+                    }
+                }
+                None => {
+                    let (desc, data) = describe_vif_tag(w[0]);
+                    if data > 0 {
+                        unpack = Some((w[0] & 0x3FF, data, data));
+                    }
+                    desc
+                }
+            };
+            out += &format!(
+                "QW {qw:4} +0x{:05X}  {:08X} {:08X} {:08X} {:08X}  {note}\n",
+                qw * 16, w[0], w[1], w[2], w[3],
+            );
+        }
+        out
+    }
+
+    /// Index of the frame the next `build_packet` emits.
+    pub fn next_frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Claim the next packet slot and return its EE RAM base (the DMAC MADR).
+    fn next_slot(&mut self) -> usize {
+        let base = PACKET_BASE + self.slot * PACKET_SLOT_SIZE;
+        self.slot ^= 1;
+        base
+    }
+
+    /// Copy a caller-supplied VIF1 packet into the next packet slot, in place of a built
+    /// one, and return (madr, qwc) for DMAC kick.
+    pub fn load_packet(&mut self, bytes: &[u8]) -> Result<(u32, u32), String> {
+        if !bytes.len().is_multiple_of(16) {
+            return Err(format!("VIF packet length {} is not a multiple of 16 bytes", bytes.len()));
+        }
+        if bytes.len() > PACKET_SLOT_SIZE {
+            return Err(format!(
+                "VIF packet of {} bytes exceeds the {} byte packet slot",
+                bytes.len(), PACKET_SLOT_SIZE,
+            ));
+        }
+        let base = self.next_slot();
+        self.ee_ram[base..base + bytes.len()].copy_from_slice(bytes);
+        self.last_packet = (base, bytes.len() / 16);
+        Ok((base as u32, (bytes.len() / 16) as u32))
+    }
+
+    /// Build the VIF1 DMA packet for the next frame in EE RAM and return (madr, qwc)
+    /// for DMAC kick.
     pub fn build_packet(&mut self) -> (u32, u32) {
         let frame = self.frame;
         self.frame += 1;
+        let base = self.next_slot();
+        let qwc = self.write_packet(frame, base);
+        self.last_packet = (base, qwc);
+        (base as u32, qwc as u32)
+    }
 
-        // ---- Compute MVP per frame ----
-        let angle_y = frame as f32 * (PI / 180.0);
-        let angle_x = frame as f32 * (PI / 360.0);
-        let rot_y  = rotate_y(angle_y);
-        let rot_x  = rotate_x(angle_x);
-        let model  = mat_mul(rot_x, rot_y);
-        let view   = translate_z(-3.0);
-        let proj   = perspective(PI / 3.0, 640.0 / 448.0, 0.1, 100.0);
-        let mv     = mat_mul(view, model);
-        let mvp    = mat_mul(proj, mv);
+    /// Build the packet for an arbitrary frame index without advancing the animation,
+    /// into the out-of-band slot, and return (madr, qwc) for DMAC kick. Nothing else
+    /// changes: the packet bytes depend only on `frame` and the current settings, so the
+    /// same frame always yields the same packet (for replay and golden-packet comparisons),
+    /// and the frame loop's slots and `hexdump_packet` are left alone.
+    pub fn build_packet_for_frame(&mut self, frame: u64) -> (u32, u32) {
+        let qwc = self.write_packet(frame, OOB_PACKET_BASE);
+        (OOB_PACKET_BASE as u32, qwc as u32)
+    }
 
-        // ---- Write packet into EE RAM ----
-        let base = PACKET_BASE;
-        let ram  = &mut *self.ee_ram;
-        let mut qw = 0usize; // current QW index
+    /// Write the packet for `frame` at EE RAM `base`; returns its length in QWs.
+    fn write_packet(&mut self, frame: u64, base: usize) -> usize {
+        if let Some(sprite) = self.sprite {
+            return self.write_sprite_packet(sprite, base);
+        }
 
-        // Helper: write a u32 at a byte offset
-        fn w32(ram: &mut [u8], off: usize, val: u32) {
-            let b = val.to_le_bytes();
-            ram[off..off+4].copy_from_slice(&b);
+        let proj   = perspective(PI / 3.0, self.viewport.aspect(), 0.1, 100.0, self.depth_range);
+        let view   = self.view;
+        let t = self.anim_phase(frame) as f32;
+
+        // Everything the frame draws, in packet order: (vertices, model, color tint).
+        // Ground first: with no depth test, everything after paints over it
+        let recolored: Vec<Vec<MeshVertex>>;
+        let mut draws: Vec<Draw> = Vec::new();
+        if let Some(ground) = &self.ground {
+            draws.push((ground.vertices(), translate_scale([0.0, GROUND_Y, 0.0], 1.0), [1.0; 3]));
         }
 
-        fn write_qw(ram: &mut [u8], base: usize, qw: usize, w0: u32, w1: u32, w2: u32, w3: u32) {
-            let off = base + qw * 16;
-            w32(ram, off,    w0);
-            w32(ram, off+4,  w1);
-            w32(ram, off+8,  w2);
-            w32(ram, off+12, w3);
+        if self.scene.is_empty() {
+            // ---- Compute MVP per frame ----
+            let model = match &self.animation {
+                Some(anim) => pose_matrix(&anim.sample(t)),
+                None => {
+                    let angle_y = t * (PI / 180.0);
+                    let angle_x = t * (PI / 360.0);
+                    let rot_y  = rotate_y(angle_y);
+                    let rot_x  = rotate_x(angle_x);
+                    mat_mul(rot_x, rot_y)
+                }
+            };
+            draws.push((&CUBE, model, [1.0; 3]));
+        } else {
+            for obj in self.scene.draw_list() {
+                draws.push((obj.mesh.vertices(), obj.transform, obj.material.color));
+            }
         }
 
-        fn write_f32_qw(ram: &mut [u8], base: usize, qw: usize, x: f32, y: f32, z: f32, w: f32) {
-            write_qw(ram, base, qw,
-                f32::to_bits(x), f32::to_bits(y),
-                f32::to_bits(z), f32::to_bits(w));
+        // Tessellate everything after the ground, then clip to the frustum before VU1's
+        // perspective divide, which would flip anything behind the eye onto the screen.
+        // Clipping can turn a triangle into a fan of up to seven, so the level is the
+        // highest up to the one set whose clipped batches still fit in the slot
+        let meshes = self.ground.is_some() as usize;
+        let depth = self.depth_range;
+        let mvps: Vec<_> = draws.iter().map(|d| mat_mul(proj, mat_mul(view, d.1))).collect();
+        let unclipped = |level: u32| -> usize {
+            draws[meshes..].iter().map(|d| (d.0.len() << (2 * level)).div_ceil(BATCH_VERTS)).sum()
+        };
+        let prepare = |level: u32| -> Vec<Option<Vec<MeshVertex>>> {
+            draws
+                .iter()
+                .zip(&mvps)
+                .enumerate()
+                .map(|(i, (d, &mvp))| {
+                    if i < meshes || level == 0 {
+                        return clip_to_frustum(d.0, mvp, depth);
+                    }
+                    let verts = (0..level).fold(d.0.to_vec(), |v, _| scene::subdivide(&v));
+                    Some(clip_to_frustum(&verts, mvp, depth).unwrap_or(verts))
+                })
+                .collect()
+        };
+        let batches = |prepared: &[Option<Vec<MeshVertex>>]| -> usize {
+            draws
+                .iter()
+                .zip(prepared)
+                .map(|(d, p)| p.as_ref().map_or(d.0.len(), Vec::len).div_ceil(BATCH_VERTS))
+                .sum()
+        };
+        let mut level = (0..=self.tessellation)
+            .rev()
+            .find(|&l| unclipped(l) <= MAX_SCENE_BATCHES)
+            .unwrap_or(0);
+        let prepared = loop {
+            let p = prepare(level);
+            if level == 0 || batches(&p) <= MAX_SCENE_BATCHES + GROUND_BATCHES {
+                break p;
+            }
+            level -= 1;
+        };
+        for (draw, verts) in draws.iter_mut().zip(&prepared) {
+            if let Some(verts) = verts {
+                draw.0 = verts;
+            }
         }
 
-        // VIF tag helpers:
-        // STCYCL(wl=1, cl=1): cmd=0x01, data = (wl<<8)|cl = 0x0101
-        // UNPACK V4-32: cmd=0x6C, data = (num<<16)|addr
-        // MSCAL(addr=0): cmd=0x14, data=0
-        // FLUSH: cmd=0x11, data=0
-        fn vif_tag(cmd: u8, data: u32) -> u32 {
-            ((cmd as u32) << 24) | (data & 0x00FF_FFFF)
+        // Debug normals: the normal colors go through the transform-only routine untinted
+        if self.debug_normals {
+            recolored = draws.iter().map(|&(v, model, _)| normal_colors(v, model)).collect();
+            for (draw, verts) in draws.iter_mut().zip(&recolored) {
+                *draw = (verts, draw.1, [1.0; 3]);
+            }
         }
+        let lit = self.lit && !self.debug_normals;
+
+        // The unlit and linear routines take f32 attributes only
+        let f32_only = !lit || self.linear_lighting;
+        let palette = (self.indexed_colors && !f32_only).then(|| {
+            Palette::new(draws.iter().flat_map(|&(verts, _, tint)| {
+                verts.iter().map(move |v| [0, 1, 2].map(|c| v.color[c] * tint[c]))
+            }))
+        });
+        let packed_normals = self.packed_normals && !f32_only;
+        let packed_colors  = self.packed_colors && !f32_only;
+        // Only the main program's f32 attributes have interleaved and fog routines
+        let main_f32 = !f32_only && !packed_normals && !packed_colors && palette.is_none();
+        let format = BatchFormat {
+            lit,
+            linear:         self.linear_lighting,
+            tangents:       self.tangents,
+            packed_normals,
+            packed_colors,
+            palette:        palette.as_ref(),
+            interleaved:    self.interleaved && main_f32 && self.fog.is_none(),
+            fog:            self.fog.filter(|_| main_f32),
+            face_colors:    self.face_colors && main_f32 && !self.interleaved
+                && self.fog.is_none(),
+            viewport:       self.viewport.scale(),
+        };
+
+        // ---- Write packet into EE RAM ----
+        let ram  = &mut *self.ee_ram;
+        let mut qw = 0usize; // current QW index
 
         // QW 0: STCYCL
         write_qw(ram, base, qw, vif_tag(0x01, 0x0101), 0, 0, 0); qw += 1;
 
-        // QW 1: UNPACK V4-32 num=1 addr=108
-        write_qw(ram, base, qw, vif_tag(0x6C, (1 << 16) | 108), 0, 0, 0); qw += 1;
+        if let Some(palette) = &palette {
+            qw = write_palette(ram, base, qw, palette);
+        }
 
-        // QW 2: GIF tag
-        let gt = gif_tag();
-        write_qw(ram, base, qw, gt[0], gt[1], gt[2], gt[3]); qw += 1;
+        // One batch per 36-vertex chunk of each mesh, in draw order, as long as whole
+        // batches fit in the slot: a scene clipped into more than that even untessellated
+        // loses its last batches rather than running into the next slot
+        let last_start = PACKET_SLOT_SIZE / 16 - format.qwc(false);
+        'draws: for (verts, model, tint) in draws {
+            let mvp = mat_mul(proj, mat_mul(view, model));
+            for chunk in verts.chunks(BATCH_VERTS) {
+                if qw > last_start {
+                    break 'draws;
+                }
+                qw = write_batch(ram, base, qw, chunk, mvp, tint, format);
+            }
+        }
+        qw
+    }
 
-        // QW 3: UNPACK positions num=36 addr=0
-        write_qw(ram, base, qw, vif_tag(0x6C, (36 << 16) | 0), 0, 0, 0); qw += 1;
+    /// Write, at `base`, a packet that uploads a ready-made GIF SPRITE packet to
+    /// datamem[108] and MSCALs the micro-program's pass-through entry, which XGKICKs it
+    /// unmodified. Returns its length in QWs.
+    ///
+    ///  QW 0     UNPACK V4-32 num=5 addr=108
+    ///  QW 1     GIF tag: NLOOP=2, EOP=1, PRE=1, PRIM=6 (SPRITE), NREG=2 (RGBAQ, XYZ2)
+    ///  QW 2..5  RGBAQ [r,g,b,a] f32 / XYZ2 [x<<4, y<<4] i32, per corner
+    ///  QW 6     MSCAL execaddr=KICK_ENTRY
+    ///  QW 7     FLUSH
+    fn write_sprite_packet(&mut self, sprite: Sprite, base: usize) -> usize {
+        let ram  = &mut *self.ee_ram;
+        let mut qw = 0usize;
 
-        // QW 4..39: 36 position QWs
-        for v in &CUBE {
-            write_f32_qw(ram, base, qw, v.pos[0], v.pos[1], v.pos[2], 1.0);
-            qw += 1;
-        }
+        write_qw(ram, base, qw, vif_tag(0x6C, (5 << 16) | 108), 0, 0, 0); qw += 1;
 
-        // QW 40: UNPACK normals num=36 addr=36
-        write_qw(ram, base, qw, vif_tag(0x6C, (36 << 16) | 36), 0, 0, 0); qw += 1;
+        let gt = gif_tag_packed(2, gif::PRIM_SPRITE as u32, 2, 0x01 | (0x05 << 4));
+        write_qw(ram, base, qw, gt[0], gt[1], gt[2], gt[3]); qw += 1;
 
-        // QW 41..76: 36 normal QWs
-        for v in &CUBE {
-            write_f32_qw(ram, base, qw, v.normal[0], v.normal[1], v.normal[2], 0.0);
-            qw += 1;
+        let [r, g, b, a] = sprite.color.map(|c| c as f32 / 255.0);
+        // The GS leaves out a sprite's far edges when sampling at pixel centres: the second
+        // corner goes one past the inclusive rect
+        let [x0, y0, x1, y1] = sprite.rect;
+        for (x, y) in [(x0, y0), (x1 + 1, y1 + 1)] {
+            write_f32_qw(ram, base, qw, r, g, b, a); qw += 1;
+            write_qw(ram, base, qw, (x << 4) as u32, (y << 4) as u32, 0, 0); qw += 1;
         }
 
-        // QW 77: UNPACK colors num=36 addr=72
-        write_qw(ram, base, qw, vif_tag(0x6C, (36 << 16) | 72), 0, 0, 0); qw += 1;
+        write_qw(ram, base, qw, vif_tag(0x14, KICK_ENTRY as u32), 0, 0, 0); qw += 1;
+        write_qw(ram, base, qw, vif_tag(0x11, 0), 0, 0, 0); qw += 1;
+        qw
+    }
+}
 
-        // QW 78..113: 36 color QWs
-        for v in &CUBE {
-            write_f32_qw(ram, base, qw, v.color[0], v.color[1], v.color[2], 1.0);
-            qw += 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vert(pos: [f32; 3]) -> MeshVertex {
+        MeshVertex { pos, normal: [0.0, 0.0, 1.0], color: [1.0; 3], uv: [0.0; 2] }
+    }
+
+    /// `count` QWs of the packet at `madr` from QW `first` on, as f32 lanes.
+    fn packet_qws(ee: &EmotionEngine, madr: u32, first: usize, count: usize) -> Vec<[f32; 4]> {
+        ee.ee_ram[madr as usize + first * 16..][..count * 16]
+            .chunks_exact(16)
+            .map(|qw| std::array::from_fn(|i| f32::from_le_bytes(qw[i * 4..][..4].try_into().unwrap())))
+            .collect()
+    }
+
+    #[test]
+    fn triangles_reaching_behind_the_eye_are_clipped() {
+        let proj = perspective(PI / 3.0, 1.0, 0.1, 100.0, DepthRange::NegOneToOne);
+        let ahead = [vert([-1.0, -1.0, -5.0]), vert([1.0, -1.0, -5.0]), vert([0.0, 1.0, -5.0])];
+        assert!(clip_to_frustum(&ahead, proj, DepthRange::NegOneToOne).is_none());
+
+        // One corner behind the eye (+z), where VU1's divide would flip it on screen
+        let through = [vert([-1.0, -1.0, -5.0]), vert([1.0, -1.0, -5.0]), vert([0.0, 0.0, 1.0])];
+        let clipped = clip_to_frustum(&through, proj, DepthRange::NegOneToOne).unwrap();
+        assert_eq!(clipped.len() % 3, 0);
+        assert!(!clipped.is_empty());
+        for v in &clipped {
+            let w = (0..3).map(|c| proj[c][3] * v.pos[c]).sum::<f32>() + proj[3][3];
+            assert!(w >= 0.1 - 1e-5, "vertex {:?} nearer than the near plane", v.pos);
         }
+    }
 
-        // QW 114: UNPACK MVP num=4 addr=182
-        write_qw(ram, base, qw, vif_tag(0x6C, (4 << 16) | 182), 0, 0, 0); qw += 1;
+    #[test]
+    fn clipped_scenes_stay_within_the_packet_slot() {
+        // Each triangle reaches past three sides of the view and clips to a fan of four
+        let tri = [vert([-3.0, -0.1, 0.0]), vert([3.0, -0.1, 0.0]), vert([0.0, 6.0, 0.0])];
+        let verts: Vec<MeshVertex> =
+            tri.iter().copied().cycle().take(MAX_SCENE_BATCHES * BATCH_VERTS).collect();
+        let mut ee = EmotionEngine::new();
+        ee.set_tangents(true);
+        ee.set_tessellation_level(MAX_TESSELLATION_LEVEL);
+        ee.add_scene_object(SceneObject {
+            mesh:       Rc::new(Mesh::new(verts).unwrap()),
+            transform:  translate_scale([0.0; 3], 1.0),
+            material:   Default::default(),
+            draw_order: 0,
+        })
+        .unwrap();
+        let (_, qwc) = ee.build_packet();
+        assert!(qwc as usize * 16 <= PACKET_SLOT_SIZE, "{qwc} QWs overrun the slot");
+    }
 
-        // QW 115..118: 4 MVP column QWs (column-major: each column is [r0,r1,r2,r3])
-        for col in 0..4 {
-            write_f32_qw(ram, base, qw,
-                mvp[col][0], mvp[col][1], mvp[col][2], mvp[col][3]);
-            qw += 1;
+    #[test]
+    fn loaded_packets_must_be_whole_qws() {
+        let mut ee = EmotionEngine::new();
+        assert!(ee.load_packet(&[0; 20]).is_err());
+        assert_eq!(ee.load_packet(&[0; 32]).map(|(_, qwc)| qwc), Ok(2));
+    }
+
+    #[test]
+    fn half_time_scale_halves_the_per_frame_angle() {
+        let mut ee = EmotionEngine::new();
+        ee.build_packet();
+        ee.build_packet();
+        let now = ee.anim_phase(ee.frame);
+        let full = ee.anim_phase(ee.frame + 1) - now;
+
+        ee.set_time_scale(0.5);
+        assert_eq!(ee.anim_phase(ee.frame), now); // no jump in the pose
+        assert_eq!(ee.anim_phase(ee.frame + 1) - now, full / 2.0);
+        ee.set_time_scale(0.0);
+        assert_eq!(ee.anim_phase(ee.frame + 10), now);
+    }
+
+    #[test]
+    fn packet_for_a_frame_is_pure() {
+        let mut ee = EmotionEngine::new();
+        let packet = |ee: &mut EmotionEngine| {
+            let (madr, qwc) = ee.build_packet_for_frame(0);
+            ee.ee_ram[madr as usize..][..qwc as usize * 16].to_vec()
+        };
+        let first = packet(&mut ee);
+        assert!(!first.is_empty());
+        assert_eq!(packet(&mut ee), first);
+
+        // Neither stepping the frame loop nor a golden packet disturbs the other
+        let (madr, qwc) = ee.build_packet();
+        let live = ee.ee_ram[madr as usize..][..qwc as usize * 16].to_vec();
+        assert_eq!(packet(&mut ee), first);
+        assert_eq!(ee.ee_ram[madr as usize..][..qwc as usize * 16], live[..]);
+        assert_eq!(ee.build_packet().0, madr + PACKET_SLOT_SIZE as u32);
+    }
+
+    #[test]
+    fn look_at_from_plus_z_is_the_original_translate() {
+        let view = look_at([0.0, 0.0, 3.0], [0.0; 3], [0.0, 1.0, 0.0]).unwrap();
+        let fixed = translate_z(-3.0);
+        for (a, b) in view.iter().flatten().zip(fixed.iter().flatten()) {
+            assert!((a - b).abs() < 1e-6, "{view:?}");
         }
 
-        // QW 119: UNPACK light num=1 addr=186
-        write_qw(ram, base, qw, vif_tag(0x6C, (1 << 16) | 186), 0, 0, 0); qw += 1;
+        let mut ee = EmotionEngine::new();
+        let packet = |ee: &mut EmotionEngine| {
+            let (madr, qwc) = ee.build_packet_for_frame(5);
+            ee.ee_ram[madr as usize..][..qwc as usize * 16].to_vec()
+        };
+        let before = packet(&mut ee);
+        ee.set_view([0.0, 0.0, 3.0], [0.0; 3], [0.0, 1.0, 0.0]).unwrap();
+        assert_eq!(packet(&mut ee), before);
+        assert!(ee.set_view([0.0; 3], [0.0; 3], [0.0, 1.0, 0.0]).is_err());
+    }
 
-        // QW 120: light direction + ambient [lx, ly, lz, ambient]
-        write_f32_qw(ram, base, qw, 0.577, 0.577, 0.577, 0.2); qw += 1;
+    #[test]
+    fn near_and_far_planes_land_on_the_range_ends() {
+        let ndc_z = |depth, z: f32| {
+            let m = perspective(PI / 3.0, 1.0, 0.1, 100.0, depth);
+            let clip: [f32; 4] = std::array::from_fn(|r| m[2][r] * z + m[3][r]);
+            clip[2] / clip[3]
+        };
+        for (depth, near, far) in [
+            (DepthRange::NegOneToOne, -1.0, 1.0),
+            (DepthRange::ZeroToOne, 0.0, 1.0),
+        ] {
+            assert!((ndc_z(depth, -0.1) - near).abs() < 1e-5, "{depth:?}");
+            assert!((ndc_z(depth, -100.0) - far).abs() < 1e-5, "{depth:?}");
+        }
+    }
 
-        // QW 121: UNPACK viewport num=1 addr=187
-        write_qw(ram, base, qw, vif_tag(0x6C, (1 << 16) | 187), 0, 0, 0); qw += 1;
+    #[test]
+    fn linear_animation_midpoint_averages_the_keys_in_the_mvp() {
+        use crate::animation::Easing;
 
-        // QW 122: viewport scale [320.0, 224.0, 0.0, 0.0]
-        // (pixel-space half-extents; FTOI4 in VU1 will multiply by 16 → GS 12.4 format)
-        write_f32_qw(ram, base, qw, 320.0, 224.0, 0.0, 0.0); qw += 1;
+        let key = |time, translation, rotation, scale| Keyframe { time, translation, rotation, scale };
+        let keys = vec![
+            key(0.0, [0.0; 3], [0.0; 3], 1.0),
+            key(2.0, [2.0, 0.0, -1.0], [0.0, 90.0, 30.0], 3.0),
+        ];
+        let mut ee = EmotionEngine::new();
+        ee.set_animation(Some(Animation::new(keys, Easing::Linear, false).unwrap()));
+        let (madr, _) = ee.build_packet_for_frame(1);
 
-        // QW 123: MSCAL execaddr=0
-        write_qw(ram, base, qw, vif_tag(0x14, 0), 0, 0, 0); qw += 1;
+        // QW 115..118: the MVP columns of the default lit f32 packet
+        let mvp = packet_qws(&ee, madr, 115, 4);
+        let mid = pose_matrix(&key(1.0, [1.0, 0.0, -0.5], [0.0, 45.0, 15.0], 2.0));
+        let proj = perspective(PI / 3.0, ee.viewport.aspect(), 0.1, 100.0, ee.depth_range);
+        let expected = mat_mul(proj, mat_mul(ee.view, mid));
+        for (a, b) in mvp.iter().flatten().zip(expected.iter().flatten()) {
+            assert!((a - b).abs() < 1e-5, "{mvp:?} vs {expected:?}");
+        }
+    }
 
-        // QW 124: FLUSH
-        write_qw(ram, base, qw, vif_tag(0x11, 0), 0, 0, 0); qw += 1;
+    #[test]
+    fn cube_front_face_tangents_run_along_plus_x() {
+        let front: Vec<[f32; 4]> = CUBE.iter()
+            .zip(scene::tangents(&CUBE))
+            .filter(|(v, _)| v.normal == [0.0, 0.0, 1.0])
+            .map(|(_, t)| t)
+            .collect();
+        assert_eq!(front.len(), 6);
+        for t in front {
+            assert!((t[0] - 1.0).abs() < 1e-6 && t[1].abs() < 1e-6 && t[2].abs() < 1e-6, "{t:?}");
+            assert_eq!(t[3], 1.0);
+        }
+    }
 
-        debug_assert_eq!(qw, PACKET_QWC as usize);
+    #[test]
+    fn resolution_sets_the_aspect_and_the_viewport_scale_together() {
+        let mut ee = EmotionEngine::new();
+        for (w, h) in [(640, 448), (320, 240), (1280, 448)] {
+            ee.set_viewport(w, h);
+            let (madr, _) = ee.build_packet_for_frame(0);
+            // Frame 0 is unrotated, so the MVP's x and y scales are the projection's
+            let mvp = packet_qws(&ee, madr, 115, 4);
+            let aspect = mvp[1][1] / mvp[0][0];
+            assert!((aspect - w as f32 / h as f32).abs() < 1e-5, "{w}x{h}: aspect {aspect}");
+            let scale = packet_qws(&ee, madr, 122, 1)[0];
+            assert_eq!(scale[..2], [w as f32 / 2.0, h as f32 / 2.0]);
+        }
+    }
 
-        (D1_MADR, D1_QWC)
+    #[test]
+    fn hexdump_decodes_the_stcycl_and_the_gif_tag() {
+        let mut ee = EmotionEngine::new();
+        ee.build_packet();
+        let dump = ee.hexdump_packet();
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 125);
+        assert!(lines[0].starts_with("QW    0 +0x00000  01000101 "), "{}", lines[0]);
+        assert!(lines[0].ends_with("STCYCL wl=1 cl=1"), "{}", lines[0]);
+        assert!(lines[1].contains("UNPACK"), "{}", lines[1]);
+        assert!(lines[2].contains("GIF tag NLOOP=36 EOP PRE"), "{}", lines[2]);
     }
 }
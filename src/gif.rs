@@ -9,101 +9,329 @@ pub struct GifVertex {
     pub x: i32,
     /// Pixel-space Y (decoded from GS 12.4 fixed-point via >> 4)
     pub y: i32,
+    /// GS FOG coefficient: 255 = no fog, 0 = all fog color. Set by XYZF2 writes.
+    pub fog: u8,
 }
 
-/// A GS primitive (triangle strip/list) with Gouraud flag.
+/// GS PRIM type (PRIM[2:0]) of a line list: one segment per two vertices.
+pub const PRIM_LINE: u8 = 1;
+
+/// GS PRIM type (PRIM[2:0]) of a triangle strip: each vertex after the first two closes
+/// a triangle with the two before it.
+pub const PRIM_TRIANGLE_STRIP: u8 = 4;
+
+/// GS PRIM type (PRIM[2:0]) of a sprite: axis-aligned rectangle from two vertices.
+pub const PRIM_SPRITE: u8 = 6;
+
+/// How RGBAQ channels outside [0, 1] become 8-bit colors, after the GS COLCLAMP register.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorClampMode {
+    /// COLCLAMP.CLAMP = 1: saturate to 0..255 (1.5 → 255).
+    #[default]
+    Clamp,
+    /// COLCLAMP.CLAMP = 0: keep the low 8 bits of the scaled value (1.5 → 383 → 127).
+    Wrap,
+    /// Not a GS mode: zero any out-of-range channel, making overflow stand out.
+    Mask,
+}
+
+impl ColorClampMode {
+    fn to_u8(self, c: f32) -> u8 {
+        match self {
+            ColorClampMode::Clamp => (c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8,
+            ColorClampMode::Wrap  => (c * 255.0).round() as i32 as u8,
+            ColorClampMode::Mask if (0.0..=1.0).contains(&c) => (c * 255.0 + 0.5) as u8,
+            ColorClampMode::Mask  => 0,
+        }
+    }
+}
+
+/// A GS primitive (triangle list, line pairs or sprite pairs) with Gouraud flag.
 pub struct GsPrimitive {
+    /// PRIM[2:0]: 1 = line, 3 = triangle (list), 4 = triangle strip, 6 = sprite; other
+    /// types are drawn as triangle lists
+    pub prim_type: u8,
     pub iip:      bool,
+    /// PRIM.AA1: antialiased, so the rasterizer records edge flags for it
+    pub aa1:      bool,
+    /// PRIM.FGE: blend toward the fog color by each vertex's `fog`
+    pub fge:      bool,
     pub vertices: Vec<GifVertex>,
 }
 
-/// Parse a GIF packet starting at `vu_mem[base_qw]`.
+impl GsPrimitive {
+    /// Screen-space bounding box (min_x, min_y, max_x, max_y); None if there are no vertices.
+    pub fn bounding_box(&self) -> Option<(i32, i32, i32, i32)> {
+        let first = self.vertices.first()?;
+        Some(self.vertices.iter().fold(
+            (first.x, first.y, first.x, first.y),
+            |(x0, y0, x1, y1), v| (x0.min(v.x), y0.min(v.y), x1.max(v.x), y1.max(v.y)),
+        ))
+    }
+}
+
+/// Decoded primitives, reused from frame to frame: `clear` keeps every primitive and its
+/// vertex storage, so once the buffer has grown to a frame's size, parsing into it no
+/// longer allocates. Memory stays bounded by the largest frame seen.
+#[derive(Default)]
+pub struct PrimBuffer {
+    prims: Vec<GsPrimitive>,
+    len:   usize, // prims[len..] are spare, kept for their allocations
+}
+
+impl PrimBuffer {
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn as_slice(&self) -> &[GsPrimitive] {
+        &self.prims[..self.len]
+    }
+
+    /// Append an empty primitive, recycling a spare one if there is any.
+    fn push(&mut self, prim_type: u8, iip: bool, aa1: bool, fge: bool, nloop: usize) {
+        if let Some(prim) = self.prims.get_mut(self.len) {
+            prim.prim_type = prim_type;
+            prim.iip = iip;
+            prim.aa1 = aa1;
+            prim.fge = fge;
+            prim.vertices.clear();
+            prim.vertices.reserve(nloop);
+        } else {
+            self.prims.push(GsPrimitive {
+                prim_type, iip, aa1, fge, vertices: Vec::with_capacity(nloop),
+            });
+        }
+        self.len += 1;
+    }
+
+    fn last_mut(&mut self) -> Option<&mut GsPrimitive> {
+        self.prims[..self.len].last_mut()
+    }
+
+    /// Drop the primitives from `start` on that got no vertices, keeping their storage spare.
+    fn retain_nonempty(&mut self, start: usize) {
+        let mut kept = start;
+        for i in start..self.len {
+            if !self.prims[i].vertices.is_empty() {
+                self.prims.swap(kept, i);
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+/// Parse a GIF packet starting at `vu_mem[base_qw]`, appending its primitives to `out`.
 ///
 /// Layout expected:
 ///   vu_mem[base_qw]      — 128-bit GIF tag (low u64 / high u64 in two f32×4 QWs)
-///   vu_mem[base_qw+1..]  — NLOOP×NREG data QWs
+///   vu_mem[base_qw+1..]  — NLOOP×NREG data QWs, REGS nibbles naming each one, e.g.
 ///     QW+0: RGBAQ register  → [r,g,b,1.0] as f32
 ///     QW+1: XYZ2 register   → [x_fixed, y_fixed, z, _] (bit-cast i32 from FTOI4)
-pub fn parse_gif_packet(vu_mem: &[[f32; 4]; 1024], base_qw: usize) -> Vec<GsPrimitive> {
-    // --- Decode GIF tag (128-bit = two f32[4] QWs merged) ---
-    // The GIF tag is stored in a single f32[4] QW (VU mem uses [f32;4] per slot).
-    // bit-cast the two f32 pairs as two u64s.
-    let tag_qw = vu_mem[base_qw];
-
-    // Low 64 bits: indices [0] and [1] as u32 pairs
-    let lo_lo = tag_qw[0].to_bits();   // bits [31:0]
-    let lo_hi = tag_qw[1].to_bits();   // bits [63:32]
-    let hi_lo = tag_qw[2].to_bits();   // bits [95:64]  (REGS low 32)
-    // hi_hi = tag_qw[3] not needed
-
-    // NLOOP[14:0]
-    let nloop = (lo_lo & 0x7FFF) as usize;
-    // EOP[15] — end of packet (we don't need it here)
-    // PRE[46] — whether PRIM field is pre-set
-    let pre   = ((lo_hi >> 14) & 1) != 0;
-    // PRIM[57:47] in the low 64 bits
-    let prim_raw = ((lo_hi >> 15) & 0x7FF) as u16;
-    // FLG at lo64[60:59] = lo_hi[28:27]
-    let flg   = (lo_hi >> 27) & 0x3;
-    // NREG[63:60] — actually bits [63:60] of the low u64
-    let nreg_raw = (lo_hi >> 28) & 0xF; // bits [63:60] of lo64 is [31:28] of lo_hi u32
-    // Re-read: lo64 = lo_lo | (lo_hi << 32)
-    // NREG is bits [63:60] of lo64 → bits [31:28] of lo_hi
-    let nreg  = (nreg_raw as usize).max(1); // 0 means 16
-
-    // REGS field in hi64: 4 bits per register (we care about reg0 and reg1)
-    let reg0  = (hi_lo & 0xF) as u8;       // first reg descriptor
-    let reg1  = ((hi_lo >> 4) & 0xF) as u8; // second reg descriptor
-
-    if nloop == 0 || flg != 0 {
-        // Only handle PACKED mode (FLG=0)
-        return vec![];
-    }
+///       or XYZF2            → [x_fixed, y_fixed, z, f << 4], F in bits [11:4]
+///   Tags chain back to back until one has EOP set.
+///
+/// Like the GS, register state persists: a vertex is kicked on every XYZ2 write using
+/// the RGBAQ most recently written, so a packet may set RGBAQ once (e.g. in its own
+/// NLOOP=1 tag) and then send only XYZ2 for a run of flat-colored vertices.
+///
+/// `xy_offset` is the GS XYOFFSET register (OFX, OFY) in 12.4 fixed-point; it is
+/// subtracted from every XYZ2 coordinate before the pixel conversion. Hardware code
+/// typically uses (2048 << 4, 2048 << 4); the built-in VU1 program emits 0-based
+/// coordinates and runs with (0, 0).
+///
+/// `clamp` decides how RGBAQ channels outside [0, 1] are stored, see `ColorClampMode`.
+pub fn parse_gif_packet(
+    vu_mem:    &[[f32; 4]; 1024],
+    base_qw:   usize,
+    xy_offset: (i32, i32),
+    clamp:     ColorClampMode,
+    out:       &mut PrimBuffer,
+) {
+    let start = out.len;
+
+    // GS register state — survives across loop iterations and chained tags
+    let mut prim_type = 3u8; // TRIANGLE until a tag sets PRIM
+    let mut r = 0u8;
+    let mut g = 0u8;
+    let mut b = 0u8;
+    let mut a = 255u8;
+    let mut fog = 255u8;
 
-    // IIP (Gouraud) = bit 3 of PRIM
-    let iip = pre && ((prim_raw >> 3) & 1) != 0;
+    let mut cur = base_qw;
 
-    let mut vertices = Vec::with_capacity(nloop);
-    let mut cur = base_qw + 1;
+    while cur < 1024 {
+        // --- Decode GIF tag (128-bit, stored as one f32[4] QW) ---
+        // bit-cast the four f32 lanes back to the u32 words of the tag.
+        let tag_qw = vu_mem[cur];
+        cur += 1;
 
-    for _ in 0..nloop {
-        let mut r = 0u8;
-        let mut g = 0u8;
-        let mut b = 0u8;
-        let mut a = 255u8;
-        let mut px = 0i32;
-        let mut py = 0i32;
+        let lo_lo = tag_qw[0].to_bits();   // bits [31:0]
+        let lo_hi = tag_qw[1].to_bits();   // bits [63:32]
+        let hi_lo = tag_qw[2].to_bits();   // bits [95:64]   REGS 0..7
+        let hi_hi = tag_qw[3].to_bits();   // bits [127:96]  REGS 8..15
+
+        // NLOOP[14:0]
+        let nloop = (lo_lo & 0x7FFF) as usize;
+        // EOP[15] — last tag of the packet
+        let eop   = ((lo_lo >> 15) & 1) != 0;
+        // PRE[46] — whether PRIM field is pre-set
+        let pre   = ((lo_hi >> 14) & 1) != 0;
+        // PRIM[57:47] in the low 64 bits
+        let prim_raw = ((lo_hi >> 15) & 0x7FF) as u16;
+        // FLG[59:58] = lo_hi[27:26]
+        let flg   = (lo_hi >> 26) & 0x3;
+        // NREG[63:60] = lo_hi[31:28]; 0 means 16
+        let nreg  = match (lo_hi >> 28) & 0xF { 0 => 16, n => n as usize };
+
+        // REGS field in hi64: 4 bits per register descriptor, reg0 in the low nibble
+        let regs  = (hi_lo as u64) | ((hi_hi as u64) << 32);
+
+        if flg != 0 {
+            // Only handle PACKED mode (FLG=0)
+            break;
+        }
 
-        for reg_idx in 0..nreg {
-            if cur >= 1024 {
-                break;
+        if pre || out.len == start {
+            if pre {
+                prim_type = (prim_raw & 0x7) as u8;
             }
-            let qw = vu_mem[cur];
-            cur += 1;
-
-            let reg_id = if reg_idx == 0 { reg0 } else { reg1 };
-            match reg_id {
-                0x01 => {
-                    // RGBAQ: f32 [r,g,b,a] in [0,1]
-                    r = (qw[0].clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
-                    g = (qw[1].clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
-                    b = (qw[2].clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
-                    a = (qw[3].clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+            // IIP (Gouraud) = bit 3 of PRIM
+            let iip = pre && ((prim_raw >> 3) & 1) != 0;
+            // FGE = bit 5 of PRIM
+            let fge = pre && ((prim_raw >> 5) & 1) != 0;
+            // AA1 = bit 6 of PRIM
+            let aa1 = pre && ((prim_raw >> 6) & 1) != 0;
+            out.push(prim_type, iip, aa1, fge, nloop);
+        }
+
+        for _ in 0..nloop {
+            for reg_idx in 0..nreg {
+                if cur >= 1024 {
+                    break;
                 }
-                0x05 => {
-                    // XYZ2: bit-cast i32 from FTOI4 output, then >> 4 for pixel coords
-                    let xi = qw[0].to_bits() as i32;
-                    let yi = qw[1].to_bits() as i32;
-                    px = xi >> 4;
-                    py = yi >> 4;
+                let qw = vu_mem[cur];
+                cur += 1;
+
+                let reg_id = ((regs >> (reg_idx * 4)) & 0xF) as u8;
+                match reg_id {
+                    0x01 => {
+                        // RGBAQ: f32 [r,g,b,a], nominally in [0,1]
+                        r = clamp.to_u8(qw[0]);
+                        g = clamp.to_u8(qw[1]);
+                        b = clamp.to_u8(qw[2]);
+                        a = clamp.to_u8(qw[3]);
+                    }
+                    0x04 | 0x05 => {
+                        // XYZ2: bit-cast i32 from FTOI4 output, remove XYOFFSET, >> 4 for pixels.
+                        // Writing XYZ2 kicks a vertex with the current RGBAQ; XYZF2 also
+                        // sets FOG from bits [11:4] of its last word.
+                        if reg_id == 0x04 {
+                            fog = (qw[3].to_bits() >> 4) as u8;
+                        }
+                        let xi = (qw[0].to_bits() as i32).wrapping_sub(xy_offset.0);
+                        let yi = (qw[1].to_bits() as i32).wrapping_sub(xy_offset.1);
+                        if let Some(prim) = out.last_mut() {
+                            prim.vertices.push(GifVertex {
+                                r, g, b, a, x: xi >> 4, y: yi >> 4, fog,
+                            });
+                        }
+                    }
+                    _ => {} // unknown register — skip
                 }
-                _ => {} // unknown register — skip
             }
         }
 
-        vertices.push(GifVertex { r, g, b, a, x: px, y: py });
+        if eop {
+            break;
+        }
     }
 
-    // Split into triangles (primitive type assumed TRIANGLE_LIST from PRIM)
-    vec![GsPrimitive { iip, vertices }]
+    out.retain_nonempty(start);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PACKED GIF tag as the f32 lanes VU memory holds it.
+    fn tag(nloop: u32, eop: bool, prim: Option<u32>, nreg: u32, regs: u32) -> [f32; 4] {
+        let lo_lo = nloop | (eop as u32) << 15;
+        let lo_hi = prim.map_or(0, |p| (1 << 14) | (p << 15)) | (nreg << 28);
+        [lo_lo, lo_hi, regs, 0].map(f32::from_bits)
+    }
+
+    fn xyz(x: i32, y: i32) -> [f32; 4] {
+        [(x << 4) as u32, (y << 4) as u32, 0, 0].map(f32::from_bits)
+    }
+
+    #[test]
+    fn rgbaq_written_once_colors_every_later_vertex() {
+        let mut mem = [[0.0; 4]; 1024];
+        mem[0] = tag(1, false, Some(3), 1, 0x1);        // RGBAQ once
+        mem[1] = [1.0, 0.5, 0.0, 1.0];
+        mem[2] = tag(3, true, None, 1, 0x5);            // then three XYZ2
+        mem[3..6].copy_from_slice(&[xyz(0, 0), xyz(10, 0), xyz(0, 10)]);
+
+        let mut out = PrimBuffer::default();
+        parse_gif_packet(&mem, 0, (0, 0), ColorClampMode::Clamp, &mut out);
+        let prims = out.as_slice();
+        assert_eq!(prims.len(), 1);
+        assert_eq!(prims[0].prim_type, 3);
+        let verts = &prims[0].vertices;
+        assert_eq!(verts.iter().map(|v| (v.x, v.y)).collect::<Vec<_>>(), [(0, 0), (10, 0), (0, 10)]);
+        assert!(verts.iter().all(|v| (v.r, v.g, v.b, v.a) == (255, 128, 0, 255)));
+    }
+
+    #[test]
+    fn reused_buffer_parses_like_a_fresh_one() {
+        // A strip of 4 and a triangle list of 3, then just a list of 6 at another address
+        let mut mem = [[0.0; 4]; 1024];
+        mem[0] = tag(4, false, Some(4), 1, 0x5);
+        mem[1..5].copy_from_slice(&[xyz(0, 0), xyz(9, 0), xyz(0, 9), xyz(9, 9)]);
+        mem[5] = tag(3, true, Some(3), 1, 0x5);
+        mem[6..9].copy_from_slice(&[xyz(1, 2), xyz(3, 4), xyz(5, 6)]);
+        mem[20] = tag(6, true, Some(3), 1, 0x5);
+        for k in 0..6 {
+            mem[21 + k] = xyz(k as i32, 2 * k as i32);
+        }
+
+        let summary = |buf: &PrimBuffer| -> Vec<(u8, Vec<(i32, i32)>)> {
+            buf.as_slice().iter()
+                .map(|p| (p.prim_type, p.vertices.iter().map(|v| (v.x, v.y)).collect()))
+                .collect()
+        };
+        let fresh = |base| {
+            let mut out = PrimBuffer::default();
+            parse_gif_packet(&mem, base, (0, 0), ColorClampMode::Clamp, &mut out);
+            summary(&out)
+        };
+
+        let mut reused = PrimBuffer::default();
+        for base in [0, 20, 0, 20] {
+            reused.clear();
+            parse_gif_packet(&mem, base, (0, 0), ColorClampMode::Clamp, &mut reused);
+            assert_eq!(summary(&reused), fresh(base), "base {base}");
+        }
+        assert_eq!(fresh(0).len(), 2);
+    }
+
+    #[test]
+    fn over_bright_colors_follow_the_clamp_mode() {
+        let mut mem = [[0.0; 4]; 1024];
+        mem[0] = tag(1, true, Some(3), 2, 0x51);
+        mem[1] = [1.5, 0.5, -0.25, 1.0];
+        mem[2] = xyz(0, 0);
+        let rgb = |mode| {
+            let mut out = PrimBuffer::default();
+            parse_gif_packet(&mem, 0, (0, 0), mode, &mut out);
+            let v = &out.as_slice()[0].vertices[0];
+            (v.r, v.g, v.b)
+        };
+        assert_eq!(rgb(ColorClampMode::Clamp), (255, 128, 0));
+        // 1.5 → 383 → low 8 bits 127; -0.25 → -64 → 192
+        assert_eq!(rgb(ColorClampMode::Wrap), (127, 128, 192));
+        assert_eq!(rgb(ColorClampMode::Mask), (0, 128, 0));
+    }
 }
@@ -2,12 +2,15 @@
 // Maps to: PS2 GS display output reading from eDRAM framebuffer.
 // The software rasterizer writes a Framebuffer (CPU), this uploads it as a
 // wgpu Rgba8Unorm texture and blits it to the canvas via a fullscreen quad.
+// Where wgpu can't start (no WebGL2), frames are put on a 2D canvas context instead.
 
-use wasm_bindgen::JsCast;
-use crate::gs_rasterizer::{Framebuffer, FB_W, FB_H};
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::gs_rasterizer::{DirtyRect, Framebuffer, FB_W, FB_H};
 
 // ---------------------------------------------------------------------------
-// WGSL shader — 6-vertex hardcoded fullscreen quad, nearest-neighbor sample
+// WGSL shader — 6-vertex hardcoded fullscreen quad, filtered sample
 // ---------------------------------------------------------------------------
 
 const SHADER_SRC: &str = r#"
@@ -55,36 +58,249 @@ fn fs(v: VO) -> @location(0) vec4<f32> {
 // GsDisplay
 // ---------------------------------------------------------------------------
 
-pub struct GsDisplay {
-    surface:    wgpu::Surface<'static>,
-    device:     wgpu::Device,
-    queue:      wgpu::Queue,
-    config:     wgpu::SurfaceConfiguration,
-    pipeline:   wgpu::RenderPipeline,
-    fb_texture: wgpu::Texture,
-    bind_group: wgpu::BindGroup,
+/// The canvas output, through whichever path could be set up.
+pub enum GsDisplay {
+    Wgpu(Box<WgpuBlit>),
+    /// Fallback when wgpu fails to initialise.
+    Canvas2d(Canvas2dBlit),
+    /// No canvas at all: the host-side unit tests run the pipeline without presenting.
+    #[cfg(test)]
+    Headless,
 }
 
 impl GsDisplay {
+    /// Set up the wgpu blit on canvas `canvas_id`, falling back to a 2D canvas context if
+    /// any step of the wgpu setup fails.
     pub async fn new(canvas_id: &str) -> Result<Self, String> {
         // --- DOM canvas ---
         let window   = web_sys::window().ok_or("no window")?;
         let document = window.document().ok_or("no document")?;
-        let canvas: web_sys::HtmlCanvasElement = document
+        let canvas: HtmlCanvasElement = document
             .get_element_by_id(canvas_id)
             .ok_or_else(|| format!("canvas #{canvas_id} not found"))?
             .dyn_into()
             .map_err(|_| "element is not a canvas")?;
 
+        let wgpu = WgpuBlit::new(canvas.clone())
+            .await
+            .map(|blit| GsDisplay::Wgpu(Box::new(blit)));
+        with_fallback(wgpu, |wgpu_err| {
+            let blit = Canvas2dBlit::new(canvas)?;
+            web_sys::console::warn_1(&format!(
+                "wgpu unavailable ({wgpu_err}); presenting through a 2D canvas",
+            ).into());
+            Ok(GsDisplay::Canvas2d(blit))
+        })
+    }
+
+    /// Present the software framebuffer on the canvas.
+    pub fn upload_and_present(&mut self, fb: &mut Framebuffer) {
+        let changed = fb.take_changed_rect();
+        match self {
+            GsDisplay::Wgpu(blit)     => blit.upload_and_present(fb, changed),
+            GsDisplay::Canvas2d(blit) => blit.upload_and_present(fb),
+            #[cfg(test)]
+            GsDisplay::Headless       => {}
+        }
+    }
+
+    /// Name of the path in use: "wgpu" or "canvas2d".
+    pub fn backend(&self) -> &'static str {
+        match self {
+            GsDisplay::Wgpu(_)     => "wgpu",
+            GsDisplay::Canvas2d(_) => "canvas2d",
+            #[cfg(test)]
+            GsDisplay::Headless    => "headless",
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Canvas2dBlit
+// ---------------------------------------------------------------------------
+
+/// CPU presentation through `CanvasRenderingContext2d.putImageData`: no scaling, so the
+/// canvas is resized to the render resolution.
+pub struct Canvas2dBlit {
+    canvas: HtmlCanvasElement,
+    ctx:    CanvasRenderingContext2d,
+}
+
+fn context_2d(canvas: &HtmlCanvasElement) -> Option<CanvasRenderingContext2d> {
+    canvas.get_context("2d").ok().flatten()?.dyn_into().ok()
+}
+
+impl Canvas2dBlit {
+    pub fn new(mut canvas: HtmlCanvasElement) -> Result<Self, String> {
+        let ctx = match context_2d(&canvas) {
+            Some(ctx) => ctx,
+            None => {
+                // wgpu got as far as taking a WebGL2 context, and a canvas keeps its first
+                // context type: put a fresh copy of the element in its place
+                let fresh: HtmlCanvasElement = canvas
+                    .clone_node()
+                    .map_err(|_| "could not copy the canvas")?
+                    .dyn_into()
+                    .map_err(|_| "canvas copy is not a canvas")?;
+                canvas
+                    .replace_with_with_node_1(&fresh)
+                    .map_err(|_| "could not replace the canvas")?;
+                canvas = fresh;
+                context_2d(&canvas).ok_or("canvas has no 2D context")?
+            }
+        };
+        Ok(Canvas2dBlit { canvas, ctx })
+    }
+
+    pub fn upload_and_present(&mut self, fb: &Framebuffer) {
+        let (width, height) = (fb.width as u32, fb.height as u32);
+        if self.canvas.width() != width || self.canvas.height() != height {
+            self.canvas.set_width(width);
+            self.canvas.set_height(height);
+        }
+        // sRGB-encoded, as the wgpu blit to an sRGB surface shows it
+        let rgba = fb.to_rgba8(true);
+        let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&rgba), width, height);
+        if let Ok(image) = image {
+            let _ = self.ctx.put_image_data(&image, 0.0, 0.0);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// WgpuBlit
+// ---------------------------------------------------------------------------
+
+pub struct WgpuBlit {
+    surface:    wgpu::Surface<'static>,
+    device:     wgpu::Device,
+    queue:      wgpu::Queue,
+    config:     wgpu::SurfaceConfiguration,
+    pipeline:   wgpu::RenderPipeline,
+    bgl:        wgpu::BindGroupLayout,
+    sampler:    wgpu::Sampler,
+    fb_texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Create the framebuffer upload texture and its bind group at the given size.
+fn create_fb_texture(
+    device:  &wgpu::Device,
+    bgl:     &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    width:   u32,
+    height:  u32,
+) -> (wgpu::Texture, wgpu::BindGroup) {
+    let fb_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label:           Some("FB Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count:    1,
+        dimension:       wgpu::TextureDimension::D2,
+        format:          wgpu::TextureFormat::Rgba8Unorm,
+        usage:           wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats:    &[],
+    });
+
+    let tex_view = fb_texture.create_view(&Default::default());
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label:  Some("FB BindGroup"),
+        layout: bgl,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding:  0,
+                resource: wgpu::BindingResource::TextureView(&tex_view),
+            },
+            wgpu::BindGroupEntry {
+                binding:  1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    });
+
+    (fb_texture, bind_group)
+}
+
+/// Take the next surface texture from `acquire`. A transient loss (Lost/Outdated, e.g.
+/// on resize or a tab switch) is met with `reconfigure` and one more try, so the
+/// framebuffer texture already uploaded is still presented this frame; None drops it.
+fn acquire_with_retry<T>(
+    mut acquire: impl FnMut() -> Result<T, wgpu::SurfaceError>,
+    reconfigure: impl FnOnce(),
+) -> Option<T> {
+    match acquire() {
+        Ok(frame) => Some(frame),
+        Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+            reconfigure();
+            acquire().ok()
+        }
+        Err(_) => None,
+    }
+}
+
+/// The wgpu display if it started, else `fallback` (given the wgpu error); an error names
+/// both failures when neither works.
+fn with_fallback<T>(
+    wgpu:     Result<T, String>,
+    fallback: impl FnOnce(&str) -> Result<T, String>,
+) -> Result<T, String> {
+    wgpu.or_else(|wgpu_err| {
+        fallback(&wgpu_err).map_err(|e| format!("{wgpu_err}; 2D canvas fallback: {e}"))
+    })
+}
+
+/// A wgpu surface on `canvas`. Canvas surfaces only exist on wasm32; a native build
+/// (the host-side unit tests) has no DOM to present to, so it reports an error.
+fn canvas_surface(
+    instance: &wgpu::Instance,
+    canvas:   HtmlCanvasElement,
+) -> Result<wgpu::Surface<'static>, String> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        instance
+            .create_surface(wgpu::SurfaceTarget::Canvas(canvas))
+            .map_err(|e| format!("create_surface: {e}"))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = (instance, canvas);
+        Err("create_surface: canvas surfaces need the wasm32 target".into())
+    }
+}
+
+/// Pick the surface format (sRGB preferred) and alpha mode from the adapter's capabilities.
+/// An adapter that can't present to the canvas reports none; fail instead of indexing.
+fn choose_surface_format(
+    caps: &wgpu::SurfaceCapabilities,
+) -> Result<(wgpu::TextureFormat, wgpu::CompositeAlphaMode), String> {
+    let format = caps
+        .formats
+        .iter()
+        .copied()
+        .find(|f| f.is_srgb())
+        .or_else(|| caps.formats.first().copied())
+        .ok_or("surface is not supported by the adapter (no texture formats)")?;
+    let alpha_mode = *caps
+        .alpha_modes
+        .first()
+        .ok_or("surface is not supported by the adapter (no alpha modes)")?;
+    Ok((format, alpha_mode))
+}
+
+impl WgpuBlit {
+    pub async fn new(canvas: HtmlCanvasElement) -> Result<Self, String> {
         // --- wgpu instance (WebGL2) ---
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::GL,
             ..Default::default()
         });
 
-        let surface = instance
-            .create_surface(wgpu::SurfaceTarget::Canvas(canvas))
-            .map_err(|e| format!("create_surface: {e}"))?;
+        let surface = canvas_surface(&instance, canvas)?;
 
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
@@ -108,13 +324,8 @@ impl GsDisplay {
             .map_err(|e| format!("request_device: {e}"))?;
 
         // --- surface config ---
-        let caps   = surface.get_capabilities(&adapter);
-        let format = caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(caps.formats[0]);
+        let caps = surface.get_capabilities(&adapter);
+        let (format, alpha_mode) = choose_surface_format(&caps)?;
 
         let config = wgpu::SurfaceConfiguration {
             usage:        wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -122,36 +333,22 @@ impl GsDisplay {
             width:        FB_W as u32,
             height:       FB_H as u32,
             present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode:   caps.alpha_modes[0],
+            alpha_mode,
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
         surface.configure(&device, &config);
 
-        // --- framebuffer texture (CPU→GPU upload target) ---
-        let fb_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label:           Some("FB Texture"),
-            size: wgpu::Extent3d {
-                width:                 FB_W as u32,
-                height:                FB_H as u32,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count:    1,
-            dimension:       wgpu::TextureDimension::D2,
-            format:          wgpu::TextureFormat::Rgba8Unorm,
-            usage:           wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats:    &[],
-        });
-
-        // --- nearest sampler ---
+        // --- linear sampler ---
+        // At 1:1 render scale texel centers land on pixel centers, so this is identical
+        // to nearest sampling; at other scales it filters the down/upsample.
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label:             Some("FB Sampler"),
             address_mode_u:    wgpu::AddressMode::ClampToEdge,
             address_mode_v:    wgpu::AddressMode::ClampToEdge,
             address_mode_w:    wgpu::AddressMode::ClampToEdge,
-            mag_filter:        wgpu::FilterMode::Nearest,
-            min_filter:        wgpu::FilterMode::Nearest,
+            mag_filter:        wgpu::FilterMode::Linear,
+            min_filter:        wgpu::FilterMode::Linear,
             mipmap_filter:     wgpu::MipmapFilterMode::Nearest,
             ..Default::default()
         });
@@ -179,22 +376,9 @@ impl GsDisplay {
             ],
         });
 
-        let tex_view = fb_texture.create_view(&Default::default());
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label:  Some("FB BindGroup"),
-            layout: &bgl,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding:  0,
-                    resource: wgpu::BindingResource::TextureView(&tex_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding:  1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-        });
+        // --- framebuffer texture (CPU→GPU upload target), sized to the render resolution ---
+        let (fb_texture, bind_group) =
+            create_fb_texture(&device, &bgl, &sampler, FB_W as u32, FB_H as u32);
 
         // --- render pipeline ---
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -242,49 +426,66 @@ impl GsDisplay {
             cache:          None,
         });
 
-        Ok(GsDisplay {
+        Ok(WgpuBlit {
             surface,
             device,
             queue,
             config,
             pipeline,
+            bgl,
+            sampler,
             fb_texture,
             bind_group,
         })
     }
 
     /// Upload the software framebuffer as a texture, then blit it fullscreen.
-    pub fn upload_and_present(&mut self, fb: &Framebuffer) {
-        // Reinterpret u32 pixels as raw bytes for write_texture
-        let bytes: &[u8] = bytemuck::cast_slice(&fb.pixels);
-
-        self.queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture:   &self.fb_texture,
-                mip_level: 0,
-                origin:    wgpu::Origin3d::ZERO,
-                aspect:    wgpu::TextureAspect::All,
-            },
-            bytes,
-            wgpu::TexelCopyBufferLayout {
-                offset:         0,
-                bytes_per_row:  Some((FB_W * 4) as u32),
-                rows_per_image: Some(FB_H as u32),
-            },
-            wgpu::Extent3d {
-                width:                 FB_W as u32,
-                height:                FB_H as u32,
-                depth_or_array_layers: 1,
-            },
-        );
+    /// The framebuffer may be at any render resolution; the blit rescales it to the canvas.
+    /// `changed` is the region that differs from the last upload.
+    pub fn upload_and_present(&mut self, fb: &Framebuffer, changed: Option<DirtyRect>) {
+        let (width, height) = (fb.width as u32, fb.height as u32);
+        let mut upload = changed;
+        if self.fb_texture.width() != width || self.fb_texture.height() != height {
+            let (tex, bg) = create_fb_texture(&self.device, &self.bgl, &self.sampler, width, height);
+            self.fb_texture = tex;
+            self.bind_group = bg;
+            // Fresh texture: upload everything, not just what changed
+            upload = Some(DirtyRect { x0: 0, y0: 0, x1: fb.width - 1, y1: fb.height - 1 });
+        }
 
-        let frame = match self.surface.get_current_texture() {
-            Ok(f) => f,
-            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-                self.surface.configure(&self.device, &self.config);
-                return;
-            }
-            Err(_) => return,
+        // Upload only the rectangle that differs from the last presented frame.
+        // Reinterpret u32 pixels as raw bytes for write_texture; the layout offset skips to
+        // the rectangle's first texel and rows keep the full framebuffer stride.
+        if let Some(r) = upload {
+            let bytes: &[u8] = bytemuck::cast_slice(fb.pixels());
+
+            self.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture:   &self.fb_texture,
+                    mip_level: 0,
+                    origin:    wgpu::Origin3d { x: r.x0 as u32, y: r.y0 as u32, z: 0 },
+                    aspect:    wgpu::TextureAspect::All,
+                },
+                bytes,
+                wgpu::TexelCopyBufferLayout {
+                    offset:         ((r.y0 * fb.width + r.x0) * 4) as u64,
+                    bytes_per_row:  Some(width * 4),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width:  (r.x1 - r.x0 + 1) as u32,
+                    height: (r.y1 - r.y0 + 1) as u32,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let (surface, device, config) = (&self.surface, &self.device, &self.config);
+        let Some(frame) = acquire_with_retry(
+            || surface.get_current_texture(),
+            || surface.configure(device, config),
+        ) else {
+            return;
         };
 
         let view = frame.texture.create_view(&Default::default());
@@ -320,3 +521,61 @@ impl GsDisplay {
         frame.present();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outdated_surface_is_reconfigured_and_retried() {
+        let mut results = vec![Ok(7), Err(wgpu::SurfaceError::Outdated)];
+        let mut reconfigured = false;
+        let frame = acquire_with_retry(|| results.pop().unwrap(), || reconfigured = true);
+        assert_eq!(frame, Some(7));
+        assert!(reconfigured);
+    }
+
+    #[test]
+    fn other_surface_errors_drop_the_frame() {
+        let mut reconfigured = false;
+        let frame: Option<u32> =
+            acquire_with_retry(|| Err(wgpu::SurfaceError::Timeout), || reconfigured = true);
+        assert_eq!(frame, None);
+        assert!(!reconfigured);
+    }
+
+    #[test]
+    fn adapter_without_formats_is_a_clean_error() {
+        let caps = wgpu::SurfaceCapabilities {
+            alpha_modes: vec![wgpu::CompositeAlphaMode::Opaque],
+            ..Default::default()
+        };
+        let err = choose_surface_format(&caps).unwrap_err();
+        assert!(err.contains("no texture formats"), "{err}");
+
+        let caps = wgpu::SurfaceCapabilities {
+            formats: vec![wgpu::TextureFormat::Bgra8Unorm, wgpu::TextureFormat::Bgra8UnormSrgb],
+            ..caps
+        };
+        assert_eq!(
+            choose_surface_format(&caps),
+            Ok((wgpu::TextureFormat::Bgra8UnormSrgb, wgpu::CompositeAlphaMode::Opaque)),
+        );
+    }
+
+    #[test]
+    fn failed_wgpu_start_falls_back_to_the_2d_canvas() {
+        let mut seen = None;
+        let picked = with_fallback(Err("request_adapter: no adapter".into()), |e| {
+            seen = Some(e.to_string());
+            Ok("canvas2d")
+        });
+        assert_eq!(picked, Ok("canvas2d"));
+        assert_eq!(seen.as_deref(), Some("request_adapter: no adapter"));
+
+        // A working wgpu never touches the canvas; if both fail, both are reported
+        assert_eq!(with_fallback(Ok("wgpu"), |_| unreachable!()), Ok("wgpu"));
+        let both = with_fallback::<()>(Err("no adapter".into()), |_| Err("no 2D context".into()));
+        assert_eq!(both, Err("no adapter; 2D canvas fallback: no 2D context".into()));
+    }
+}
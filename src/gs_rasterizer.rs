@@ -2,67 +2,279 @@
 // Maps to: PS2 GS fixed-function rasterizer writing to 4 MB eDRAM.
 // Implements Pineda edge-function rasterization with Gouraud interpolation.
 
+/// Display resolution (PS2 NTSC 640×448). The render resolution may differ, see `with_size`.
 pub const FB_W: usize = 640;
 pub const FB_H: usize = 448;
 
-/// Software framebuffer — 640×448 RGBA pixels stored as 0xAA_BB_GG_RR (ABGR little-endian).
+/// Inclusive pixel rectangle [x0, x1] × [y0, y1].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl DirtyRect {
+    fn union(self, o: DirtyRect) -> DirtyRect {
+        DirtyRect {
+            x0: self.x0.min(o.x0),
+            y0: self.y0.min(o.y0),
+            x1: self.x1.max(o.x1),
+            y1: self.y1.max(o.y1),
+        }
+    }
+}
+
+fn union_opt(a: Option<DirtyRect>, b: Option<DirtyRect>) -> Option<DirtyRect> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(b)),
+        _ => a.or(b),
+    }
+}
+
+/// Software framebuffer — RGBA pixels stored as 0xAA_BB_GG_RR (ABGR little-endian).
+///
+/// Tracks which pixels can differ from the clear color (`dirty`, drawn since the last
+/// clear) and from the image last handed to the display (`unpresented`, drawn or cleared
+/// since), so `clear` and the texture upload only touch that region instead of the whole
+/// buffer. The draw functions here record what they write; any other writer goes through
+/// `pixels_mut`, which gives up and marks the whole buffer.
 pub struct Framebuffer {
-    pub pixels: Vec<u32>,
+    pixels: Vec<u32>,
+    /// GS alpha-correction edge flag per pixel: set where the last triangle drawn there was
+    /// an AA1 primitive covering only part of the pixel, for a later compositing pass.
+    /// Cleared along with the pixels.
+    pub edge_flags: Vec<bool>,
+    pub width:  usize,
+    pub height: usize,
+    clear_color: u32,
+    dirty:       Option<DirtyRect>,
+    unpresented: Option<DirtyRect>,
 }
 
 impl Framebuffer {
     pub fn new() -> Self {
-        Framebuffer {
-            pixels: vec![0xFF_08_0A_14; FB_W * FB_H],
-        }
+        Self::with_size(FB_W, FB_H)
+    }
+
+    /// Framebuffer at an internal render resolution; GsDisplay rescales it during blit.
+    pub fn with_size(width: usize, height: usize) -> Self {
+        let mut fb = Framebuffer {
+            pixels: vec![0xFF_08_0A_14; width * height],
+            edge_flags: vec![false; width * height],
+            width,
+            height,
+            clear_color: 0xFF_08_0A_14,
+            dirty:       None,
+            unpresented: None,
+        };
+        // Nothing has been uploaded yet: the first upload must cover everything
+        fb.unpresented = fb.full_rect();
+        fb
+    }
+
+    fn full_rect(&self) -> Option<DirtyRect> {
+        (self.width > 0 && self.height > 0).then(|| DirtyRect {
+            x0: 0,
+            y0: 0,
+            x1: self.width - 1,
+            y1: self.height - 1,
+        })
     }
 
     /// Clear to a given ABGR color (e.g. 0xFF_14_0A_08 = dark blue-ish PS2 bg).
+    /// Only the region drawn since the last clear is refilled when the color is unchanged.
     pub fn clear(&mut self, color: u32) {
-        self.pixels.fill(color);
+        if color != self.clear_color {
+            self.pixels.fill(color);
+            self.edge_flags.fill(false);
+            self.clear_color = color;
+            self.unpresented = self.full_rect();
+        } else if let Some(r) = self.dirty {
+            for y in r.y0..=r.y1 {
+                let row = y * self.width;
+                self.pixels[row + r.x0..=row + r.x1].fill(color);
+                self.edge_flags[row + r.x0..=row + r.x1].fill(false);
+            }
+            self.unpresented = union_opt(self.unpresented, Some(r));
+        }
+        self.dirty = None;
+    }
+
+    /// Record that pixels inside [x0, x1] × [y0, y1] (clamped to the buffer) were written.
+    fn mark_dirty(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        let (x0, y0) = (x0.max(0), y0.max(0));
+        let (x1, y1) = (x1.min(self.width as i32 - 1), y1.min(self.height as i32 - 1));
+        if x0 > x1 || y0 > y1 {
+            return;
+        }
+        let r = DirtyRect { x0: x0 as usize, y0: y0 as usize, x1: x1 as usize, y1: y1 as usize };
+        self.dirty = union_opt(self.dirty, Some(r));
+        self.unpresented = union_opt(self.unpresented, Some(r));
+    }
+
+    /// The pixels, row-major.
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// The pixels for writing outside the draw functions. The writes can't be tracked, so
+    /// the next clear refills and the next upload covers the whole buffer.
+    pub fn pixels_mut(&mut self) -> &mut [u32] {
+        self.dirty = self.full_rect();
+        self.unpresented = self.full_rect();
+        &mut self.pixels
+    }
+
+    /// True if anything was drawn since the last clear.
+    pub fn drawn(&self) -> bool {
+        self.dirty.is_some()
+    }
+
+    /// The pixels as row-major RGBA bytes, optionally sRGB-encoded (see `srgb_encode`).
+    pub fn to_rgba8(&self, srgb: bool) -> Vec<u8> {
+        let lut: [u8; 256] =
+            std::array::from_fn(|c| if srgb { srgb_encode(c as u8) } else { c as u8 });
+        self.pixels
+            .iter()
+            .flat_map(|p| {
+                let [r, g, b, a] = p.to_le_bytes();
+                [lut[r as usize], lut[g as usize], lut[b as usize], a]
+            })
+            .collect()
+    }
+
+    /// Region that may differ from the image last taken for display: everything drawn or
+    /// cleared since. Taking it starts the next region empty, so GsDisplay calls this once
+    /// per present and uploads just this rectangle.
+    pub fn take_changed_rect(&mut self) -> Option<DirtyRect> {
+        self.unpresented.take()
     }
 }
 
+/// Encode a linear 8-bit channel value with the sRGB transfer function, as an sRGB surface
+/// does when the Rgba8Unorm framebuffer is blitted to it (linear 128 → sRGB 188).
+pub fn srgb_encode(c: u8) -> u8 {
+    let l = c as f32 / 255.0;
+    let e = if l <= 0.003_130_8 { l * 12.92 } else { 1.055 * l.powf(1.0 / 2.4) - 0.055 };
+    (e * 255.0 + 0.5) as u8
+}
+
 /// A single GIF-decoded vertex ready for the rasterizer.
 pub use crate::gif::GifVertex;
 
+/// Gouraud color interpolation precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorInterp {
+    /// Per-pixel float barycentrics, truncated to 8 bits — the smoothest result.
+    #[default]
+    Float,
+    /// As Float but rounded to nearest, so a flat-colored triangle reproduces its vertex
+    /// color exactly despite float error in the weights — what `rasterize_sprite` fills.
+    FloatRounded,
+    /// GS-style: per-triangle fixed-point color gradients accumulated across each span
+    /// and truncated to 8 bits, giving the hardware's slight darkening and banding.
+    GsFixed,
+}
+
+/// Fractional bits of the GsFixed color gradients and accumulators.
+const GS_COLOR_FRAC: u32 = 12;
+
 /// Rasterize one triangle using the Pineda edge-function algorithm with Gouraud shading.
 /// GifVertex x/y are already in pixel coordinates (decoded from GS 12.4 fixed-point).
-pub fn rasterize_triangle(fb: &mut Framebuffer, v0: &GifVertex, v1: &GifVertex, v2: &GifVertex) {
-    // Bounding box clamped to framebuffer extent
-    let min_x = v0.x.min(v1.x).min(v2.x).max(0) as usize;
-    let min_y = v0.y.min(v1.y).min(v2.y).max(0) as usize;
-    let max_x = (v0.x.max(v1.x).max(v2.x) as usize).min(FB_W - 1);
-    let max_y = (v0.y.max(v1.y).max(v2.y) as usize).min(FB_H - 1);
+///
+/// With `pixel_center` each pixel is sampled at (px + 0.5, py + 0.5) like the GS, so a
+/// triangle spanning x0..x1 covers pixels x0..x1-1; without it, at the integer corner
+/// (px, py), which also fills the right and bottom edge pixels.
+///
+/// With `aa1` (PRIM.AA1) every drawn pixel whose square [px, px+1] × [py, py+1] the
+/// triangle only partly covers gets its edge flag set; otherwise drawn pixels clear it.
+pub fn rasterize_triangle(
+    fb:     &mut Framebuffer,
+    v0:     &GifVertex,
+    v1:     &GifVertex,
+    v2:     &GifVertex,
+    interp: ColorInterp,
+    pixel_center: bool,
+    aa1:    bool,
+) {
+    // Bounding box clamped to framebuffer extent, in i32 so one entirely left of or
+    // above the buffer comes out empty rather than wrapping to the far edge
+    let min_x = v0.x.min(v1.x).min(v2.x).max(0);
+    let min_y = v0.y.min(v1.y).min(v2.y).max(0);
+    let max_x = v0.x.max(v1.x).max(v2.x).min(fb.width as i32 - 1);
+    let max_y = v0.y.max(v1.y).max(v2.y).min(fb.height as i32 - 1);
 
     // Edge function: e(a,b,p) = (bx-ax)*(py-ay) - (by-ay)*(px-ax)
     // Positive means p is to the left of a→b (CCW convention).
-    let edge = |ax: i32, ay: i32, bx: i32, by: i32, px: i32, py: i32| -> i32 {
+    // Evaluated in i64: products of i32 coordinate differences overflow i32 once a
+    // triangle spans more than ~46k units (e.g. off-screen vertices of a clipped mesh).
+    let edge = |ax: i64, ay: i64, bx: i64, by: i64, px: i64, py: i64| -> i64 {
         (bx - ax) * (py - ay) - (by - ay) * (px - ax)
     };
 
+    // Coverage is tested on a half-pixel grid so the centre offset stays exact in integers:
+    // vertices and sample points are both doubled, and the centre adds one half-pixel step.
+    let half = pixel_center as i64;
+    let [(x0, y0), (x1, y1), (x2, y2)] =
+        [v0, v1, v2].map(|v| (2 * v.x as i64, 2 * v.y as i64));
+    let inside = |sx: i64, sy: i64| {
+        edge(x1, y1, x2, y2, sx, sy) <= 0
+            && edge(x2, y2, x0, y0, sx, sy) <= 0
+            && edge(x0, y0, x1, y1, sx, sy) <= 0
+    };
+
     // Signed area × 2 — used to normalise barycentric weights.
-    let area2 = edge(v0.x, v0.y, v1.x, v1.y, v2.x, v2.y);
+    let area2 = edge(
+        v0.x as i64, v0.y as i64, v1.x as i64, v1.y as i64, v2.x as i64, v2.y as i64,
+    );
 
     // The viewport Y-flip (screen.y = (1-ndc.y)*H) reverses winding from CCW-NDC to CW-screen.
     // Front-facing triangles (CCW in 3D/NDC) therefore have area2 < 0 in screen space.
     // Cull degenerate (area2==0) and back-facing (area2>0, i.e. CW-NDC) triangles.
-    if area2 >= 0 {
+    if area2 >= 0 || min_x > max_x || min_y > max_y {
         return;
     }
+    fb.mark_dirty(min_x, min_y, max_x, max_y);
+    let [min_x, min_y, max_x, max_y] = [min_x, min_y, max_x, max_y].map(|c| c as usize);
 
-    // area2 is negative; use its absolute value for normalisation.
-    let area2f = (-area2) as f32;
+    // area2 is negative; use its absolute value for normalisation, ×4 for the doubled grid.
+    let area2f = (-area2 * 4) as f32;
+
+    // GsFixed: d(color)/dx and d(color)/dy per channel from the color plane equation,
+    // quantised once at setup like the GS's triangle setup unit.
+    let chans = [
+        [v0.r, v1.r, v2.r],
+        [v0.g, v1.g, v2.g],
+        [v0.b, v1.b, v2.b],
+    ];
+    let grads = chans.map(|[c0, c1, c2]| {
+        let (dc1, dc2) = (c1 as i64 - c0 as i64, c2 as i64 - c0 as i64);
+        let dx = dc1 * (v2.y as i64 - v0.y as i64) - dc2 * (v1.y as i64 - v0.y as i64);
+        let dy = dc2 * (v1.x as i64 - v0.x as i64) - dc1 * (v2.x as i64 - v0.x as i64);
+        ((dx << GS_COLOR_FRAC) / area2, (dy << GS_COLOR_FRAC) / area2)
+    });
 
     for py in min_y..=max_y {
+        // GsFixed span accumulators, stepped by d/dx per pixel from the left edge of the bbox
+        // (plus half a step each way when sampling at the pixel centre)
+        let mut acc: [i64; 3] = std::array::from_fn(|i| {
+            let (ddx, ddy) = grads[i];
+            ((chans[i][0] as i64) << GS_COLOR_FRAC)
+                + ddx * (min_x as i64 - v0.x as i64)
+                + ddy * (py as i64 - v0.y as i64)
+                + half * ((ddx + ddy) >> 1)
+        });
+        let sy = 2 * py as i64 + half;
+
         for px in min_x..=max_x {
-            let px_i = px as i32;
-            let py_i = py as i32;
+            let sx = 2 * px as i64 + half;
 
             // Barycentric weights — negative for CW (front-facing) triangles in screen space.
-            let w0 = edge(v1.x, v1.y, v2.x, v2.y, px_i, py_i);
-            let w1 = edge(v2.x, v2.y, v0.x, v0.y, px_i, py_i);
-            let w2 = edge(v0.x, v0.y, v1.x, v1.y, px_i, py_i);
+            let w0 = edge(x1, y1, x2, y2, sx, sy);
+            let w1 = edge(x2, y2, x0, y0, sx, sy);
+            let w2 = edge(x0, y0, x1, y1, sx, sy);
 
             // Inside test: all weights ≤ 0 (they are negative for interior points of CW triangles).
             if w0 <= 0 && w1 <= 0 && w2 <= 0 {
@@ -71,15 +283,313 @@ pub fn rasterize_triangle(fb: &mut Framebuffer, v0: &GifVertex, v1: &GifVertex,
                 let b1 = (-w1) as f32 / area2f;
                 let b2 = (-w2) as f32 / area2f;
 
-                // Gouraud-interpolate RGBA
-                let r = (b0 * v0.r as f32 + b1 * v1.r as f32 + b2 * v2.r as f32) as u32;
-                let g = (b0 * v0.g as f32 + b1 * v1.g as f32 + b2 * v2.g as f32) as u32;
-                let b = (b0 * v0.b as f32 + b1 * v1.b as f32 + b2 * v2.b as f32) as u32;
+                let (r, g, b) = match interp {
+                    // Gouraud-interpolate RGBA
+                    ColorInterp::Float => (
+                        (b0 * v0.r as f32 + b1 * v1.r as f32 + b2 * v2.r as f32) as u32,
+                        (b0 * v0.g as f32 + b1 * v1.g as f32 + b2 * v2.g as f32) as u32,
+                        (b0 * v0.b as f32 + b1 * v1.b as f32 + b2 * v2.b as f32) as u32,
+                    ),
+                    ColorInterp::FloatRounded => (
+                        (b0 * v0.r as f32 + b1 * v1.r as f32 + b2 * v2.r as f32 + 0.5) as u32,
+                        (b0 * v0.g as f32 + b1 * v1.g as f32 + b2 * v2.g as f32 + 0.5) as u32,
+                        (b0 * v0.b as f32 + b1 * v1.b as f32 + b2 * v2.b as f32 + 0.5) as u32,
+                    ),
+                    // Truncate the accumulators; the quantised gradients can drift a step
+                    // outside the vertex range, hence the clamp
+                    ColorInterp::GsFixed => {
+                        let c = acc.map(|v| (v >> GS_COLOR_FRAC).clamp(0, 255) as u32);
+                        (c[0], c[1], c[2])
+                    }
+                };
 
                 // Pack as 0xFF_BB_GG_RR
                 let pixel = 0xFF00_0000 | (b << 16) | (g << 8) | r;
-                fb.pixels[py * FB_W + px] = pixel;
+                fb.pixels[py * fb.width + px] = pixel;
+
+                // The triangle is convex, so it covers the whole pixel square exactly when
+                // it contains all four corners
+                let (cx, cy) = (2 * px as i64, 2 * py as i64);
+                fb.edge_flags[py * fb.width + px] = aa1
+                    && ![(cx, cy), (cx + 2, cy), (cx, cy + 2), (cx + 2, cy + 2)]
+                        .into_iter()
+                        .all(|(x, y)| inside(x, y));
+            }
+
+            for (a, (ddx, _)) in acc.iter_mut().zip(&grads) {
+                *a += ddx;
+            }
+        }
+    }
+}
+
+/// Assemble a triangle strip into a triangle list: triangle i is vertices i, i+1, i+2,
+/// with the first two swapped on odd i so every triangle keeps the strip's winding.
+/// Zero-area triangles are dropped: disjoint strips are joined by repeating a vertex at
+/// each end (a degenerate restart), and those joins must not draw or count as primitives.
+pub fn expand_triangle_strip(strip: &[GifVertex]) -> Vec<GifVertex> {
+    let mut out = Vec::with_capacity(strip.len().saturating_sub(2) * 3);
+    for (i, w) in strip.windows(3).enumerate() {
+        let tri = if i % 2 == 0 { [&w[0], &w[1], &w[2]] } else { [&w[1], &w[0], &w[2]] };
+        let d = |a: i32, b: i32| b as i64 - a as i64;
+        let area2 = d(tri[0].x, tri[1].x) * d(tri[0].y, tri[2].y)
+            - d(tri[0].y, tri[1].y) * d(tri[0].x, tri[2].x);
+        if area2 != 0 {
+            out.extend(tri.map(GifVertex::clone));
+        }
+    }
+    out
+}
+
+/// True if the triangle is front-facing in screen space (CW after the viewport Y-flip,
+/// area2 < 0) — the same test `rasterize_triangle` uses to cull.
+pub fn is_front_facing(v0: &GifVertex, v1: &GifVertex, v2: &GifVertex) -> bool {
+    let d = |a: i32, b: i32| b as i64 - a as i64;
+    d(v0.x, v1.x) * d(v0.y, v2.y) - d(v0.y, v1.y) * d(v0.x, v2.x) < 0
+}
+
+/// Rasterize a GS SPRITE: an axis-aligned rectangle spanned by two corner vertices,
+/// flat-filled with the color of the second vertex. Coverage matches `rasterize_triangle`
+/// with the same `pixel_center`: the far corner is exclusive when sampling at pixel
+/// centres and inclusive otherwise.
+pub fn rasterize_sprite(fb: &mut Framebuffer, v0: &GifVertex, v1: &GifVertex, pixel_center: bool) {
+    let far = pixel_center as i32;
+    let min_x = v0.x.min(v1.x).max(0);
+    let min_y = v0.y.min(v1.y).max(0);
+    let max_x = (v0.x.max(v1.x) - far).min(fb.width as i32 - 1);
+    let max_y = (v0.y.max(v1.y) - far).min(fb.height as i32 - 1);
+    if min_x > max_x || min_y > max_y {
+        return;
+    }
+
+    fb.mark_dirty(min_x, min_y, max_x, max_y);
+
+    let pixel = 0xFF00_0000 | ((v1.b as u32) << 16) | ((v1.g as u32) << 8) | v1.r as u32;
+    for py in min_y as usize..=max_y as usize {
+        let row = py * fb.width;
+        fb.pixels[row + min_x as usize..=row + max_x as usize].fill(pixel);
+    }
+}
+
+/// `v` with its color blended toward the clear color (standing in for FOGCOL) by its fog
+/// coefficient: f/255 of the vertex color plus (255-f)/255 of the fog color. The GS blends
+/// each pixel after interpolating F; blending at the vertices and interpolating the result
+/// differs only in a second-order term across a triangle.
+pub fn fog_vertex(fb: &Framebuffer, v: &GifVertex) -> GifVertex {
+    let f = v.fog as u32;
+    let mix = |c: u8, shift: u32| {
+        ((c as u32 * f + ((fb.clear_color >> shift) & 0xFF) * (255 - f) + 127) / 255) as u8
+    };
+    GifVertex { r: mix(v.r, 0), g: mix(v.g, 8), b: mix(v.b, 16), ..v.clone() }
+}
+
+/// Rasterize a GS LINE between two vertices, flat-colored with the second vertex's color
+/// (the GS's flat-shading convention, as for sprites).
+pub fn rasterize_line(fb: &mut Framebuffer, v0: &GifVertex, v1: &GifVertex) {
+    let pixel = 0xFF00_0000 | ((v1.b as u32) << 16) | ((v1.g as u32) << 8) | v1.r as u32;
+    draw_line(fb, v0.x, v0.y, v1.x, v1.y, pixel);
+}
+
+/// Draw a 1-pixel line with Bresenham's algorithm; pixels outside the framebuffer are skipped.
+pub fn draw_line(fb: &mut Framebuffer, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+    fb.mark_dirty(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1));
+
+    loop {
+        if x >= 0 && y >= 0 && (x as usize) < fb.width && (y as usize) < fb.height {
+            fb.pixels[y as usize * fb.width + x as usize] = color;
+        }
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy { err += dy; x += sx; }
+        if e2 <= dx { err += dx; y += sy; }
+    }
+}
+
+/// Outline the axis-aligned rectangle [min_x, max_x] × [min_y, max_y].
+pub fn draw_rect(fb: &mut Framebuffer, min_x: i32, min_y: i32, max_x: i32, max_y: i32, color: u32) {
+    draw_line(fb, min_x, min_y, max_x, min_y, color);
+    draw_line(fb, max_x, min_y, max_x, max_y, color);
+    draw_line(fb, max_x, max_y, min_x, max_y, color);
+    draw_line(fb, min_x, max_y, min_x, min_y, color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: i32, y: i32, r: u8) -> GifVertex {
+        GifVertex { r, g: 0, b: 0, a: 128, x, y, fog: 255 }
+    }
+
+    #[test]
+    fn gs_fixed_gradient_truncates_a_little_below_float() {
+        // An odd-sized red gradient, front-facing (clockwise on screen)
+        let tri = [v(3, 5, 7), v(11, 97, 251), v(131, 17, 90)];
+        let red = |interp| {
+            let mut fb = Framebuffer::with_size(140, 100);
+            rasterize_triangle(&mut fb, &tri[0], &tri[1], &tri[2], interp, true, false);
+            fb.pixels().iter().map(|p| (p & 0xFF) as i32).collect::<Vec<_>>()
+        };
+        let (float, fixed) = (red(ColorInterp::Float), red(ColorInterp::GsFixed));
+
+        // The quantised gradients lose a fraction of a step: GsFixed matches Float on
+        // nearly every pixel and otherwise lands exactly one step darker, never lighter
+        let steps: Vec<i32> = fixed.iter().zip(&float).map(|(f, g)| f - g).collect();
+        assert!(steps.iter().all(|&d| d == 0 || d == -1));
+        let darker = steps.iter().filter(|&&d| d == -1).count();
+        assert!(darker > 0 && darker < float.len() / 100, "{darker} darker pixels");
+    }
+
+    #[test]
+    fn huge_triangles_cover_exactly_their_inside() {
+        // Edge products of these coordinates (doubled for the half-pixel grid) are ~1e11,
+        // far past i32. The hypotenuse x + y = 64 cuts the buffer diagonally
+        let tri = [v(-100_000, -100_000, 200), v(-100_000, 100_064, 200), v(100_064, -100_000, 200)];
+        let mut fb = Framebuffer::with_size(64, 64);
+        rasterize_triangle(&mut fb, &tri[0], &tri[1], &tri[2], ColorInterp::Float, true, false);
+        for y in 0..64 {
+            for x in 0..64 {
+                // Pixel centres (x + ½, y + ½) on or below the hypotenuse
+                let inside = x + y < 64;
+                assert_eq!(fb.pixels()[y * 64 + x] != 0xFF_08_0A_14, inside, "({x}, {y})");
             }
         }
     }
+
+    #[test]
+    fn off_screen_triangles_leave_the_buffer_undrawn() {
+        let mut fb = Framebuffer::with_size(64, 64);
+        // Front-facing, entirely left of and entirely above the buffer
+        for tri in [
+            [v(-40, 10, 200), v(-40, 50, 200), v(-10, 10, 200)],
+            [v(10, -40, 200), v(10, -10, 200), v(50, -40, 200)],
+        ] {
+            rasterize_triangle(&mut fb, &tri[0], &tri[1], &tri[2], ColorInterp::Float, true, false);
+        }
+        assert!(!fb.drawn());
+        assert!(fb.pixels().iter().all(|&p| p == 0xFF_08_0A_14));
+    }
+
+    #[test]
+    fn pixel_centres_shift_coverage_off_the_far_edges() {
+        let tri = [v(2, 2, 200), v(2, 10, 200), v(10, 2, 200)];
+        let covered = |pixel_center| {
+            let mut fb = Framebuffer::with_size(16, 16);
+            let interp = ColorInterp::Float;
+            rasterize_triangle(&mut fb, &tri[0], &tri[1], &tri[2], interp, pixel_center, false);
+            fb.pixels().iter().map(|&p| p != 0xFF_08_0A_14).collect::<Vec<_>>()
+        };
+        let (centre, corner) = (covered(true), covered(false));
+        for y in 0..16 {
+            for x in 0..16 {
+                // Centres (x + ½, y + ½) against corners (x, y) inside x, y ≥ 2, x + y ≤ 12
+                let inner = x >= 2 && y >= 2;
+                assert_eq!(centre[y * 16 + x], inner && x + y < 12, "centre ({x}, {y})");
+                assert_eq!(corner[y * 16 + x], inner && x + y <= 12, "corner ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn dirty_tracking_matches_full_clears_and_uploads() {
+        const BG: u32 = 0xFF_08_0A_14;
+        let (w, h) = (48, 40);
+        let mut fb = Framebuffer::with_size(w, h);
+        // What the display shows: patched with each changed rect, like the texture upload
+        let mut shown = vec![0u32; w * h];
+
+        let mut seed = 7u32;
+        let mut rand = |n: i32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as i32 % n
+        };
+        for frame in 0..200 {
+            let bg = if frame % 50 == 49 { 0xFF_00_00_FF } else { BG };
+            fb.clear(bg);
+            assert!(fb.pixels().iter().all(|&p| p == bg), "frame {frame}");
+            assert!(fb.edge_flags.iter().all(|&e| !e), "frame {frame}");
+
+            for _ in 0..rand(4) {
+                let mut p = || v(rand(80) - 16, rand(70) - 15, rand(256) as u8);
+                let (a, b, c) = (p(), p(), p());
+                match rand(5) {
+                    0 => rasterize_triangle(&mut fb, &a, &b, &c, ColorInterp::Float, true, true),
+                    1 => rasterize_sprite(&mut fb, &a, &b, true),
+                    2 => rasterize_line(&mut fb, &a, &b),
+                    3 => draw_rect(&mut fb, a.x, a.y, b.x, b.y, 0xFF_FF_FF_FF),
+                    _ => fb.pixels_mut()[rand((w * h) as i32) as usize] = 0xFF_12_34_56,
+                }
+            }
+
+            // Some frames are never presented: their changes carry over to the next upload
+            if rand(3) != 0 {
+                if let Some(r) = fb.take_changed_rect() {
+                    for y in r.y0..=r.y1 {
+                        shown[y * w + r.x0..=y * w + r.x1]
+                            .copy_from_slice(&fb.pixels()[y * w + r.x0..=y * w + r.x1]);
+                    }
+                }
+                assert!(shown == fb.pixels(), "frame {frame}");
+            }
+        }
+    }
+
+    #[test]
+    fn srgb_capture_lifts_linear_mid_gray() {
+        let mut fb = Framebuffer::with_size(2, 2);
+        fb.clear(0x80_80_80_80);
+        assert_eq!(fb.to_rgba8(false)[..4], [128, 128, 128, 128]);
+        // Alpha is not a color channel and stays as it is
+        assert_eq!(fb.to_rgba8(true)[..4], [188, 188, 188, 128]);
+        assert_eq!((srgb_encode(0), srgb_encode(255)), (0, 255));
+    }
+
+    #[test]
+    fn aa1_flags_only_the_partly_covered_pixels() {
+        let tri = [v(2, 2, 200), v(2, 10, 200), v(10, 2, 200)];
+        let mut fb = Framebuffer::with_size(16, 16);
+        rasterize_triangle(&mut fb, &tri[0], &tri[1], &tri[2], ColorInterp::Float, true, true);
+        for y in 0..16 {
+            for x in 0..16 {
+                // Drawn where x + y < 12; the hypotenuse x + y = 12 cuts the squares of the
+                // outer diagonal, while the axis-aligned legs run along pixel boundaries
+                let drawn = x >= 2 && y >= 2 && x + y < 12;
+                assert_eq!(fb.pixels()[y * 16 + x] != 0xFF_08_0A_14, drawn, "({x}, {y})");
+                assert_eq!(fb.edge_flags[y * 16 + x], drawn && x + y == 11, "({x}, {y})");
+            }
+        }
+
+        // Without AA1 the same pixels leave no flags behind
+        rasterize_triangle(&mut fb, &tri[0], &tri[1], &tri[2], ColorInterp::Float, true, false);
+        assert!(fb.edge_flags.iter().all(|&e| !e));
+    }
+
+    #[test]
+    fn degenerate_restart_splits_a_strip_into_two_groups() {
+        // Two quads as strips (red 1 on the left, red 2 on the right), joined by repeating
+        // the last vertex of the first and the first of the second
+        let a = [v(0, 0, 1), v(0, 10, 1), v(10, 0, 1), v(10, 10, 1)];
+        let b = [v(50, 0, 2), v(50, 10, 2), v(60, 0, 2), v(60, 10, 2)];
+        let strip: Vec<GifVertex> =
+            a.iter().chain([&a[3], &b[0]]).chain(&b).cloned().collect();
+
+        let list = expand_triangle_strip(&strip);
+        let tris: Vec<&[GifVertex]> = list.chunks_exact(3).collect();
+        assert_eq!(tris.len(), 4);
+        let groups: Vec<u8> = tris.iter().map(|t| t[0].r).collect();
+        assert_eq!(groups, [1, 1, 2, 2]);
+        for t in &tris {
+            // None bridges the gap, and all keep the strip's winding
+            assert!(t.iter().all(|p| p.r == t[0].r), "{t:?}");
+            assert!(is_front_facing(&t[0], &t[1], &t[2]) == is_front_facing(&a[0], &a[1], &a[2]));
+        }
+    }
 }
@@ -1,17 +1,32 @@
 // emotion-cube — PS2 Emotion Engine WASM proof-of-concept.
 // PS2-faithful pipeline: EE → DMAC → VIF1 → VU1 → GIF → SW-GS → wgpu blit
 
+mod animation;
+mod clipper;
 mod dmac;
 mod ee;
 mod gif;
 mod gs_display;
 mod gs_rasterizer;
+mod profile;
+mod scene;
 mod vif1;
 mod vu1;
 mod vu1_program;
 
+use std::collections::VecDeque;
+
 use wasm_bindgen::prelude::*;
 
+use gif::GifVertex;
+
+/// Default per-frame triangle cap — far above the cube's 12, low enough to bound the
+/// work a malformed packet can cause.
+const DEFAULT_PRIM_LIMIT: usize = 65_536;
+
+/// Diagnostics kept for `drain_log`; the oldest are dropped beyond this.
+const LOG_CAPACITY: usize = 256;
+
 /// Top-level emulator core, exposed to JavaScript.
 ///
 /// Lifecycle (JS):
@@ -29,6 +44,27 @@ pub struct EmulatorCore {
     frame_count: u64,
     emu_cycles:  u64,
     vu1_mat_ops: u64,
+    vu1_cycles:  u64,                   // micro-program cycles run, all frames
+    debug_bbox:  bool,
+    wire_overlay: bool,
+    xy_offset:   (i32, i32),
+    frame_diff:  bool,
+    prev_pixels: Vec<u32>,
+    motion_blur: f32,                   // history weight; 0 = off
+    accum:       Vec<f32>,              // blurred RGB history, 3 floats per pixel
+    sprite_fast_path: bool,
+    color_interp: gs_rasterizer::ColorInterp,
+    color_clamp: gif::ColorClampMode,
+    pixel_center: bool,
+    prim_limit:  usize,
+    prims_dropped: bool,
+    empty_frame: bool,                  // no primitive reached the framebuffer
+    log:         VecDeque<String>,
+    vu1_halted:  bool,                  // stopped at a VU1 breakpoint mid-packet
+    prims:       gif::PrimBuffer,       // this frame's; kept across a breakpoint, drawn on resume
+    profile:     bool,
+    frame_profile: profile::FrameProfile, // accumulating for the frame in progress
+    last_profile: Option<profile::FrameProfile>,
 }
 
 #[wasm_bindgen]
@@ -41,17 +77,7 @@ impl EmulatorCore {
             .await
             .map_err(|e| JsValue::from_str(&e))?;
 
-        Ok(EmulatorCore {
-            ee:          ee::EmotionEngine::new(),
-            dmac:        dmac::Dmac::new(),
-            vif1:        vif1::Vif1::new(),
-            vu1:         vu1::Vu1::new(),
-            gs_fb:       gs_rasterizer::Framebuffer::new(),
-            gs_display,
-            frame_count: 0,
-            emu_cycles:  0,
-            vu1_mat_ops: 0,
-        })
+        Ok(EmulatorCore::with_display(gs_display))
     }
 
     /// Simulate one frame through the full PS2 pipeline.
@@ -64,45 +90,12 @@ impl EmulatorCore {
     /// 6. Software GS rasterizer → Framebuffer
     /// 7. wgpu texture blit → canvas
     ///
-    /// Returns telemetry: { emulatedCycles, vu1MatOps, frameCount }
+    /// Returns telemetry: { emulatedCycles, vu1MatOps, vu1Cycles, frameCount,
+    /// primitivesDropped, emptyFrame }; `vu1Cycles` totals the micro-program cycles VU1
+    /// has actually run, and `emptyFrame` is true when no primitive reached the framebuffer, which
+    /// shows only the clear color (e.g. every triangle was back-facing or off-screen).
     pub fn step_frame(&mut self) -> JsValue {
-        // 1. EE: build VIF1 packet, kick DMAC (300 MHz / 60 fps ≈ 5M cycles/frame)
-        let (madr, qwc) = self.ee.build_packet();
-        self.emu_cycles += 300_000;
-        self.dmac.kick(madr, qwc);
-
-        // 2. DMAC: transfer EE RAM → VIF1 FIFO
-        self.dmac.transfer(&*self.ee.ee_ram, &mut self.vif1.fifo);
-
-        // 3. VIF1: parse packet → VU1 data memory
-        self.vif1.process(&mut self.vu1.data_mem);
-
-        // 4. VU1: run micro-program until XGKICK
-        self.vu1.pc = self.vif1.mscal_addr.take().unwrap_or(0);
-        let xgkick_base = self.vu1.run_until_xgkick();
-        self.vu1_mat_ops += 5; // 3 mat-mul + 2 rotation = 5 per frame
-
-        // 5. GIF: parse tag + vertex data from VU data memory
-        // xgkick_base = VI[05] = 108 (GIF tag QW address in VU data memory)
-        let prims = gif::parse_gif_packet(&self.vu1.data_mem, xgkick_base as usize);
-
-        // 6. GS rasterizer: clear then draw triangles
-        self.gs_fb.clear(0xFF_08_0A_14); // PS2-ish dark bg
-        for prim in &prims {
-            for tri in prim.vertices.chunks(3) {
-                if tri.len() == 3 {
-                    gs_rasterizer::rasterize_triangle(
-                        &mut self.gs_fb,
-                        &tri[0], &tri[1], &tri[2],
-                    );
-                }
-            }
-        }
-
-        // 7. Upload framebuffer texture and blit to canvas
-        self.gs_display.upload_and_present(&self.gs_fb);
-
-        self.frame_count += 1;
+        self.advance_frame();
 
         // Telemetry
         let obj = js_sys::Object::new();
@@ -114,10 +107,1828 @@ impl EmulatorCore {
             &obj, &JsValue::from_str("vu1MatOps"),
             &JsValue::from_f64(self.vu1_mat_ops as f64),
         );
+        let _ = js_sys::Reflect::set(
+            &obj, &JsValue::from_str("vu1Cycles"),
+            &JsValue::from_f64(self.vu1_cycles as f64),
+        );
         let _ = js_sys::Reflect::set(
             &obj, &JsValue::from_str("frameCount"),
             &JsValue::from_f64(self.frame_count as f64),
         );
+        let _ = js_sys::Reflect::set(
+            &obj, &JsValue::from_str("primitivesDropped"),
+            &JsValue::from_bool(self.prims_dropped),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj, &JsValue::from_str("emptyFrame"),
+            &JsValue::from_bool(self.empty_frame),
+        );
+        obj.into()
+    }
+
+    /// Re-rasterize and present the current frame without advancing the animation, where
+    /// pausing (not calling `step_frame`) would redraw nothing. The primitives the frame
+    /// decoded are drawn again, with nothing recomputed by the EE or VU1, so the cube keeps
+    /// its orientation while rasterizer settings (wireframe overlay, bounding boxes, color
+    /// interpolation, fill convention) update live. Does nothing while VU1 is halted at a
+    /// breakpoint, where the frame is still incomplete.
+    pub fn hold_frame(&mut self) {
+        if self.vu1_halted {
+            return;
+        }
+        self.draw_frame_prims();
+        self.gs_display.upload_and_present(&mut self.gs_fb);
+        self.frame_count += 1;
+    }
+
+    /// List the instructions of `program` that the VU1 interpreter does not implement,
+    /// as a JS array of `[pc, description]` pairs (empty if everything is supported).
+    pub fn check_micro_program_support(program: &[u64]) -> js_sys::Array {
+        vu1::unsupported_ops(program)
+            .into_iter()
+            .map(|(pc, op)| {
+                js_sys::Array::of2(&JsValue::from_f64(pc as f64), &JsValue::from_str(&op))
+            })
+            .collect()
+    }
+
+    /// Feed a raw VIF1 DMA packet (QW-aligned bytes) through the pipeline instead of the
+    /// EE-built cube packet. If the packet issues MSCAL (or MSCNT, continuing the previous
+    /// micro-program batch), the XGKICK output is rasterized and presented; a data-only
+    /// packet just updates VU1 memory.
+    pub fn submit_vif_packet(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        if self.vu1_halted {
+            return Err(JsValue::from_str("VU1 is halted at a breakpoint; call vu1_continue first"));
+        }
+        let (madr, qwc) = self.ee.load_packet(bytes).map_err(|e| JsValue::from_str(&e))?;
+        if self.run_packet(madr, qwc) {
+            self.gs_display.upload_and_present(&mut self.gs_fb);
+            self.frame_count += 1;
+        }
+        Ok(())
+    }
+
+    /// The framebuffer as raw RGBA bytes, `capture_width` × `capture_height`, row 0 at the
+    /// top. These are the values the rasterizer wrote, which the blit treats as linear:
+    /// on an sRGB canvas (the usual case) the browser shows them sRGB-encoded, so a PNG
+    /// of these bytes looks darker than the screen. See `capture_frame_srgb`.
+    pub fn capture_frame(&self) -> Vec<u8> {
+        self.gs_fb.to_rgba8(false)
+    }
+
+    /// The framebuffer like `capture_frame`, with RGB sRGB-encoded to match what an sRGB
+    /// canvas displays, for saving as a correct-looking PNG. Alpha is unchanged.
+    pub fn capture_frame_srgb(&self) -> Vec<u8> {
+        self.gs_fb.to_rgba8(true)
+    }
+
+    /// Render animation frame `frame` once through the whole software pipeline and return
+    /// it as `capture_frame` bytes, e.g. for thumbnails. Nothing is presented and the
+    /// animation does not advance. The still is drawn into a scratch framebuffer with
+    /// motion blur, frame diff and VU1 breakpoints suspended, and the live frame's
+    /// primitives, status flags, diagnostics and counters are put back afterwards, so the
+    /// canvas and the next `step_frame` are unaffected. Returns an empty buffer while VU1
+    /// is halted at a breakpoint.
+    pub fn render_still(&mut self, frame: u64) -> Vec<u8> {
+        if self.vu1_halted {
+            return Vec::new();
+        }
+        self.offscreen(|core| {
+            let (madr, qwc) = core.ee.build_packet_for_frame(frame);
+            core.run_packet(madr, qwc);
+        })
+        .to_rgba8(false)
+    }
+
+    /// Run animation frames 0..`frames` through the software pipeline headless (EE to GS,
+    /// nothing presented) and time them, returning `{ frames, totalMs, fps, avgFrameUs }`.
+    /// Like `render_still`, frames go into a scratch framebuffer with the live frame's
+    /// state set aside, and the animation does not advance, so every run does the same
+    /// work. Returns null while VU1 is halted at a breakpoint.
+    pub fn benchmark(&mut self, frames: u32) -> JsValue {
+        if self.vu1_halted {
+            return JsValue::NULL;
+        }
+        let result = self.run_benchmark(frames, profile::now_ms);
+
+        let obj = js_sys::Object::new();
+        let fields: [(&str, f64); 4] = [
+            ("frames",     frames as f64),
+            ("totalMs",    result.total_ms),
+            ("fps",        result.fps()),
+            ("avgFrameUs", result.avg_frame_us()),
+        ];
+        for (name, value) in fields {
+            let _ = js_sys::Reflect::set(&obj, &JsValue::from_str(name), &JsValue::from_f64(value));
+        }
+        obj.into()
+    }
+
+    /// Width of the `capture_frame` image: the render resolution, not the display's.
+    pub fn capture_width(&self) -> u32 {
+        self.gs_fb.width as u32
+    }
+
+    /// Height of the `capture_frame` image.
+    pub fn capture_height(&self) -> u32 {
+        self.gs_fb.height as u32
+    }
+
+    /// Run the frame the next `step_frame` draws up to the GIF stage and return its
+    /// decoded triangles as SVG, one `<polygon>` each in framebuffer pixel coordinates,
+    /// filled with the average of the vertex colors. Back-facing triangles (culled by the
+    /// rasterizer) are kept as dashed outlines with class `back`; sprites become
+    /// four-corner polygons and lines `<line>` elements. The animation does not advance
+    /// and the framebuffer is not touched. Returns an empty string while VU1 is halted at
+    /// a breakpoint.
+    pub fn export_frame_svg(&mut self) -> String {
+        if self.vu1_halted {
+            return String::new();
+        }
+        // The live frame's primitives stay for hold_frame to redraw
+        let live = std::mem::take(&mut self.prims);
+        let (madr, qwc) = self.ee.build_packet_for_frame(self.ee.next_frame());
+        let kicked = self.run_to_gif(madr, qwc);
+        let frame = std::mem::replace(&mut self.prims, live);
+        let prims = if kicked { frame.as_slice() } else { &[] };
+
+        let (w, h) = (self.gs_fb.width, self.gs_fb.height);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\">\n\
+             <style>.back {{ fill: none; stroke: #888; stroke-dasharray: 4 2; }}</style>\n\
+             <rect width=\"{w}\" height=\"{h}\" fill=\"#140a08\"/>\n"
+        );
+        for prim in prims {
+            if prim.prim_type == gif::PRIM_SPRITE {
+                // Sprites take the second corner's color, like rasterize_sprite
+                for pair in prim.vertices.chunks_exact(2) {
+                    let (a, b) = (&pair[0], &pair[1]);
+                    svg += &format!(
+                        "<polygon points=\"{},{} {},{} {},{} {},{}\" fill=\"rgb({},{},{})\"/>\n",
+                        a.x, a.y, b.x, a.y, b.x, b.y, a.x, b.y, b.r, b.g, b.b,
+                    );
+                }
+                continue;
+            }
+            if prim.prim_type == gif::PRIM_LINE {
+                // Lines likewise take the second vertex's color, like rasterize_line
+                for pair in prim.vertices.chunks_exact(2) {
+                    let (a, b) = (&pair[0], &pair[1]);
+                    svg += &format!(
+                        "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"rgb({},{},{})\"/>\n",
+                        a.x, a.y, b.x, b.y, b.r, b.g, b.b,
+                    );
+                }
+                continue;
+            }
+            let assembled: Vec<GifVertex>;
+            let verts = if prim.prim_type == gif::PRIM_TRIANGLE_STRIP {
+                assembled = gs_rasterizer::expand_triangle_strip(&prim.vertices);
+                &assembled
+            } else {
+                &prim.vertices
+            };
+            for tri in verts.chunks_exact(3) {
+                let points = tri
+                    .iter()
+                    .map(|v| format!("{},{}", v.x, v.y))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let avg = |c: fn(&GifVertex) -> u8| tri.iter().map(|v| c(v) as u32).sum::<u32>() / 3;
+                let (r, g, b) = (avg(|v| v.r), avg(|v| v.g), avg(|v| v.b));
+                let class = if gs_rasterizer::is_front_facing(&tri[0], &tri[1], &tri[2]) {
+                    ""
+                } else {
+                    " class=\"back\""
+                };
+                svg += &format!("<polygon points=\"{points}\" fill=\"rgb({r},{g},{b})\"{class}/>\n");
+            }
+        }
+        svg += "</svg>\n";
+        svg
+    }
+
+    /// Rasterize at `scale` × the 640×448 display resolution (e.g. 0.5 or 2.0).
+    /// The display stays 640×448; the blit filters the framebuffer up or down. A NaN,
+    /// infinite, zero or negative scale renders at 1.0.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        let scale = if scale.is_finite() && scale > 0.0 { scale } else { 1.0 };
+        // 2048 = max texture dimension under wgpu's WebGL2 downlevel limits. Clamp the
+        // scale rather than each side, keeping the display's aspect ratio.
+        let scale = scale.min(2048.0 / gs_rasterizer::FB_W as f32);
+        let w = ((gs_rasterizer::FB_W as f32 * scale).round() as usize).max(1);
+        let h = ((gs_rasterizer::FB_H as f32 * scale).round() as usize).max(1);
+        self.gs_fb = gs_rasterizer::Framebuffer::with_size(w, h);
+        self.ee.set_viewport(w as u32, h as u32);
+    }
+
+    /// Orbit the camera: view from `eye` towards `target` with the given `up` vector
+    /// (each a 3-element array). The default is eye [0,0,3], target [0,0,0], up [0,1,0].
+    pub fn set_view(&mut self, eye: &[f32], target: &[f32], up: &[f32]) -> Result<(), JsValue> {
+        let vec3 = |v: &[f32], name: &str| -> Result<[f32; 3], JsValue> {
+            v.try_into()
+                .map_err(|_| JsValue::from_str(&format!("{name} must have 3 elements, got {}", v.len())))
+        };
+        self.ee
+            .set_view(vec3(eye, "eye")?, vec3(target, "target")?, vec3(up, "up")?)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Replace the cube with a flat GS SPRITE spanning pixels (x0, y0)–(x1, y1) inclusive,
+    /// colored `rgba` (0xRRGGBBAA). Pass `full_screen` to cover the whole framebuffer.
+    pub fn show_sprite(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, rgba: u32, full_screen: bool) {
+        let rect = if full_screen {
+            [0, 0, self.gs_fb.width as i32 - 1, self.gs_fb.height as i32 - 1]
+        } else {
+            [x0, y0, x1, y1]
+        };
+        self.ee.set_sprite(Some(ee::Sprite { rect, color: rgba.to_be_bytes() }));
+    }
+
+    /// Go back to drawing the cube after `show_sprite`.
+    pub fn show_cube(&mut self) {
+        self.ee.set_sprite(None);
+    }
+
+    /// Add a cube to the scene, which replaces the spinning cube once non-empty: centred
+    /// at (x, y, z), scaled by `scale`, its face colors multiplied by `rgb` (0xRRGGBB).
+    /// Objects are painted in ascending `draw_order` (insertion order for ties), since
+    /// there is no depth buffer. Returns the object's index.
+    pub fn add_scene_cube(
+        &mut self, x: f32, y: f32, z: f32, scale: f32, rgb: u32, draw_order: i32,
+    ) -> Result<u32, JsValue> {
+        let [_, r, g, b] = rgb.to_be_bytes();
+        let object = scene::SceneObject {
+            mesh:       std::rc::Rc::new(ee::cube_mesh()),
+            transform:  ee::translate_scale([x, y, z], scale),
+            material:   scene::Material { color: [r, g, b].map(|c| c as f32 / 255.0) },
+            draw_order,
+        };
+        self.ee
+            .add_scene_object(object)
+            .map(|i| i as u32)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Add an imported polygon mesh to the scene, untransformed: `positions` holds x,y,z
+    /// triples, and `face_sizes` splits `indices` into faces of 3 or more vertices, which
+    /// are fanned into triangles and flat-shaded in `rgb` (0xRRGGBB). Draw order as for
+    /// `add_scene_cube`. Returns the object's index.
+    pub fn add_scene_mesh(
+        &mut self, positions: &[f32], indices: &[u32], face_sizes: &[u32], rgb: u32,
+        draw_order: i32,
+    ) -> Result<u32, JsValue> {
+        if !positions.len().is_multiple_of(3) {
+            return Err(JsValue::from_str(&format!(
+                "positions must hold x,y,z triples, got {} values", positions.len(),
+            )));
+        }
+        let points: Vec<[f32; 3]> = positions.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect();
+        let mesh = scene::Mesh::from_polygons(&points, indices, face_sizes)
+            .map_err(|e| JsValue::from_str(&e))?;
+        let [_, r, g, b] = rgb.to_be_bytes();
+        let object = scene::SceneObject {
+            mesh:       std::rc::Rc::new(mesh),
+            transform:  ee::translate_scale([0.0; 3], 1.0),
+            material:   scene::Material { color: [r, g, b].map(|c| c as f32 / 255.0) },
+            draw_order,
+        };
+        self.ee
+            .add_scene_object(object)
+            .map(|i| i as u32)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Re-wind meshes added to the scene from now on so their front faces point outward,
+    /// flipping CW triangles (imported models would otherwise render inside-out).
+    pub fn set_auto_winding(&mut self, enabled: bool) {
+        self.ee.set_auto_winding(enabled);
+    }
+
+    /// How frames reach the canvas: "wgpu", or "canvas2d" when wgpu failed to start and
+    /// the 2D-canvas fallback took over.
+    pub fn display_backend(&self) -> String {
+        self.gs_display.backend().to_string()
+    }
+
+    /// Upload one color per cube face (6 vertices) rather than per vertex, for meshes whose
+    /// faces are flat-colored; VU1 expands them, so the image is unchanged.
+    pub fn set_face_colors(&mut self, enabled: bool) {
+        self.ee.set_face_colors(enabled);
+    }
+
+    /// Color the cube or scene by world-space normal (`n * 0.5 + 0.5`, so +X faces are
+    /// red-ish) instead of lighting it, for checking imported meshes' normals.
+    pub fn set_debug_normals(&mut self, enabled: bool) {
+        self.ee.set_debug_normals(enabled);
+    }
+
+    /// Fade the scene into the background color with view-space distance: vertices nearer
+    /// than `start` are untouched, those beyond `end` take the background color. VU1
+    /// computes the per-vertex fog coefficient; lit, unpacked f32 batches only.
+    pub fn set_fog(&mut self, start: f32, end: f32) -> Result<(), JsValue> {
+        self.ee.set_fog(Some((start, end))).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn clear_fog(&mut self) {
+        let _ = self.ee.set_fog(None);
+    }
+
+    /// Unpack vertex attributes interleaved per vertex (position, normal, color) into VU1
+    /// memory with STCYCL skipping write, drawn by a single-pointer micro-program loop.
+    /// Output is identical; only lit, unpacked f32 batches use it.
+    pub fn set_interleaved_attributes(&mut self, enabled: bool) {
+        self.ee.set_interleaved_attributes(enabled);
+    }
+
+    /// Annotated hex dump of the VIF1 packet last built or submitted, one line per QW with
+    /// its decoded VIF command, UNPACK destination or GIF tag.
+    pub fn hexdump_packet(&self) -> String {
+        self.ee.hexdump_packet()
+    }
+
+    /// Split each cube / scene triangle into 4 at its edge midpoints `level` times per
+    /// frame (0 = off, at most 4), interpolating positions, normals and colors.
+    pub fn set_tessellation_level(&mut self, level: u32) {
+        self.ee.set_tessellation_level(level);
+    }
+
+    /// Bake crude per-vertex ambient occlusion into meshes added to the scene from now on,
+    /// darkening their concave regions.
+    pub fn set_bake_ao(&mut self, enabled: bool) {
+        self.ee.set_bake_ao(enabled);
+    }
+
+    /// Show a checkered ground plane below the cube (or scene) as a reference surface.
+    pub fn set_show_ground(&mut self, enabled: bool) {
+        self.ee.set_show_ground(enabled);
+    }
+
+    /// Empty the scene, bringing the spinning cube back.
+    pub fn clear_scene(&mut self) {
+        self.ee.clear_scene();
+    }
+
+    /// Projection depth convention: `true` maps near/far to NDC z 0..1 (WebGPU),
+    /// `false` to -1..1 (OpenGL, the default).
+    pub fn set_depth_zero_to_one(&mut self, enabled: bool) {
+        self.ee.set_depth_range(if enabled {
+            ee::DepthRange::ZeroToOne
+        } else {
+            ee::DepthRange::NegOneToOne
+        });
+    }
+
+    /// Scale the cube's animation speed independently of the frame rate
+    /// (0 = frozen, 1 = normal, 2 = double speed).
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.ee.set_time_scale(scale);
+    }
+
+    /// Replace the cube's spin with a keyframed animation. `keys` is a flat array of
+    /// 8 floats per key: time (frames), tx, ty, tz, rx, ry, rz (degrees), scale, in
+    /// increasing time order. `ease` selects smoothstep instead of linear blending
+    /// between keys; `looping` repeats the span of the keys.
+    pub fn set_animation(&mut self, keys: &[f32], ease: bool, looping: bool) -> Result<(), JsValue> {
+        if keys.is_empty() || !keys.len().is_multiple_of(8) {
+            return Err(JsValue::from_str(&format!(
+                "keyframes need 8 floats each, got {} floats", keys.len(),
+            )));
+        }
+        let keys = keys
+            .chunks_exact(8)
+            .map(|k| animation::Keyframe {
+                time:        k[0],
+                translation: [k[1], k[2], k[3]],
+                rotation:    [k[4], k[5], k[6]],
+                scale:       k[7],
+            })
+            .collect();
+        let easing = if ease { animation::Easing::EaseInOut } else { animation::Easing::Linear };
+        let anim = animation::Animation::new(keys, easing, looping).map_err(|e| JsValue::from_str(&e))?;
+        self.ee.set_animation(Some(anim));
+        Ok(())
+    }
+
+    /// Go back to the built-in spin after `set_animation`.
+    pub fn clear_animation(&mut self) {
+        self.ee.set_animation(None);
+    }
+
+    /// Upload cube colors as packed RGBA8 (VIF UNPACK V4-8) instead of f32×4.
+    pub fn set_packed_colors(&mut self, enabled: bool) {
+        self.ee.set_packed_colors(enabled);
+    }
+
+    /// Upload vertex colors as 8-bit indices (VIF UNPACK S-8) into a per-frame palette that
+    /// is sent once and resolved by VU1, instead of one color per vertex.
+    pub fn set_indexed_colors(&mut self, enabled: bool) {
+        self.ee.set_indexed_colors(enabled);
+    }
+
+    /// Upload cube normals as signed 16-bit fixed point (VIF UNPACK V4-16) instead of f32×4.
+    pub fn set_packed_normals(&mut self, enabled: bool) {
+        self.ee.set_packed_normals(enabled);
+    }
+
+    /// Turn vertex lighting on or off. Off selects the VU1 micro-program's transform-only
+    /// routine (a different MSCAL entry point), drawing the raw vertex colors and leaving
+    /// the normals out of the packet.
+    pub fn set_lighting(&mut self, enabled: bool) {
+        self.ee.set_lighting(enabled);
+    }
+
+    /// Gamma-correct shading: apply the light to linear-space colors (sRGB approximated
+    /// as gamma 2.0) instead of multiplying the sRGB vertex colors directly. Selects
+    /// a separate VU1 routine; packed colors/normals are sent as f32 while it is on.
+    pub fn set_linear_lighting(&mut self, enabled: bool) {
+        self.ee.set_linear_lighting(enabled);
+    }
+
+    /// Upload EE-computed per-vertex tangents with each batch (VU datamem[188..223]),
+    /// in preparation for normal-mapped lighting.
+    pub fn set_tangents(&mut self, enabled: bool) {
+        self.ee.set_tangents(enabled);
+    }
+
+    /// Set the GS XYOFFSET register (OFX, OFY) in 12.4 fixed-point, subtracted from
+    /// primitive coordinates. Use `(2048 << 4, 2048 << 4)` for packets authored against
+    /// the usual hardware offset; the built-in cube uses (0, 0).
+    pub fn set_xy_offset(&mut self, ofx: i32, ofy: i32) {
+        self.xy_offset = (ofx, ofy);
+    }
+
+    /// Cap the primitives (triangles or sprites) rasterized per frame; a runaway packet beyond the cap is cut off
+    /// and reported through the `primitivesDropped` telemetry flag.
+    pub fn set_primitive_limit(&mut self, limit: u32) {
+        self.prim_limit = limit as usize;
+    }
+
+    /// Toggle the quad fast path (off by default): triangle pairs tiling an axis-aligned,
+    /// flat-colored rectangle are filled as one GS sprite. While it is on, float Gouraud
+    /// interpolation rounds to nearest so the remaining triangles match what the sprites
+    /// fill; a pair comes out identical either way.
+    pub fn set_sprite_fast_path(&mut self, enabled: bool) {
+        self.sprite_fast_path = enabled;
+    }
+
+    /// Select Gouraud precision: `true` mimics the GS's fixed-point color gradients
+    /// (truncating, slightly banded), `false` uses float interpolation (the default).
+    pub fn set_gs_fixed_color_interp(&mut self, enabled: bool) {
+        self.color_interp = if enabled {
+            gs_rasterizer::ColorInterp::GsFixed
+        } else {
+            gs_rasterizer::ColorInterp::Float
+        };
+    }
+
+    /// Select how vertex colors outside [0, 1] are stored, like the GS COLCLAMP register:
+    /// `"clamp"` saturates (the default), `"wrap"` keeps the low 8 bits of the value
+    /// scaled to 0..255, and `"mask"` zeroes out-of-range channels to show where they occur.
+    pub fn set_color_clamp(&mut self, mode: &str) -> Result<(), JsValue> {
+        self.color_clamp = match mode {
+            "clamp" => gif::ColorClampMode::Clamp,
+            "wrap"  => gif::ColorClampMode::Wrap,
+            "mask"  => gif::ColorClampMode::Mask,
+            _ => {
+                return Err(JsValue::from_str(&format!(
+                    "unknown color clamp mode {mode:?}; expected \"clamp\", \"wrap\" or \"mask\"",
+                )));
+            }
+        };
+        Ok(())
+    }
+
+    /// Select where pixels are sampled: `true` at the pixel centre like the GS (the
+    /// default), `false` at the integer corner, which also fills right and bottom edges.
+    pub fn set_pixel_center(&mut self, enabled: bool) {
+        self.pixel_center = enabled;
+    }
+
+    /// Toggle the frame-diff debug mode: pixels changed since the last frame are tinted.
+    pub fn set_frame_diff(&mut self, enabled: bool) {
+        self.frame_diff = enabled;
+        self.prev_pixels.clear();
+    }
+
+    /// Motion blur: each frame is blended over the history of earlier ones as
+    /// `new * (1 - strength) + history * strength`, leaving a trail behind moving objects.
+    /// 0 turns it off; `strength` is clamped below 1, which would freeze the picture.
+    pub fn set_motion_blur(&mut self, strength: f32) {
+        self.motion_blur = if strength.is_nan() { 0.0 } else { strength.clamp(0.0, 0.99) };
+        self.accum.clear();
+    }
+
+    /// Toggle the per-primitive bounding-box debug overlay (drawn in yellow).
+    pub fn set_debug_bbox(&mut self, enabled: bool) {
+        self.debug_bbox = enabled;
+    }
+
+    /// Toggle the wireframe-over-fill debug mode: each front-facing triangle is filled,
+    /// then its edges are drawn over it in white, so later triangles still paint over the
+    /// edges of earlier ones. The sprite fast path is skipped to show the real triangles.
+    pub fn set_wire_overlay(&mut self, enabled: bool) {
+        self.wire_overlay = enabled;
+    }
+
+    /// Halt VU1 before it executes the micro-program instruction at `pc`. The pipeline
+    /// freezes mid-frame (step_frame stops advancing) until `vu1_continue`, so
+    /// `vu1_registers` shows the state at that point. Any number may be set.
+    pub fn set_vu1_breakpoint(&mut self, pc: u16) {
+        self.vu1.set_breakpoint(pc);
+    }
+
+    pub fn clear_vu1_breakpoint(&mut self, pc: u16) {
+        self.vu1.clear_breakpoint(pc);
+    }
+
+    /// Guard the GIF tag at datamem[108] against the micro-program: a store into it (say
+    /// from a miscounted output loop) is dropped and reported through `drain_log`.
+    pub fn set_gif_tag_guard(&mut self, enabled: bool) {
+        let tag = vu1_program::GIF_TAG_ADDR as usize;
+        self.vu1.set_write_protect(enabled.then_some(tag..tag + 1));
+    }
+
+    /// Resume VU1 after a breakpoint and finish the frame. Returns true if the frame
+    /// completed and was presented, false if another breakpoint was hit first.
+    pub fn vu1_continue(&mut self) -> Result<bool, JsValue> {
+        if !self.vu1_halted {
+            return Err(JsValue::from_str("VU1 is not halted at a breakpoint"));
+        }
+        self.vu1_halted = false;
+        if !self.run_batches(true) {
+            return Ok(false);
+        }
+        self.draw_frame_prims();
+        self.gs_display.upload_and_present(&mut self.gs_fb);
+        self.frame_count += 1;
+        Ok(true)
+    }
+
+    /// Toggle profiling: each `step_frame` times its pipeline stages for
+    /// `last_frame_profile`. Off by default, since reading the clock per stage and batch
+    /// costs time itself.
+    pub fn set_profile(&mut self, enabled: bool) {
+        self.profile = enabled;
+        if !enabled {
+            self.last_profile = None;
+        }
+    }
+
+    /// Stage timings of the last profiled `step_frame` in milliseconds, as a JS object
+    /// `{ buildPacket, dma, vif, vu1, gif, rasterize, present, total }`; null if
+    /// profiling is off or no frame has run since it was enabled. Per-batch stages are
+    /// summed over the frame's batches.
+    pub fn last_frame_profile(&self) -> JsValue {
+        let Some(p) = self.last_profile else {
+            return JsValue::NULL;
+        };
+        let obj = js_sys::Object::new();
+        let fields: [(&str, f64); 8] = [
+            ("buildPacket", p.build_packet),
+            ("dma",         p.dma),
+            ("vif",         p.vif),
+            ("vu1",         p.vu1),
+            ("gif",         p.gif),
+            ("rasterize",   p.rasterize),
+            ("present",     p.present),
+            ("total",       p.total),
+        ];
+        for (name, value) in fields {
+            let _ = js_sys::Reflect::set(&obj, &JsValue::from_str(name), &JsValue::from_f64(value));
+        }
+        obj.into()
+    }
+
+    /// Snapshot of the VU1 register file as a JS object:
+    /// `{ vf: [[x,y,z,w] × 32], vi: [× 16], acc: [x,y,z,w], q, p, r, status, clip, pc }`.
+    /// `r` is the R register as its float value in [1, 2); `clip` the 24-bit clip flags.
+    pub fn vu1_registers(&self) -> JsValue {
+        let vec4 = |v: &[f32; 4]| -> js_sys::Array {
+            v.iter().map(|&c| JsValue::from_f64(c as f64)).collect()
+        };
+        let vu = &self.vu1;
+        let vf: js_sys::Array = vu.vf.iter().map(vec4).collect();
+        let vi: js_sys::Array = vu.vi.iter().map(|&v| JsValue::from_f64(v as f64)).collect();
+
+        let obj = js_sys::Object::new();
+        let fields: [(&str, JsValue); 11] = [
+            ("vf",     vf.into()),
+            ("vi",     vi.into()),
+            ("acc",    vec4(&vu.acc).into()),
+            ("q",      JsValue::from_f64(vu.q as f64)),
+            ("p",      JsValue::from_f64(vu.p_reg as f64)),
+            ("i",      JsValue::from_f64(vu.i_reg as f64)),
+            ("r",      JsValue::from_f64(f32::from_bits(vu.r_reg) as f64)),
+            ("status", JsValue::from_f64(vu.status_flags as f64)),
+            ("mac",    JsValue::from_f64(vu.mac_flags() as f64)),
+            ("clip",   JsValue::from_f64(vu.clip_flags() as f64)),
+            ("pc",     JsValue::from_f64(vu.pc as f64)),
+        ];
+        for (name, value) in fields {
+            let _ = js_sys::Reflect::set(&obj, &JsValue::from_str(name), &value);
+        }
+        obj.into()
+    }
+
+    /// The `step_frame` telemetry counters as a JSON string, e.g. for console or file
+    /// logging: `{"emulatedCycles":…,"vu1MatOps":…,"vu1Cycles":…,"frameCount":…,
+    /// "primitivesDropped":…,"emptyFrame":…}`.
+    pub fn telemetry_json(&self) -> String {
+        format!(
+            "{{\"emulatedCycles\":{},\"vu1MatOps\":{},\"vu1Cycles\":{},\"frameCount\":{},\
+             \"primitivesDropped\":{},\"emptyFrame\":{}}}",
+            self.emu_cycles, self.vu1_mat_ops, self.vu1_cycles, self.frame_count,
+            self.prims_dropped, self.empty_frame,
+        )
+    }
+
+    /// Take the pipeline diagnostics logged since the last call (VIF decode problems,
+    /// DMA overruns, VU1 runaways, dropped primitives) as a JS array of strings.
+    pub fn drain_log(&mut self) -> js_sys::Array {
+        self.log.drain(..).map(|msg| JsValue::from_str(&msg)).collect()
+    }
+
+    /// Debug builds only: VU1 instruction-coverage histogram as a JS object
+    /// `{ "upper:0x018": count, "lower:0x3A": count, ... }`.
+    #[cfg(debug_assertions)]
+    pub fn vu1_opcode_histogram(&self) -> JsValue {
+        let mut entries: Vec<_> = self.vu1.opcode_histogram().iter().collect();
+        entries.sort();
+
+        let obj = js_sys::Object::new();
+        for (&key, &count) in entries {
+            let name = if key & vu1::LOWER_KEY != 0 {
+                format!("lower:0x{:02X}", key & !vu1::LOWER_KEY)
+            } else {
+                format!("upper:0x{:03X}", key)
+            };
+            let _ = js_sys::Reflect::set(
+                &obj, &JsValue::from_str(&name),
+                &JsValue::from_f64(count as f64),
+            );
+        }
         obj.into()
     }
 }
+
+/// If two list triangles tile an axis-aligned rectangle — every vertex on a corner, split
+/// along a diagonal, one flat color, both front-facing — return it as sprite corners.
+fn detect_sprite_quad(a: &[GifVertex], b: &[GifVertex]) -> Option<[GifVertex; 2]> {
+    let rgba = |v: &GifVertex| (v.r, v.g, v.b, v.a);
+    if a.iter().chain(b).any(|v| rgba(v) != rgba(&a[0])) {
+        return None;
+    }
+    if !gs_rasterizer::is_front_facing(&a[0], &a[1], &a[2])
+        || !gs_rasterizer::is_front_facing(&b[0], &b[1], &b[2])
+    {
+        return None;
+    }
+
+    let x0 = a.iter().chain(b).map(|v| v.x).min()?;
+    let y0 = a.iter().chain(b).map(|v| v.y).min()?;
+    let x1 = a.iter().chain(b).map(|v| v.x).max()?;
+    let y1 = a.iter().chain(b).map(|v| v.y).max()?;
+    if a.iter().chain(b).any(|v| (v.x != x0 && v.x != x1) || (v.y != y0 && v.y != y1)) {
+        return None;
+    }
+
+    // The vertex each triangle doesn't share must sit on the opposite corner of the other's;
+    // triangles sharing an outer edge instead would leave part of the rectangle uncovered.
+    let lone = |t: &[GifVertex], o: &[GifVertex]| {
+        t.iter().find(|v| !o.iter().any(|w| w.x == v.x && w.y == v.y)).cloned()
+    };
+    let (la, lb) = (lone(a, b)?, lone(b, a)?);
+    if la.x == lb.x || la.y == lb.y {
+        return None;
+    }
+
+    let corner = |x, y| GifVertex { x, y, ..a[0].clone() };
+    Some([corner(x0, y0), corner(x1, y1)])
+}
+
+impl EmulatorCore {
+    /// A core in its power-on state, presenting through `gs_display`.
+    fn with_display(gs_display: gs_display::GsDisplay) -> EmulatorCore {
+        EmulatorCore {
+            ee:          ee::EmotionEngine::new(),
+            dmac:        dmac::Dmac::new(),
+            vif1:        vif1::Vif1::new(),
+            vu1:         vu1::Vu1::new(),
+            gs_fb:       gs_rasterizer::Framebuffer::new(),
+            gs_display,
+            frame_count: 0,
+            emu_cycles:  0,
+            vu1_mat_ops: 0,
+            vu1_cycles:  0,
+            debug_bbox:  false,
+            wire_overlay: false,
+            xy_offset:   (0, 0),
+            frame_diff:  false,
+            prev_pixels: Vec::new(),
+            motion_blur: 0.0,
+            accum:       Vec::new(),
+            sprite_fast_path: false,
+            color_interp: gs_rasterizer::ColorInterp::Float,
+            color_clamp: gif::ColorClampMode::Clamp,
+            pixel_center: true,
+            prim_limit:  DEFAULT_PRIM_LIMIT,
+            prims_dropped: false,
+            empty_frame: false,
+            log:         VecDeque::new(),
+            vu1_halted:  false,
+            prims:       gif::PrimBuffer::default(),
+            profile:     false,
+            frame_profile: profile::FrameProfile::default(),
+            last_profile: None,
+        }
+    }
+
+    /// Run one frame through the pipeline and present it: `step_frame` without the
+    /// telemetry object.
+    fn advance_frame(&mut self) {
+        self.frame_profile = profile::FrameProfile::default();
+        let frame_start = self.stage_start();
+
+        // A VU1 halted at a breakpoint freezes the pipeline until vu1_continue
+        if !self.vu1_halted {
+            // 1. EE: build VIF1 packet, kick DMAC (300 MHz / 60 fps ≈ 5M cycles/frame)
+            let t = self.stage_start();
+            let (madr, qwc) = self.ee.build_packet();
+            self.stage_end(profile::Stage::BuildPacket, t);
+            self.emu_cycles += 300_000;
+
+            // 2–6. DMAC → VIF1 → VU1 → GIF → software GS
+            self.run_packet(madr, qwc);
+            self.vu1_mat_ops += 5; // 3 mat-mul + 2 rotation = 5 per frame
+        }
+
+        // 7. Upload framebuffer texture and blit to canvas
+        let t = self.stage_start();
+        self.gs_display.upload_and_present(&mut self.gs_fb);
+        self.stage_end(profile::Stage::Present, t);
+
+        self.frame_count += 1;
+        if let Some(t) = frame_start {
+            self.frame_profile.total = profile::now_ms() - t;
+            self.last_profile = Some(self.frame_profile);
+        }
+    }
+
+    /// Append a diagnostic, evicting the oldest once LOG_CAPACITY is reached.
+    fn push_log(&mut self, msg: String) {
+        if self.log.len() == LOG_CAPACITY {
+            self.log.pop_front();
+        }
+        self.log.push_back(msg);
+    }
+
+    /// Move the diagnostics the pipeline stages recorded into the core's log.
+    fn collect_stage_logs(&mut self) {
+        let msgs: Vec<String> = self.dmac.log.drain(..)
+            .chain(self.vif1.log.drain(..))
+            .chain(self.vu1.log.drain(..))
+            .collect();
+        for msg in msgs {
+            self.push_log(msg);
+        }
+    }
+
+    /// Rasterize a triangle list, taking the sprite fast path for rectangle-forming pairs
+    /// unless the list is AA1 (sprites record no edge flags) or edges are overlaid.
+    fn draw_triangle_list(&mut self, vertices: &[GifVertex], aa1: bool) {
+        let interp = match self.color_interp {
+            gs_rasterizer::ColorInterp::Float if self.sprite_fast_path => {
+                gs_rasterizer::ColorInterp::FloatRounded
+            }
+            interp => interp,
+        };
+        let tris: Vec<&[GifVertex]> = vertices.chunks_exact(3).collect();
+        let mut i = 0;
+        while i < tris.len() {
+            if self.sprite_fast_path && !aa1 && !self.wire_overlay && i + 1 < tris.len() {
+                if let Some([s0, s1]) = detect_sprite_quad(tris[i], tris[i + 1]) {
+                    gs_rasterizer::rasterize_sprite(&mut self.gs_fb, &s0, &s1, self.pixel_center);
+                    i += 2;
+                    continue;
+                }
+            }
+            let tri = tris[i];
+            gs_rasterizer::rasterize_triangle(
+                &mut self.gs_fb, &tri[0], &tri[1], &tri[2],
+                interp, self.pixel_center, aa1,
+            );
+            if self.wire_overlay && gs_rasterizer::is_front_facing(&tri[0], &tri[1], &tri[2]) {
+                for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+                    gs_rasterizer::draw_line(
+                        &mut self.gs_fb, tri[a].x, tri[a].y, tri[b].x, tri[b].y, 0xFF_FF_FF_FF,
+                    );
+                }
+            }
+            i += 1;
+        }
+    }
+
+    /// Pipeline stages 2–5: DMA → VIF1 → VU1 → GIF, decoding every batch's primitives into
+    /// `self.prims`. Returns false if the packet issued no MSCAL/MSCNT or VU1 halted at a
+    /// breakpoint.
+    fn run_to_gif(&mut self, madr: u32, qwc: u32) -> bool {
+        self.dmac.kick(madr, qwc);
+
+        // 2. DMAC: transfer EE RAM → VIF1 FIFO
+        let t = self.stage_start();
+        self.dmac.transfer(&*self.ee.ee_ram, &mut self.vif1.fifo);
+        self.stage_end(profile::Stage::Dma, t);
+
+        self.run_batches(false)
+    }
+
+    /// Stages 3–5 over what is left in the VIF1 FIFO. With `resume`, first continue the
+    /// VU1 run halted at a breakpoint, keeping the primitives kicked before it.
+    fn run_batches(&mut self, resume: bool) -> bool {
+        // 3–5 repeat per batch: VIF1 stops at each MSCAL/MSCNT so VU1 consumes that
+        // batch's data before the next UNPACK lands, and each XGKICK's primitives are
+        // appended in packet (draw) order.
+        let mut kicked = resume;
+        let mut vu_running = resume;
+        loop {
+            if !vu_running {
+                // 3. VIF1: parse packet → VU1 data memory
+                let t = self.stage_start();
+                self.vif1.process(&mut self.vu1.data_mem);
+                self.stage_end(profile::Stage::Vif, t);
+
+                // 4. VU1: run micro-program until XGKICK
+                // MSCAL jumps to its address; MSCNT resumes at the PC the last XGKICK left behind
+                match self.vif1.vu_start.take() {
+                    Some(vif1::VuStart::Call(exec_addr)) => self.vu1.pc = exec_addr,
+                    Some(vif1::VuStart::Continue) => {}
+                    None => break, // FIFO drained
+                }
+                // The first batch replaces the last frame's primitives; a data-only packet
+                // leaves them for hold_frame
+                if !kicked {
+                    self.prims.clear();
+                }
+                self.vu1.top  = self.vif1.top;
+                self.vu1.itop = self.vif1.itop;
+            }
+            vu_running = false;
+            let t = self.stage_start();
+            let run = self.vu1.run_until_xgkick();
+            self.stage_end(profile::Stage::Vu1, t);
+            self.vu1_cycles += u64::from(run.cycles);
+            let xgkick_base = match run.stop {
+                vu1::Stop::XgKick(base) => base,
+                // Nothing was kicked: skip GIF for this batch rather than parse whatever
+                // data memory holds
+                vu1::Stop::Aborted => continue,
+                vu1::Stop::Breakpoint(pc) => {
+                    // Freeze the pipeline mid-packet until vu1_continue
+                    self.vu1_halted = true;
+                    self.push_log(format!("VU1: breakpoint at PC {pc}"));
+                    self.collect_stage_logs();
+                    return false;
+                }
+            };
+
+            // 5. GIF: parse tag + vertex data from VU data memory
+            // xgkick_base = VI[05] = 108 (GIF tag QW address in VU data memory)
+            let t = self.stage_start();
+            gif::parse_gif_packet(
+                &self.vu1.data_mem, xgkick_base as usize, self.xy_offset, self.color_clamp,
+                &mut self.prims,
+            );
+            kicked = true;
+            self.stage_end(profile::Stage::Gif, t);
+        }
+        self.collect_stage_logs();
+        kicked // false for a data-only packet: VU1 stays idle
+    }
+
+    /// `benchmark` with the clock passed in: `now` is read once before the first frame and
+    /// once after the last.
+    fn run_benchmark(
+        &mut self, frames: u32, mut now: impl FnMut() -> f64,
+    ) -> profile::BenchResult {
+        let start = now();
+        self.offscreen(|core| {
+            for frame in 0..frames {
+                let (madr, qwc) = core.ee.build_packet_for_frame(frame as u64);
+                core.run_packet(madr, qwc);
+            }
+        });
+        profile::BenchResult { frames, total_ms: now() - start }
+    }
+
+    /// Run `render` against a scratch framebuffer the live one's size and return it, with
+    /// the live frame set aside: its primitives, status flags, diagnostics, stage profile
+    /// and VU1 cycle count are restored afterwards, and motion blur and frame diff (which would mix the
+    /// two) and VU1 breakpoints (which would freeze the live pipeline) are off meanwhile.
+    fn offscreen(&mut self, render: impl FnOnce(&mut Self)) -> gs_rasterizer::Framebuffer {
+        let scratch = gs_rasterizer::Framebuffer::with_size(self.gs_fb.width, self.gs_fb.height);
+        let live        = std::mem::replace(&mut self.gs_fb, scratch);
+        let blur        = std::mem::replace(&mut self.motion_blur, 0.0);
+        let frame_diff  = std::mem::replace(&mut self.frame_diff, false);
+        let breakpoints = self.vu1.take_breakpoints();
+        let prims       = std::mem::take(&mut self.prims);
+        let log         = std::mem::take(&mut self.log);
+        let (empty_frame, prims_dropped, vu1_cycles, frame_profile) =
+            (self.empty_frame, self.prims_dropped, self.vu1_cycles, self.frame_profile);
+
+        render(self);
+
+        self.motion_blur = blur;
+        self.frame_diff  = frame_diff;
+        self.vu1.set_breakpoints(breakpoints);
+        self.prims = prims;
+        self.log   = log;
+        (self.empty_frame, self.prims_dropped, self.vu1_cycles, self.frame_profile) =
+            (empty_frame, prims_dropped, vu1_cycles, frame_profile);
+        std::mem::replace(&mut self.gs_fb, live)
+    }
+
+    /// Pipeline stages 2–6 for the packet at `madr`: DMA → VIF1 → VU1 → GIF → GS.
+    /// Returns false if the packet issued no MSCAL/MSCNT, leaving VU1 idle and the framebuffer
+    /// untouched, or if VU1 halted at a breakpoint.
+    fn run_packet(&mut self, madr: u32, qwc: u32) -> bool {
+        if !self.run_to_gif(madr, qwc) {
+            return false; // data-only packet: VU1 stays idle
+        }
+        self.draw_frame_prims();
+        true
+    }
+
+    /// Stage 6 for the primitives `run_to_gif` decoded into `self.prims`.
+    fn draw_frame_prims(&mut self) {
+        let prims = std::mem::take(&mut self.prims);
+        self.draw_prims(prims.as_slice());
+        self.prims = prims;
+    }
+
+    /// Stage 6: rasterize a frame's primitives, then apply the debug overlays.
+    fn draw_prims(&mut self, prims: &[gif::GsPrimitive]) {
+        let t = self.stage_start();
+        // 6. GS rasterizer: clear then draw each primitive by its PRIM type, in packet
+        // order, up to the per-frame cap
+        self.gs_fb.clear(0xFF_08_0A_14); // PS2-ish dark bg
+        let mut budget = self.prim_limit;
+        let mut dropped = 0;
+        for prim in prims {
+            // Strips are assembled into lists first, dropping degenerate restarts
+            let assembled: Vec<GifVertex>;
+            let verts = if prim.prim_type == gif::PRIM_TRIANGLE_STRIP {
+                assembled = gs_rasterizer::expand_triangle_strip(&prim.vertices);
+                &assembled
+            } else {
+                &prim.vertices
+            };
+            let per = match prim.prim_type {
+                gif::PRIM_SPRITE | gif::PRIM_LINE => 2,
+                _ => 3,
+            };
+            let count = verts.len() / per;
+            let n = count.min(budget);
+            let verts = &verts[..n * per];
+            let fogged: Vec<GifVertex>;
+            let verts = if prim.fge {
+                fogged = verts.iter().map(|v| gs_rasterizer::fog_vertex(&self.gs_fb, v)).collect();
+                &fogged
+            } else {
+                verts
+            };
+            match prim.prim_type {
+                gif::PRIM_SPRITE => {
+                    for pair in verts.chunks_exact(2) {
+                        gs_rasterizer::rasterize_sprite(
+                            &mut self.gs_fb, &pair[0], &pair[1], self.pixel_center,
+                        );
+                    }
+                }
+                gif::PRIM_LINE => {
+                    for pair in verts.chunks_exact(2) {
+                        gs_rasterizer::rasterize_line(&mut self.gs_fb, &pair[0], &pair[1]);
+                    }
+                }
+                _ => self.draw_triangle_list(verts, prim.aa1),
+            }
+            budget -= n;
+            dropped += count - n;
+        }
+        // Before the overlays, which draw whether or not any primitive did
+        self.empty_frame = !self.gs_fb.drawn();
+        self.prims_dropped = dropped > 0;
+        if self.prims_dropped {
+            self.push_log(format!(
+                "GS: {dropped} primitives over the per-frame limit of {} dropped",
+                self.prim_limit,
+            ));
+        }
+
+        if self.motion_blur > 0.0 {
+            self.blend_motion_blur();
+        }
+
+        // Debug overlay: screen-space bounding box of each primitive batch
+        if self.debug_bbox {
+            for prim in prims {
+                if let Some((x0, y0, x1, y1)) = prim.bounding_box() {
+                    gs_rasterizer::draw_rect(&mut self.gs_fb, x0, y0, x1, y1, 0xFF_00_FF_FF);
+                }
+            }
+        }
+
+        // Debug: tint pixels that differ (XOR != 0) from the previous untinted frame magenta
+        if self.frame_diff {
+            let cur = self.gs_fb.pixels().to_vec();
+            if self.prev_pixels.len() == cur.len() {
+                for (px, &prev) in self.gs_fb.pixels_mut().iter_mut().zip(&self.prev_pixels) {
+                    if *px ^ prev != 0 {
+                        *px = 0xFF00_0000 | (((*px >> 1) & 0x007F_7F7F) + 0x007F_007F);
+                    }
+                }
+            }
+            self.prev_pixels = cur;
+        }
+        self.stage_end(profile::Stage::Rasterize, t);
+    }
+
+    /// Blend the frame just drawn into the motion-blur history and show the result.
+    fn blend_motion_blur(&mut self) {
+        let keep = self.motion_blur;
+        let fb = &mut self.gs_fb;
+        // (Re)start the history from this frame, e.g. after a resolution change
+        if self.accum.len() != fb.pixels().len() * 3 {
+            self.accum.clear();
+            for p in fb.pixels() {
+                let [r, g, b, _] = p.to_le_bytes();
+                self.accum.extend([r, g, b].map(f32::from));
+            }
+        }
+        // The trail fades out over many frames, anywhere on screen: pixels_mut covers it all
+        for (px, acc) in fb.pixels_mut().iter_mut().zip(self.accum.chunks_exact_mut(3)) {
+            let [r, g, b, _] = px.to_le_bytes();
+            for (c, new) in acc.iter_mut().zip([r, g, b]) {
+                *c = new as f32 * (1.0 - keep) + *c * keep;
+            }
+            *px = u32::from_le_bytes([acc[0], acc[1], acc[2], 255.0].map(|c| (c + 0.5) as u8));
+        }
+    }
+
+    /// Start timing a pipeline stage: the clock reading, or None when not profiling.
+    fn stage_start(&self) -> Option<f64> {
+        self.profile.then(profile::now_ms)
+    }
+
+    /// Charge the time since `start` to `stage` of the frame in progress.
+    fn stage_end(&mut self, stage: profile::Stage, start: Option<f64>) {
+        if let Some(t) = start {
+            self.frame_profile.add(stage, profile::now_ms() - t);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A core that runs the whole pipeline but presents nowhere.
+    fn core() -> EmulatorCore {
+        EmulatorCore::with_display(gs_display::GsDisplay::Headless)
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn opcode_histogram_covers_the_micro_program() {
+        use std::collections::HashSet;
+
+        let mut core = core();
+        core.advance_frame();
+
+        // The default lit cube runs the main routine, PC 0 up to its XGKICK at PC 40
+        let mut used = HashSet::new();
+        for &instr in &vu1_program::VU1_MICRO[..=40] {
+            let (upper, lower) = ((instr >> 32) as u32, instr as u32);
+            used.insert(upper & 0x1FF);
+            used.insert(vu1::LOWER_KEY | (lower >> 26));
+        }
+        let executed: HashSet<u32> = core.vu1.opcode_histogram().keys().copied().collect();
+        assert_eq!(executed, used);
+        assert!(vu1::unsupported_ops(vu1_program::VU1_MICRO).is_empty());
+    }
+
+    #[test]
+    fn bbox_overlay_frames_the_centered_cube() {
+        let mut core = core();
+        core.set_debug_bbox(true);
+        core.advance_frame();
+
+        let (x0, y0, x1, y1) = core.prims.as_slice()[0].bounding_box().unwrap();
+        let (w, h) = (core.gs_fb.width as i32, core.gs_fb.height as i32);
+        // Frame 0 is unrotated: the box sits in the middle, equal margins either side
+        assert!((x0 - (w - 1 - x1)).abs() <= 2, "x {x0}..{x1}");
+        assert!((y0 - (h - 1 - y1)).abs() <= 2, "y {y0}..{y1}");
+        assert!(x1 - x0 > w / 8 && y1 - y0 > h / 8);
+        let at = |x: i32, y: i32| core.gs_fb.pixels()[(y * w + x) as usize];
+        assert_eq!(at(x0, (y0 + y1) / 2), 0xFF_00_FF_FF);
+        assert_eq!(at((x0 + x1) / 2, y1), 0xFF_00_FF_FF);
+    }
+
+    #[test]
+    fn half_render_scale_halves_the_framebuffer() {
+        let mut core = core();
+        core.set_render_scale(0.5);
+        core.advance_frame();
+
+        assert_eq!((core.capture_width(), core.capture_height()), (320, 224));
+        assert_eq!(core.capture_frame().len(), 320 * 224 * 4);
+        // The EE viewport follows: the cube is centered in the smaller buffer
+        let (x0, _, x1, _) = core.prims.as_slice()[0].bounding_box().unwrap();
+        assert!((x0 - (319 - x1)).abs() <= 2, "x {x0}..{x1}");
+    }
+
+    #[test]
+    fn invalid_render_scales_render_at_full_size() {
+        let mut core = core();
+        for scale in [f32::NAN, f32::INFINITY, 0.0, -2.0] {
+            core.set_render_scale(scale);
+            assert_eq!((core.capture_width(), core.capture_height()), (640, 448), "{scale}");
+        }
+        core.set_render_scale(10.0);
+        assert_eq!(core.capture_width(), 2048);
+    }
+
+    /// Packet bytes from QWs of four little-endian words.
+    fn packet(qws: &[[u32; 4]]) -> Vec<u8> {
+        qws.iter().flatten().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    /// A packet that uploads the GIF packet `gif` to datamem[108] and MSCALs the
+    /// pass-through entry, which XGKICKs it unmodified.
+    fn kick_packet(gif: &[[u32; 4]]) -> Vec<u8> {
+        let mut qws = vec![
+            [0x0100_0101, 0, 0, 0],                                         // STCYCL wl=1 cl=1
+            [(0x6C << 24) | ((gif.len() as u32) << 16) | 108, 0, 0, 0],     // UNPACK V4-32
+        ];
+        qws.extend_from_slice(gif);
+        qws.push([(0x14 << 24) | u32::from(vu1_program::KICK_ENTRY), 0, 0, 0]); // MSCAL
+        packet(&qws)
+    }
+
+    /// PACKED GIF tag with PRE=1 and two registers, RGBAQ then XYZ2.
+    fn gif_tag(nloop: u32, eop: bool, prim: u32) -> [u32; 4] {
+        [nloop | (u32::from(eop) << 15), (1 << 14) | (prim << 15) | (2 << 28), 0x51, 0]
+    }
+
+    fn rgba(r: f32, b: f32) -> [u32; 4] {
+        [r, 0.0, b, 1.0].map(f32::to_bits)
+    }
+
+    fn xyz2(x: u32, y: u32) -> [u32; 4] {
+        [x << 4, y << 4, 0, 0]
+    }
+
+    #[test]
+    fn submitted_vif_packet_unpacks_into_vu_memory() {
+        let mut core = core();
+        let bytes = packet(&[
+            [0x0100_0101, 0, 0, 0],                         // STCYCL wl=1 cl=1
+            [(0x6C << 24) | (2 << 16) | 300, 0, 0, 0],      // UNPACK V4-32 num=2 addr=300
+            [1.0f32, 2.0, 3.0, 4.0].map(f32::to_bits),
+            [5.0f32, 6.0, 7.0, 8.0].map(f32::to_bits),
+            [(0x14 << 24) | u32::from(vu1_program::KICK_ENTRY), 0, 0, 0], // MSCAL
+            [0x11 << 24, 0, 0, 0],                          // FLUSH
+        ]);
+        assert!(core.submit_vif_packet(&bytes).is_ok());
+        assert_eq!(core.vu1.data_mem[300], [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(core.vu1.data_mem[301], [5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(core.frame_count, 1);
+    }
+
+    #[test]
+    fn xy_offset_shifts_the_cube() {
+        let mut plain = core();
+        plain.advance_frame();
+        let mut shifted = core();
+        shifted.set_xy_offset(10 << 4, 5 << 4); // 12.4 fixed point
+        shifted.advance_frame();
+
+        let (x0, y0, x1, y1) = plain.prims.as_slice()[0].bounding_box().unwrap();
+        let moved = shifted.prims.as_slice()[0].bounding_box().unwrap();
+        assert_eq!(moved, (x0 - 10, y0 - 5, x1 - 10, y1 - 5));
+        let w = plain.gs_fb.width;
+        let mid = (y0 as usize + y1 as usize) / 2 * w;
+        assert_eq!(
+            shifted.gs_fb.pixels()[mid - 5 * w + x0 as usize - 10..][..100],
+            plain.gs_fb.pixels()[mid + x0 as usize..][..100],
+        );
+    }
+
+    #[test]
+    fn frame_diff_tints_only_what_moved() {
+        let tinted = |time_scale: f32| {
+            let mut core = core();
+            core.set_time_scale(time_scale);
+            core.set_frame_diff(true);
+            core.advance_frame();
+            core.advance_frame();
+            // prev_pixels now holds this frame untinted: what differs was tinted
+            core.gs_fb.pixels().iter().zip(&core.prev_pixels).filter(|(a, b)| a != b).count()
+        };
+        assert_eq!(tinted(0.0), 0);
+        assert!(tinted(1.0) > 1000);
+    }
+
+    #[test]
+    fn sprite_fast_path_fills_what_the_triangles_would() {
+        let v = |x, y| GifVertex { r: 201, g: 99, b: 37, a: 128, x, y, fog: 255 };
+        let quad = [v(10, 10), v(10, 40), v(50, 10), v(50, 40), v(50, 10), v(10, 40)];
+        assert!(detect_sprite_quad(&quad[..3], &quad[3..]).is_some());
+
+        let mut core = core();
+        core.set_sprite_fast_path(true);
+        core.gs_fb.clear(0xFF_08_0A_14);
+        core.draw_triangle_list(&quad, false);
+
+        let mut fb = gs_rasterizer::Framebuffer::new();
+        fb.clear(0xFF_08_0A_14);
+        for t in quad.chunks_exact(3) {
+            gs_rasterizer::rasterize_triangle(
+                &mut fb, &t[0], &t[1], &t[2],
+                gs_rasterizer::ColorInterp::FloatRounded, core.pixel_center, false,
+            );
+        }
+        assert!(fb.pixels().contains(&0xFF_25_63_C9));
+        assert!(fb.pixels() == core.gs_fb.pixels());
+    }
+
+    #[test]
+    fn packets_alternate_slots_and_both_transfer() {
+        let mut core = core();
+        let xy = |core: &EmulatorCore| -> Vec<(i32, i32)> {
+            core.prims.as_slice().iter().flat_map(|p| p.vertices.iter().map(|v| (v.x, v.y))).collect()
+        };
+
+        let (madr0, qwc0) = core.ee.build_packet();
+        assert!(core.run_to_gif(madr0, qwc0));
+        let frame0 = xy(&core);
+        let (madr1, qwc1) = core.ee.build_packet();
+        assert!(core.run_to_gif(madr1, qwc1));
+        let frame1 = xy(&core);
+        assert_ne!(madr0, madr1);
+        assert!(!frame0.is_empty() && frame1.len() == frame0.len() && frame1 != frame0);
+
+        // Building frame 1 left frame 0's slot intact: it transfers again unchanged
+        assert!(core.run_to_gif(madr0, qwc0));
+        assert_eq!(xy(&core), frame0);
+        assert_eq!(core.ee.build_packet().0, madr0);
+    }
+
+    #[test]
+    fn mscnt_continues_the_program_across_packets() {
+        use vu1_program::{i, l_iaddiu, l_xgkick, u_nop};
+        let mut core = core();
+        core.vu1.code_mem[..4].copy_from_slice(&[
+            i(u_nop(), l_iaddiu(1, 0, 1)),
+            i(u_nop(), l_xgkick(0)),
+            i(u_nop(), l_iaddiu(2, 0, 2)),
+            i(u_nop(), l_xgkick(0)),
+        ]);
+
+        // MSCAL at 0 runs up to the first XGKICK ...
+        assert!(core.submit_vif_packet(&packet(&[[0x14 << 24, 0, 0, 0]])).is_ok());
+        assert_eq!((core.vu1.vi[1], core.vu1.vi[2]), (1, 0));
+        // ... and MSCNT in the next packet picks up after it, where MSCAL would restart
+        assert!(core.submit_vif_packet(&packet(&[[0x17 << 24, 0, 0, 0]])).is_ok());
+        assert_eq!((core.vu1.vi[1], core.vu1.vi[2]), (1, 2));
+        assert_eq!(core.vu1.pc, 4);
+    }
+
+    #[test]
+    fn mscal_execaddr_selects_the_routine() {
+        use vu1_program::{i, l_iaddiu, l_xgkick, u_nop};
+        let mut core = core();
+        core.vu1.code_mem[..4].copy_from_slice(&[
+            i(u_nop(), l_iaddiu(1, 0, 1)),
+            i(u_nop(), l_xgkick(0)),
+            i(u_nop(), l_iaddiu(2, 0, 2)),
+            i(u_nop(), l_xgkick(0)),
+        ]);
+
+        // MSCAL execaddr=2 runs only the second routine
+        assert!(core.submit_vif_packet(&packet(&[[(0x14 << 24) | 2, 0, 0, 0]])).is_ok());
+        assert_eq!((core.vu1.vi[1], core.vu1.vi[2]), (0, 2));
+        assert!(core.submit_vif_packet(&packet(&[[0x14 << 24, 0, 0, 0]])).is_ok());
+        assert_eq!((core.vu1.vi[1], core.vu1.vi[2]), (1, 2));
+    }
+
+    #[test]
+    fn an_aborted_run_kicks_nothing() {
+        use vu1_program::{i, l_b, u_nop};
+        let mut core = core();
+        core.vu1.code_mem[0] = i(u_nop(), l_b(-1));
+
+        // A well-formed triangle at datamem[109], which an aborted run must not kick
+        let mut qws = vec![[(0x6C << 24) | (7 << 16) | 109, 0, 0, 0], gif_tag(3, true, 3)];
+        for (x, y) in [(20, 20), (20, 60), (60, 20)] {
+            qws.extend([rgba(1.0, 0.0), xyz2(x, y)]);
+        }
+        qws.push([0x14 << 24, 0, 0, 0]); // MSCAL 0
+        let frames = core.frame_count;
+        assert!(core.submit_vif_packet(&packet(&qws)).is_ok());
+        assert!(core.prims.as_slice().is_empty());
+        assert_eq!(core.frame_count, frames);
+    }
+
+    #[test]
+    fn xtop_reads_the_buffer_each_start_filled() {
+        use vu1_program::{i, l_xgkick, l_xitop, l_xtop, u_nop};
+        let mut core = core();
+        core.vu1.code_mem[..2].copy_from_slice(&[
+            i(u_nop(), l_xtop(1)),
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        core.vu1.code_mem[2..4].copy_from_slice(&[
+            i(u_nop(), l_xitop(2)),
+            i(u_nop(), l_xgkick(0)),
+        ]);
+
+        let setup = [
+            [(0x03 << 24) | 100, 0, 0, 0], // BASE 100
+            [(0x02 << 24) | 400, 0, 0, 0], // OFFSET 400
+            [0x14 << 24, 0, 0, 0],         // MSCAL: TOP = 100, then flip
+        ];
+        assert!(core.submit_vif_packet(&packet(&setup)).is_ok());
+        assert_eq!(core.vu1.vi[1], 100);
+        // After the flip the next start sees the second buffer
+        assert!(core.submit_vif_packet(&packet(&[[0x14 << 24, 0, 0, 0]])).is_ok());
+        assert_eq!(core.vu1.vi[1], 500);
+
+        let itop = [[(0x04 << 24) | 37, 0, 0, 0], [(0x14 << 24) | 2, 0, 0, 0]]; // ITOP, MSCAL 2
+        assert!(core.submit_vif_packet(&packet(&itop)).is_ok());
+        assert_eq!(core.vu1.vi[2], 37);
+    }
+
+    #[test]
+    fn packed_colors_decode_like_the_f32_path() {
+        let rgb = |core: &EmulatorCore| -> Vec<[u8; 3]> {
+            core.prims.as_slice().iter()
+                .flat_map(|p| p.vertices.iter().map(|v| [v.r, v.g, v.b]))
+                .collect()
+        };
+        let mut plain = core();
+        plain.advance_frame();
+        let mut packed = core();
+        packed.set_packed_colors(true);
+        packed.advance_frame();
+
+        // Colors take a quarter of the QWs they did
+        let qws = |core: &EmulatorCore| core.ee.hexdump_packet().lines().count();
+        assert!(qws(&packed) < qws(&plain));
+
+        let (a, b) = (rgb(&plain), rgb(&packed));
+        assert!(!a.is_empty() && a.len() == b.len());
+        for (a, b) in a.iter().zip(&b) {
+            assert!(a.iter().zip(b).all(|(x, y)| x.abs_diff(*y) <= 1), "{a:?} vs {b:?}");
+        }
+    }
+
+    #[test]
+    fn primitive_limit_draws_up_to_the_cap_and_flags_the_rest() {
+        let mut capped = core();
+        capped.set_primitive_limit(7);
+        capped.advance_frame();
+        assert!(capped.prims_dropped);
+        assert!(capped.log.iter().any(|m| m.contains("over the per-frame limit of 7")));
+
+        // The same as drawing just the first 7 triangles of the uncapped frame
+        let mut full = core();
+        full.advance_frame();
+        assert!(!full.prims_dropped);
+        let first = full.prims.as_slice()[0].vertices[..7 * 3].to_vec();
+        assert!(capped.gs_fb.pixels() != full.gs_fb.pixels());
+        full.gs_fb.clear(0xFF_08_0A_14);
+        full.draw_triangle_list(&first, false);
+        assert!(capped.gs_fb.pixels() == full.gs_fb.pixels());
+    }
+
+    #[test]
+    fn svg_export_lists_the_cube_without_advancing() {
+        let mut core = core();
+        core.advance_frame();
+        let pixels = core.gs_fb.pixels().to_vec();
+        let dump = core.ee.hexdump_packet();
+        let kicked = core.prims.as_slice()[0].vertices.len();
+
+        let svg = core.export_frame_svg();
+        let polygons: Vec<&str> = svg.lines().filter(|l| l.starts_with("<polygon")).collect();
+        assert_eq!(polygons.len(), 12);
+        for polygon in &polygons {
+            let points = polygon.split('"').nth(1).unwrap();
+            let corners: Vec<Vec<i32>> = points
+                .split(' ')
+                .map(|p| p.split(',').map(|n| n.parse().unwrap()).collect())
+                .collect();
+            assert!(corners.len() == 3 && corners.iter().all(|c| c.len() == 2), "{polygon}");
+        }
+        // Culled triangles are there too, as outlines
+        assert!(polygons.iter().any(|p| p.ends_with("class=\"back\"/>")));
+
+        // The same frame again: nothing advanced, and the live frame is untouched
+        assert_eq!(core.export_frame_svg(), svg);
+        assert_eq!(core.ee.next_frame(), 1);
+        assert_eq!(core.ee.hexdump_packet(), dump);
+        assert_eq!(core.prims.as_slice()[0].vertices.len(), kicked);
+        assert!(core.gs_fb.pixels() == pixels);
+    }
+
+    #[test]
+    fn malformed_vif_commands_are_logged() {
+        let mut core = core();
+        let bytes = packet(&[
+            [0x7F00_1234, 0, 0, 0],                         // no such VIF command
+            [(0x6C << 24) | (2 << 16) | 300, 0, 0, 0],      // UNPACK of 2 QWs ...
+            [1.0f32, 2.0, 3.0, 4.0].map(f32::to_bits),      // ... with only 1 sent
+        ]);
+        assert!(core.submit_vif_packet(&bytes).is_ok());
+        let log: Vec<&String> = core.log.iter().collect();
+        assert_eq!(log.len(), 2, "{log:?}");
+        assert!(log[0].contains("unsupported command 0x7F (tag 0x7F001234)"));
+        assert!(log[1].contains("packet ended mid-UNPACK (1 QWs still expected"));
+    }
+
+    #[test]
+    fn ee_sprite_decodes_and_fills_its_rectangle() {
+        let mut core = core();
+        core.show_sprite(100, 50, 199, 79, 0x20_40_C0_FF, false);
+        core.advance_frame();
+
+        let prims = core.prims.as_slice();
+        assert_eq!(prims.len(), 1);
+        assert_eq!(prims[0].prim_type, gif::PRIM_SPRITE);
+        assert_eq!(prims[0].vertices.len(), 2);
+
+        let w = core.gs_fb.width;
+        for (i, &px) in core.gs_fb.pixels().iter().enumerate() {
+            let (x, y) = (i % w, i / w);
+            let inside = (100..=199).contains(&x) && (50..=79).contains(&y);
+            assert_eq!(px == 0xFF_C0_40_20, inside, "({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn scene_objects_are_drawn_in_draw_order() {
+        let mut core = core();
+        // Added first but drawn second: red on the left, blue on the right
+        assert!(core.add_scene_cube(-1.5, 0.0, 0.0, 0.5, 0xFF_00_00, 1).is_ok());
+        assert!(core.add_scene_cube(1.5, 0.0, 0.0, 0.5, 0x00_00_FF, 0).is_ok());
+        core.advance_frame();
+
+        let center = core.gs_fb.width as i32 / 2;
+        let mut sides = Vec::new();
+        for prim in core.prims.as_slice() {
+            for v in &prim.vertices {
+                assert!(v.r == 0 || v.b == 0);
+                let side = if v.b > 0 { "blue" } else { "red" };
+                assert_eq!(v.x > center, side == "blue");
+                if sides.last() != Some(&side) {
+                    sides.push(side);
+                }
+            }
+        }
+        assert_eq!(sides, ["blue", "red"]);
+    }
+
+    #[test]
+    fn auto_winding_flips_a_cw_imported_triangle_to_face_the_camera() {
+        // Seen from the camera on +z, (-1,-1) → (-1,1) → (1,-1) runs clockwise
+        let positions = [-1.0, -1.0, 0.5, -1.0, 1.0, 0.5, 1.0, -1.0, 0.5];
+        let covered = |auto_winding: bool| {
+            let mut core = core();
+            core.set_auto_winding(auto_winding);
+            assert!(core.add_scene_mesh(&positions, &[0, 1, 2], &[3], 0xFF_FF_FF, 0).is_ok());
+            core.advance_frame();
+            let clear = core.gs_fb.pixels()[0];
+            core.gs_fb.pixels().iter().filter(|&&px| px != clear).count()
+        };
+        assert_eq!(covered(false), 0);
+        assert!(covered(true) > 1000);
+    }
+
+    #[test]
+    fn ground_adds_primitives_below_the_cube() {
+        let mut plain = core();
+        plain.advance_frame();
+        let cube = plain.prims.as_slice().len();
+
+        let mut core = core();
+        core.set_show_ground(true);
+        core.advance_frame();
+        let prims = core.prims.as_slice();
+        assert!(prims.len() > cube);
+
+        // The ground goes first; seen level from the camera it all lies under the
+        // horizon through the cube's centre
+        let ground = &prims[..prims.len() - cube];
+        let horizon = core.gs_fb.height as i32 / 2;
+        assert!(ground.iter().flat_map(|p| &p.vertices).all(|v| v.y > horizon));
+        assert!(ground.iter().flat_map(|p| &p.vertices).all(|v| v.r == v.g && v.g == v.b));
+    }
+
+    #[test]
+    fn linear_lighting_brightens_partly_lit_faces() {
+        let rgb = |linear: bool| -> Vec<[u8; 3]> {
+            let mut core = core();
+            core.set_linear_lighting(linear);
+            core.advance_frame();
+            core.prims.as_slice().iter().flat_map(|p| &p.vertices).map(|v| [v.r, v.g, v.b]).collect()
+        };
+        let (naive, linear) = (rgb(false), rgb(true));
+        assert_eq!(naive.len(), linear.len());
+        // sqrt(intensity) ≥ intensity: nothing darkens, and the mid-tones lift
+        let pairs = || naive.iter().flatten().zip(linear.iter().flatten());
+        assert!(pairs().all(|(n, l)| l >= n));
+        assert!(pairs().any(|(&n, &l)| n < 255 && l >= n + 20));
+    }
+
+    #[test]
+    fn profile_stages_add_up_to_the_frame() {
+        let mut core = core();
+        core.advance_frame();
+        assert_eq!(core.last_profile, None);
+
+        core.set_profile(true);
+        core.advance_frame();
+        let p = core.last_profile.unwrap();
+        let stages = p.build_packet + p.dma + p.vif + p.vu1 + p.gif + p.rasterize + p.present;
+        // Only the bookkeeping between stages is left out of the sum
+        assert!(p.rasterize > 0.0 && p.vu1 > 0.0);
+        assert!(stages <= p.total + 1e-9 && stages >= p.total * 0.5, "{p:?}");
+    }
+
+    #[test]
+    fn triangle_and_sprite_tags_in_one_packet_both_draw() {
+        let mut gif = vec![gif_tag(3, false, 3)];
+        for (x, y) in [(20, 20), (20, 60), (60, 20)] {
+            gif.extend([rgba(1.0, 0.0), xyz2(x, y)]);
+        }
+        gif.push(gif_tag(2, true, u32::from(gif::PRIM_SPRITE)));
+        for (x, y) in [(100, 100), (140, 130)] {
+            gif.extend([rgba(0.0, 1.0), xyz2(x, y)]);
+        }
+
+        let mut core = core();
+        assert!(core.submit_vif_packet(&kick_packet(&gif)).is_ok());
+        let types: Vec<u8> = core.prims.as_slice().iter().map(|p| p.prim_type).collect();
+        assert_eq!(types, [3, gif::PRIM_SPRITE]);
+
+        let w = core.gs_fb.width;
+        let at = |x: usize, y: usize| core.gs_fb.pixels()[y * w + x];
+        assert_eq!(at(25, 25), 0xFF_00_00_FF);
+        assert_eq!(at(100, 100), 0xFF_FF_00_00);
+        assert_eq!(at(139, 129), 0xFF_FF_00_00);
+        assert_ne!(at(140, 130), 0xFF_FF_00_00);
+    }
+
+    #[test]
+    fn telemetry_json_is_a_flat_object_of_the_counters() {
+        let mut core = core();
+        core.advance_frame();
+        let json = core.telemetry_json();
+
+        // A flat object of "key":value pairs, each value a number or a bool
+        let body = json.strip_prefix('{').and_then(|s| s.strip_suffix('}')).unwrap();
+        let fields: Vec<(&str, &str)> = body
+            .split(',')
+            .map(|pair| pair.split_once(':').unwrap())
+            .map(|(k, v)| (k.strip_prefix('"').and_then(|k| k.strip_suffix('"')).unwrap(), v))
+            .collect();
+        for &(key, value) in &fields {
+            assert!(value.parse::<f64>().is_ok() || value.parse::<bool>().is_ok(), "{key}: {value}");
+        }
+        let keys: Vec<&str> = fields.iter().map(|&(k, _)| k).collect();
+        assert_eq!(keys, [
+            "emulatedCycles", "vu1MatOps", "vu1Cycles", "frameCount", "primitivesDropped", "emptyFrame",
+        ]);
+        assert!(fields.contains(&("frameCount", "1")));
+    }
+
+    #[test]
+    fn stills_differ_by_frame_and_leave_the_live_frame_alone() {
+        let mut core = core();
+        core.set_frame_diff(true);
+        core.advance_frame();
+        let pixels = core.gs_fb.pixels().to_vec();
+        let prims = core.prims.as_slice().len();
+        let (prev_pixels, cycles) = (core.prev_pixels.clone(), core.vu1_cycles);
+        core.push_log("live".into());
+        core.set_vu1_breakpoint(13);
+
+        let (still0, still90) = (core.render_still(0), core.render_still(90));
+        assert_eq!(still0.len(), pixels.len() * 4);
+        assert_eq!(still0, core.render_still(0));
+        assert_ne!(still0, still90);
+
+        // The breakpoint neither halted a still nor went away
+        assert!(!core.vu1_halted);
+        assert_eq!(core.gs_fb.pixels(), &pixels[..]);
+        assert_eq!(core.prims.as_slice().len(), prims);
+        assert_eq!((&core.prev_pixels, core.vu1_cycles), (&prev_pixels, cycles));
+        assert_eq!(core.log, ["live"]);
+        assert!(!core.empty_frame && !core.prims_dropped);
+        core.advance_frame();
+        assert!(core.vu1_halted);
+    }
+
+    #[test]
+    fn wire_overlay_outlines_filled_triangles() {
+        let mut gif = vec![gif_tag(3, true, 3)];
+        for (x, y) in [(20, 20), (20, 60), (60, 20)] {
+            gif.extend([rgba(1.0, 0.0), xyz2(x, y)]);
+        }
+        let mut core = core();
+        core.set_wire_overlay(true);
+        assert!(core.submit_vif_packet(&kick_packet(&gif)).is_ok());
+
+        let w = core.gs_fb.width;
+        let at = |x: usize, y: usize| core.gs_fb.pixels()[y * w + x];
+        for (x, y) in [(30, 30), (25, 45), (45, 25)] {
+            assert_eq!(at(x, y), 0xFF_00_00_FF, "interior ({x}, {y})");
+        }
+        for (x, y) in [(20, 40), (40, 20), (40, 40), (20, 20)] {
+            assert_eq!(at(x, y), 0xFF_FF_FF_FF, "edge ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn motion_blur_leaves_a_trail_where_the_cube_was() {
+        // Frame 2 shifts the cube 120 pixels right, uncovering the left of its old place
+        let frames = |blur: f32| {
+            let mut core = core();
+            core.set_motion_blur(blur);
+            core.advance_frame();
+            let first = core.gs_fb.pixels().to_vec();
+            core.set_xy_offset(120, 0);
+            core.advance_frame();
+            (first, core.gs_fb.pixels().to_vec())
+        };
+        let (sharp1, sharp2) = frames(0.0);
+        let (_, blurred) = frames(0.6);
+        let clear = sharp1[0];
+
+        let vacated: Vec<usize> = (0..sharp1.len())
+            .filter(|&i| sharp1[i] != clear && sharp2[i] == clear)
+            .collect();
+        assert!(vacated.len() > 1000);
+        assert!(vacated.iter().all(|&i| blurred[i] != clear));
+        // Far from both cubes the history is just the clear color
+        assert_eq!(blurred[0], clear);
+    }
+
+    #[test]
+    fn all_culled_frame_reports_empty_and_shows_the_clear_color() {
+        // Counter-clockwise on screen: back-facing, so culled
+        let mut gif = vec![gif_tag(3, true, 3)];
+        for (x, y) in [(20, 20), (60, 20), (20, 60)] {
+            gif.extend([rgba(1.0, 0.0), xyz2(x, y)]);
+        }
+        let mut core = core();
+        assert!(core.submit_vif_packet(&kick_packet(&gif)).is_ok());
+        assert_eq!(core.prims.as_slice().len(), 1);
+        assert!(core.empty_frame);
+        assert!(core.telemetry_json().contains("\"emptyFrame\":true"));
+        let clear = core.gs_fb.pixels()[0];
+        assert!(core.gs_fb.pixels().iter().all(|&px| px == clear));
+
+        core.advance_frame();
+        assert!(!core.empty_frame);
+    }
+
+    #[test]
+    fn interleaved_layout_unpacks_the_same_vertices() {
+        let run = |interleaved: bool| {
+            let mut core = core();
+            core.set_interleaved_attributes(interleaved);
+            core.advance_frame();
+            let prims = core.prims.as_slice();
+            let xy: Vec<(i32, i32)> =
+                prims.iter().flat_map(|p| &p.vertices).map(|v| (v.x, v.y)).collect();
+            (core.vu1.data_mem, xy)
+        };
+        let (block, block_xy) = run(false);
+        let (inter, inter_xy) = run(true);
+        // [position, normal, color] of vertex i: three blocks of 36 against runs of 3
+        for i in 0..36 {
+            let attrs = [block[i], block[36 + i], block[72 + i]];
+            assert_eq!(attrs, inter[3 * i..3 * i + 3], "vertex {i}");
+        }
+        assert_eq!(block_xy, inter_xy);
+    }
+
+    #[test]
+    fn hold_frame_redraws_the_same_geometry_with_new_settings() {
+        let mut core = core();
+        core.advance_frame();
+        let plain = core.gs_fb.pixels().to_vec();
+        let (next, packet) = (core.ee.next_frame(), core.ee.hexdump_packet());
+
+        core.set_wire_overlay(true);
+        core.hold_frame();
+        let wired = core.gs_fb.pixels().to_vec();
+        assert_ne!(wired, plain);
+        let changed = plain.iter().zip(&wired).filter(|(a, b)| a != b);
+        assert!(changed.clone().count() > 100);
+        assert!(changed.map(|(_, &w)| w).all(|w| w == 0xFF_FF_FF_FF));
+
+        // The EE built nothing new: same frame index, same packet
+        assert_eq!(core.ee.next_frame(), next);
+        assert_eq!(core.ee.hexdump_packet(), packet);
+        core.set_wire_overlay(false);
+        core.hold_frame();
+        assert_eq!(core.gs_fb.pixels(), &plain[..]);
+        assert_eq!(core.frame_count, 3);
+    }
+
+    #[test]
+    fn hold_frame_after_a_data_only_packet_redraws_the_last_frame() {
+        let mut core = core();
+        core.advance_frame();
+        let frame = core.gs_fb.pixels().to_vec();
+
+        // UNPACK V4-32 num=1 addr=0 and its QW, with no MSCAL
+        let data = [[(0x6C << 24) | (1 << 16), 0, 0, 0], [0; 4]];
+        assert!(core.submit_vif_packet(&packet(&data)).is_ok());
+        assert!(!core.prims.as_slice().is_empty());
+        core.hold_frame();
+        assert!(!core.empty_frame);
+        assert_eq!(core.gs_fb.pixels(), &frame[..]);
+    }
+
+    #[test]
+    fn debug_normals_paint_a_plus_x_face_red() {
+        let mut core = core();
+        // A quad facing +X, counter-clockwise seen from the camera on the +X axis
+        let positions = [0.5, -1.0, 1.0, 0.5, -1.0, -1.0, 0.5, 1.0, -1.0, 0.5, 1.0, 1.0];
+        assert!(core.add_scene_mesh(&positions, &[0, 1, 2, 3], &[4], 0xFF_FF_FF, 0).is_ok());
+        core.set_view(&[3.0, 0.0, 0.0], &[0.0; 3], &[0.0, 1.0, 0.0]).unwrap();
+        core.set_debug_normals(true);
+        core.advance_frame();
+
+        let (w, h) = (core.gs_fb.width, core.gs_fb.height);
+        let mut reddish = 0;
+        for y in h / 2 - 10..h / 2 + 10 {
+            for x in w / 2 - 10..w / 2 + 10 {
+                let px = core.gs_fb.pixels()[y * w + x];
+                let [r, g, b] = [0, 8, 16].map(|s| (px >> s) & 0xFF);
+                // [1, 0, 0] * 0.5 + 0.5 = [1, 0.5, 0.5]
+                if r >= 250 && g.abs_diff(128) <= 2 && b.abs_diff(128) <= 2 {
+                    reddish += 1;
+                }
+            }
+        }
+        assert_eq!(reddish, 400);
+    }
+
+    #[test]
+    fn benchmark_reports_the_injected_timing_and_leaves_the_live_frame_alone() {
+        let mut core = core();
+        core.advance_frame();
+        let (pixels, prims) = (core.gs_fb.pixels().to_vec(), core.prims.as_slice().len());
+        let (next, telemetry) = (core.ee.next_frame(), core.telemetry_json());
+
+        // 50 frames between clock readings 1000 ms apart: 50 fps, 20000 us a frame
+        let mut clock = [250.0, 1250.0].into_iter();
+        let result = core.run_benchmark(50, || clock.next().unwrap());
+        assert_eq!(result, profile::BenchResult { frames: 50, total_ms: 1000.0 });
+        assert_eq!(result.fps(), 50.0);
+        assert_eq!(result.avg_frame_us(), 20_000.0);
+        assert!(clock.next().is_none());
+
+        assert_eq!(core.gs_fb.pixels(), &pixels[..]);
+        assert_eq!(core.prims.as_slice().len(), prims);
+        assert_eq!(core.ee.next_frame(), next);
+        assert_eq!(core.telemetry_json(), telemetry);
+    }
+
+    #[test]
+    fn face_colored_cube_renders_like_the_per_vertex_one() {
+        let render = |face_colors: bool| {
+            let mut core = core();
+            core.set_face_colors(face_colors);
+            let (_, qwc) = core.ee.build_packet_for_frame(0);
+            let frames: Vec<Vec<u32>> = (0..3)
+                .map(|_| {
+                    core.advance_frame();
+                    core.gs_fb.pixels().to_vec()
+                })
+                .collect();
+            (qwc, frames)
+        };
+        let (vertex_qwc, per_vertex) = render(false);
+        let (face_qwc, per_face) = render(true);
+        assert!(per_face == per_vertex);
+        // 6 face colors in place of 36 vertex colors
+        assert_eq!(vertex_qwc - face_qwc, 30);
+    }
+}
@@ -0,0 +1,86 @@
+// profile.rs — Per-stage frame timing for the profiling mode.
+// Maps to: nothing on the PS2; host-side instrumentation showing where emulation time goes.
+//
+// Natively the clock is std::time::Instant. On wasm32 Instant panics (there is no
+// monotonic clock in std there), so the browser build reads performance.now() instead,
+// falling back to Date.now(), which only resolves whole milliseconds, without a window.
+
+/// A pipeline stage timed by the profiler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    BuildPacket,
+    Dma,
+    Vif,
+    Vu1,
+    Gif,
+    Rasterize,
+    Present,
+}
+
+/// Milliseconds spent in each stage of one frame. Stages run several times per frame
+/// (VIF1, VU1 and GIF once per batch) are summed; `total` spans the whole frame, so it
+/// also covers the bookkeeping between stages.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameProfile {
+    pub build_packet: f64,
+    pub dma:          f64,
+    pub vif:          f64,
+    pub vu1:          f64,
+    pub gif:          f64,
+    pub rasterize:    f64,
+    pub present:      f64,
+    pub total:        f64,
+}
+
+impl FrameProfile {
+    pub fn add(&mut self, stage: Stage, ms: f64) {
+        let slot = match stage {
+            Stage::BuildPacket => &mut self.build_packet,
+            Stage::Dma         => &mut self.dma,
+            Stage::Vif         => &mut self.vif,
+            Stage::Vu1         => &mut self.vu1,
+            Stage::Gif         => &mut self.gif,
+            Stage::Rasterize   => &mut self.rasterize,
+            Stage::Present     => &mut self.present,
+        };
+        *slot += ms;
+    }
+}
+
+/// Throughput of a benchmark run: `frames` rendered in `total_ms`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchResult {
+    pub frames:   u32,
+    pub total_ms: f64,
+}
+
+impl BenchResult {
+    /// Frames per second; 0 if no time was measured.
+    pub fn fps(&self) -> f64 {
+        if self.total_ms > 0.0 { self.frames as f64 * 1000.0 / self.total_ms } else { 0.0 }
+    }
+
+    /// Mean microseconds per frame; 0 for no frames.
+    pub fn avg_frame_us(&self) -> f64 {
+        if self.frames > 0 { self.total_ms * 1000.0 / self.frames as f64 } else { 0.0 }
+    }
+}
+
+/// Current time in milliseconds from an arbitrary origin.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Current time in milliseconds from an arbitrary origin.
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 {
+    match web_sys::window().and_then(|w| w.performance()) {
+        Some(perf) => perf.now(),
+        None => js_sys::Date::now(),
+    }
+}
@@ -0,0 +1,356 @@
+// scene.rs — Multi-mesh scene description walked by the EE each frame.
+// Maps to: the game-side scene list a PS2 title iterates to build its VIF1 DMA chain,
+// one VIF1 → VU1 → GIF batch per mesh chunk.
+//
+// The micro-program transforms a fixed 36 vertices per MSCAL (its VU data memory
+// layout has room for exactly that), so a mesh is sent in 36-vertex batches; a short
+// last batch is padded with zero-area triangles, which the rasterizer culls.
+//
+// There is no depth buffer, so objects are painted in draw order: lower `draw_order`
+// first, ties in insertion order.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ee::{cross3, dot3, normalize3, sub3};
+
+/// Vertices the VU1 micro-program transforms per batch.
+pub const BATCH_VERTS: usize = 36;
+
+/// One model-space vertex: position, normal, RGB color in [0,1] and texture coordinate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshVertex {
+    pub pos:    [f32; 3],
+    pub normal: [f32; 3],
+    pub color:  [f32; 3],
+    pub uv:     [f32; 2],
+}
+
+/// Per-vertex tangents `[tx, ty, tz, w]` of a triangle list, for tangent-space (normal
+/// map) lighting: T follows +u across each triangle, orthogonalised against the vertex
+/// normal, and w = ±1 is the handedness, so the bitangent is `w * cross(N, T)`.
+/// Triangles with degenerate UVs get a zero tangent.
+pub fn tangents(vertices: &[MeshVertex]) -> Vec<[f32; 4]> {
+    let mut out = Vec::with_capacity(vertices.len());
+    for tri in vertices.chunks_exact(3) {
+        let e1 = sub3(tri[1].pos, tri[0].pos);
+        let e2 = sub3(tri[2].pos, tri[0].pos);
+        let (du1, dv1) = (tri[1].uv[0] - tri[0].uv[0], tri[1].uv[1] - tri[0].uv[1]);
+        let (du2, dv2) = (tri[2].uv[0] - tri[0].uv[0], tri[2].uv[1] - tri[0].uv[1]);
+        let det = du1 * dv2 - du2 * dv1;
+        if det.abs() < 1e-12 {
+            out.extend([[0.0; 4]; 3]);
+            continue;
+        }
+        let t = [0, 1, 2].map(|c| (e1[c] * dv2 - e2[c] * dv1) / det);
+        let b = [0, 1, 2].map(|c| (e2[c] * du1 - e1[c] * du2) / det);
+        for v in tri {
+            let n = v.normal;
+            let d = dot3(n, t);
+            let Some(t) = normalize3([t[0] - n[0] * d, t[1] - n[1] * d, t[2] - n[2] * d]) else {
+                out.push([0.0; 4]);
+                continue;
+            };
+            let w = if dot3(cross3(n, t), b) < 0.0 { -1.0 } else { 1.0 };
+            out.push([t[0], t[1], t[2], w]);
+        }
+    }
+    out
+}
+
+/// Crude per-vertex ambient occlusion of a triangle list, in [0, 1] with 1 fully exposed.
+/// Vertices sharing a position are welded; the more the welded vertex's edge neighbours
+/// rise above its tangent plane (normal to the summed face normals around it), the more
+/// enclosed it is and the lower its term. Flat and convex regions keep 1, the floor of a
+/// crevice darkens.
+pub fn ambient_occlusion(vertices: &[MeshVertex]) -> Vec<f32> {
+    // Per welded position: area-weighted normal sum and edge neighbours
+    let mut around: HashMap<_, ([f32; 3], Vec<_>)> = HashMap::new();
+    for tri in vertices.chunks_exact(3) {
+        let face = cross3(sub3(tri[1].pos, tri[0].pos), sub3(tri[2].pos, tri[0].pos));
+        for (i, v) in tri.iter().enumerate() {
+            let (n, neighbours) = around.entry(v.pos.map(f32::to_bits)).or_default();
+            *n = [0, 1, 2].map(|c| n[c] + face[c]);
+            neighbours.extend([tri[(i + 1) % 3].pos, tri[(i + 2) % 3].pos]);
+        }
+    }
+    vertices
+        .iter()
+        .map(|v| {
+            let (n, neighbours) = &around[&v.pos.map(f32::to_bits)];
+            let Some(n) = normalize3(*n) else {
+                return 1.0;
+            };
+            let rise: f32 = neighbours
+                .iter()
+                .filter_map(|&q| normalize3(sub3(q, v.pos)))
+                .map(|d| dot3(n, d).max(0.0))
+                .sum();
+            1.0 - rise / neighbours.len() as f32
+        })
+        .collect()
+}
+
+/// Split every triangle of a list into four at its edge midpoints, keeping the winding:
+/// [a, ab, ca], [ab, b, bc], [ca, bc, c] and the middle [ab, bc, ca]. Midpoints take the
+/// average position, color and UV of their edge and the renormalised average normal, so
+/// lighting is evaluated at more points across each face.
+pub fn subdivide(vertices: &[MeshVertex]) -> Vec<MeshVertex> {
+    let mid = |a: &MeshVertex, b: &MeshVertex| {
+        let avg3 = |x: [f32; 3], y: [f32; 3]| [0, 1, 2].map(|c| (x[c] + y[c]) * 0.5);
+        let normal = avg3(a.normal, b.normal);
+        MeshVertex {
+            pos:    avg3(a.pos, b.pos),
+            normal: normalize3(normal).unwrap_or(normal),
+            color:  avg3(a.color, b.color),
+            uv:     [0, 1].map(|c| (a.uv[c] + b.uv[c]) * 0.5),
+        }
+    };
+    let mut out = Vec::with_capacity(vertices.len() * 4);
+    for tri in vertices.chunks_exact(3) {
+        let (a, b, c) = (tri[0], tri[1], tri[2]);
+        let (ab, bc, ca) = (mid(&a, &b), mid(&b, &c), mid(&c, &a));
+        out.extend([a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca]);
+    }
+    out
+}
+
+/// Split a polygon face, given as vertex indices in winding order, into a fan of
+/// triangles around its first vertex: [i0, i1, i2], [i0, i2, i3], … keeping the face's
+/// winding. Exact for convex faces (and any face star-shaped around its first vertex);
+/// fewer than 3 indices give no triangles.
+pub fn triangulate_fan(indices: &[u32]) -> Vec<[u32; 3]> {
+    match indices.split_first() {
+        Some((&first, rest)) => rest.windows(2).map(|w| [first, w[0], w[1]]).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// A triangle list (3 vertices per triangle, CCW front faces).
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    vertices: Vec<MeshVertex>,
+    flat:     bool, // normals were derived from the winding, so they can't judge it
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<MeshVertex>) -> Result<Self, String> {
+        if vertices.is_empty() || !vertices.len().is_multiple_of(3) {
+            return Err(format!(
+                "mesh needs a non-empty triangle list, got {} vertices", vertices.len(),
+            ));
+        }
+        Ok(Mesh { vertices, flat: false })
+    }
+
+    /// Build a flat-shaded, white mesh from indexed polygon faces, e.g. an imported
+    /// model's N-gons: `face_sizes` splits `indices` into faces of 3 or more vertices
+    /// each, and every face is fanned into triangles (see `triangulate_fan`) that take
+    /// their own geometric normal.
+    pub fn from_polygons(
+        positions:  &[[f32; 3]],
+        indices:    &[u32],
+        face_sizes: &[u32],
+    ) -> Result<Self, String> {
+        let mut vertices = Vec::new();
+        let mut rest = indices;
+        for (f, &n) in face_sizes.iter().enumerate() {
+            let n = n as usize;
+            if n < 3 || n > rest.len() {
+                return Err(format!(
+                    "face {f} has {n} vertices; faces need at least 3 and {} indices remain",
+                    rest.len(),
+                ));
+            }
+            let (face, tail) = rest.split_at(n);
+            rest = tail;
+            if let Some(&i) = face.iter().find(|&&i| i as usize >= positions.len()) {
+                return Err(format!(
+                    "face {f} uses vertex {i}, but there are only {}", positions.len(),
+                ));
+            }
+            for tri in triangulate_fan(face) {
+                let p = tri.map(|i| positions[i as usize]);
+                let normal = normalize3(cross3(sub3(p[1], p[0]), sub3(p[2], p[0])))
+                    .unwrap_or([0.0; 3]);
+                vertices.extend(p.map(|pos| MeshVertex {
+                    pos, normal, color: [1.0; 3], uv: [0.0; 2],
+                }));
+            }
+        }
+        if !rest.is_empty() {
+            return Err(format!("{} indices left over after the last face", rest.len()));
+        }
+        let mut mesh = Mesh::new(vertices)?;
+        mesh.flat = true;
+        Ok(mesh)
+    }
+
+    pub fn vertices(&self) -> &[MeshVertex] {
+        &self.vertices
+    }
+
+    /// Make every triangle wind CCW as seen from outside (right-handed model space), the
+    /// front-face convention back-face culling keeps. Returns how many were flipped.
+    ///
+    /// A triangle faces outside when its geometric normal `cross(p1 - p0, p2 - p0)`
+    /// agrees with its vertex normals. Triangles whose normals don't say (zero, or
+    /// edge-on, or derived from the winding as in `from_polygons`) follow the sign of
+    /// the mesh's signed volume instead, which assumes a closed mesh around the origin;
+    /// a flipped flat triangle's normals are turned around with it.
+    pub fn auto_wind(&mut self) -> usize {
+        let volume: f32 = self.vertices.chunks_exact(3)
+            .map(|t| dot3(t[0].pos, cross3(t[1].pos, t[2].pos)))
+            .sum();
+        let mut flipped = 0;
+        for tri in self.vertices.chunks_exact_mut(3) {
+            let face = cross3(sub3(tri[1].pos, tri[0].pos), sub3(tri[2].pos, tri[0].pos));
+            let n = [0, 1, 2].map(|i| tri[0].normal[i] + tri[1].normal[i] + tri[2].normal[i]);
+            let agreement = if self.flat { 0.0 } else { dot3(face, n) };
+            let outward = if agreement.abs() > 1e-6 { agreement > 0.0 } else { volume >= 0.0 };
+            if !outward {
+                tri.swap(1, 2);
+                if self.flat {
+                    tri.iter_mut().for_each(|v| v.normal = v.normal.map(|c| -c));
+                }
+                flipped += 1;
+            }
+        }
+        flipped
+    }
+
+    /// Darken the vertex colors by `ambient_occlusion`. The VU1 data layout has no AO
+    /// attribute of its own, so the term rides in the color, which the lighting step
+    /// multiplies by ambient + diffuse alike.
+    pub fn bake_ao(&mut self) {
+        let ao = ambient_occlusion(&self.vertices);
+        for (v, ao) in self.vertices.iter_mut().zip(ao) {
+            v.color = v.color.map(|c| c * ao);
+        }
+    }
+
+    /// Number of VU1 batches (MSCALs) the mesh takes.
+    pub fn batch_count(&self) -> usize {
+        self.vertices.len().div_ceil(BATCH_VERTS)
+    }
+}
+
+/// Per-object surface parameters. Only a flat color multiplied into the vertex colors
+/// for now: the GS rasterizer has no texture mapping or alpha blending to drive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    pub color: [f32; 3],
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material { color: [1.0; 3] }
+    }
+}
+
+/// A mesh instance: `transform` is its column-major model matrix.
+#[derive(Clone, Debug)]
+pub struct SceneObject {
+    pub mesh:       Rc<Mesh>,
+    pub transform:  [[f32; 4]; 4],
+    pub material:   Material,
+    pub draw_order: i32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    objects: Vec<SceneObject>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Scene::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Add an object and return its index.
+    pub fn add(&mut self, object: SceneObject) -> usize {
+        self.objects.push(object);
+        self.objects.len() - 1
+    }
+
+    pub fn clear(&mut self) {
+        self.objects.clear();
+    }
+
+    /// Total VU1 batches one frame of the scene takes.
+    pub fn batch_count(&self) -> usize {
+        self.objects.iter().map(|o| o.mesh.batch_count()).sum()
+    }
+
+    /// Objects in the order they are drawn: ascending `draw_order`, stable for ties.
+    pub fn draw_list(&self) -> Vec<&SceneObject> {
+        let mut list: Vec<&SceneObject> = self.objects.iter().collect();
+        list.sort_by_key(|o| o.draw_order);
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pentagon_fans_into_three_triangles_around_its_first_vertex() {
+        assert_eq!(triangulate_fan(&[10, 11, 12, 13, 14]), [[10, 11, 12], [10, 12, 13], [10, 13, 14]]);
+        assert!(triangulate_fan(&[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn crevice_floor_is_more_occluded_than_the_rim() {
+        // A V-shaped trough along z: walls rise from the floor at x = 0 to rims at x = ±1
+        let tris = [
+            [[-1.0, 1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0, 0.0]],
+            [[-1.0, 1.0, 0.0], [-1.0, 1.0, 1.0], [0.0, 0.0, 1.0]],
+            [[1.0, 1.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+            [[1.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 1.0, 1.0]],
+        ];
+        let verts: Vec<MeshVertex> = tris.iter().flatten()
+            .map(|&pos| MeshVertex { pos, normal: [0.0, 1.0, 0.0], color: [1.0; 3], uv: [0.0; 2] })
+            .collect();
+        let ao = ambient_occlusion(&verts);
+        let at = |pos: [f32; 3]| ao[verts.iter().position(|v| v.pos == pos).unwrap()];
+
+        assert!(at([0.0, 0.0, 0.0]) < at([-1.0, 1.0, 0.0]));
+        assert!(at([0.0, 0.0, 1.0]) < at([1.0, 1.0, 1.0]));
+        assert_eq!(at([1.0, 1.0, 0.0]), 1.0);
+    }
+
+    #[test]
+    fn one_subdivision_splits_each_triangle_at_its_edge_midpoints() {
+        let vert = |pos: [f32; 3], color| MeshVertex {
+            pos, normal: [0.0, 0.0, 1.0], color, uv: [0.0; 2],
+        };
+        let tri = [
+            vert([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]),
+            vert([4.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            vert([0.0, 2.0, 0.0], [0.0, 0.0, 1.0]),
+        ];
+        let out = subdivide(&tri);
+        assert_eq!(out.len(), 4 * 3);
+
+        // Every vertex is a corner or the midpoint of an edge, carrying that edge's mean
+        // color; the middle triangle joins the three midpoints, centred on the centroid
+        let mids = [(0, 1), (1, 2), (2, 0)]
+            .map(|(a, b)| [0, 1, 2].map(|c| (tri[a].pos[c] + tri[b].pos[c]) / 2.0));
+        for v in &out {
+            let corner = tri.iter().any(|t| t.pos == v.pos && t.color == v.color);
+            let on_edge = mids.iter().zip([(0, 1), (1, 2), (2, 0)]).any(|(m, (a, b))| {
+                *m == v.pos && v.color == [0, 1, 2].map(|c| (tri[a].color[c] + tri[b].color[c]) / 2.0)
+            });
+            assert!(corner || on_edge, "{v:?}");
+        }
+        let middle: Vec<[f32; 3]> = out[9..].iter().map(|v| v.pos).collect();
+        assert_eq!(middle, mids);
+        let centroid = [0, 1, 2].map(|c| middle.iter().map(|p| p[c]).sum::<f32>() / 3.0);
+        assert_eq!(centroid, [0, 1, 2].map(|c| tri.iter().map(|t| t.pos[c]).sum::<f32>() / 3.0));
+    }
+}
@@ -1,9 +1,19 @@
 // vif1.rs — VIF1 (VPU Interface 1) packet parser.
 // Maps to: PS2 VIF1 unpacking VIF1 DMA packets into VU1 data memory.
-// Implements: STCYCL, UNPACK V4-32, MSCAL, FLUSH tags.
+// Implements: STCYCL (skipping write), OFFSET, BASE, ITOP, UNPACK S-32/S-16/S-8/V4-32/V4-16/
+// V4-8, MSCAL, MSCNT, FLUSH tags.
 
 use std::collections::VecDeque;
 
+/// How the last VU-start command in a packet asked VU1 to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VuStart {
+    /// MSCAL: jump to this micro-program address.
+    Call(u16),
+    /// MSCNT: resume from VU1's current PC (the instruction after the last XGKICK).
+    Continue,
+}
+
 pub struct Vif1 {
     pub fifo:          VecDeque<u128>,
     cl:                u8,
@@ -11,7 +21,19 @@ pub struct Vif1 {
     unpack_active:     bool,
     unpack_addr:       u16,   // VU datamem destination (in QWs)
     unpack_count:      u16,   // remaining QWs to write
-    pub mscal_addr:    Option<u16>,
+    unpack_cycle:      u8,    // QWs written in the current WL-long block
+    unpack_lane_bytes: usize, // bytes per lane: 4 (-32), 2 (-16) or 1 (-8)
+    unpack_lanes:      usize, // lanes per element: 1 (S, scalar) or 4 (V4)
+    unpack_usn:        bool,  // zero-extend (USN) instead of sign-extend
+    base:              u16,   // VIF1_BASE — first double buffer (in QWs)
+    offset:            u16,   // VIF1_OFST — distance to the second buffer (in QWs)
+    dbf:               bool,  // VIF1_STAT.DBF — which buffer TOPS points at
+    pub tops:          u16,   // VIF1_TOPS — active double-buffer base (in QWs)
+    itops:             u16,   // VIF1_ITOPS — value ITOP latches at the next VU start
+    pub top:           u16,   // VIF1_TOP — TOPS as of the last MSCAL/MSCNT (VU1 XTOP)
+    pub itop:          u16,   // VIF1_ITOP — ITOPS as of the last MSCAL/MSCNT (VU1 XITOP)
+    pub vu_start:      Option<VuStart>,
+    pub log:           Vec<String>,   // diagnostics for EmulatorCore::drain_log
 }
 
 impl Vif1 {
@@ -23,30 +45,64 @@ impl Vif1 {
             unpack_active: false,
             unpack_addr:   0,
             unpack_count:  0,
-            mscal_addr:    None,
+            unpack_cycle:  0,
+            unpack_lane_bytes: 4,
+            unpack_lanes:  4,
+            unpack_usn:    false,
+            base:          0,
+            offset:        0,
+            dbf:           false,
+            tops:          0,
+            itops:         0,
+            top:           0,
+            itop:          0,
+            vu_start:      None,
+            log:           Vec::new(),
         }
     }
 
     /// Drain the FIFO, parse VIF tags, write unpacked data into VU1 data memory.
+    ///
+    /// Stops right after an MSCAL/MSCNT, like VIF1 stalling while VU1 runs: the caller
+    /// runs the micro-program, then calls again for the rest of the FIFO, so a packet
+    /// of several batches can't overwrite a batch's data before VU1 has used it.
     pub fn process(&mut self, vu_mem: &mut [[f32; 4]; 1024]) {
         while let Some(qw) = self.fifo.pop_front() {
             if self.unpack_active {
                 // This QW is data for the active UNPACK.
-                // V4-32: four f32 values, little-endian.
                 let bytes = qw.to_le_bytes();
-                let x = f32::from_le_bytes(bytes[0..4].try_into().unwrap());
-                let y = f32::from_le_bytes(bytes[4..8].try_into().unwrap());
-                let z = f32::from_le_bytes(bytes[8..12].try_into().unwrap());
-                let w = f32::from_le_bytes(bytes[12..16].try_into().unwrap());
-
-                if (self.unpack_addr as usize) < 1024 {
-                    vu_mem[self.unpack_addr as usize] = [x, y, z, w];
-                }
-                self.unpack_addr  = self.unpack_addr.wrapping_add(1);
-                self.unpack_count -= 1;
-
-                if self.unpack_count == 0 {
-                    self.unpack_active = false;
+                if self.unpack_lane_bytes < 4 || self.unpack_lanes == 1 {
+                    // V4-16 / V4-8 / S-*: each element expands to one QW of 32-bit
+                    // integers (stored bit-cast in the f32 lanes), sign- or zero-extended;
+                    // a scalar (S) element is copied to all four lanes. The tail of a
+                    // short last QW is padding.
+                    for group in bytes.chunks_exact(self.unpack_lanes * self.unpack_lane_bytes) {
+                        if self.unpack_count == 0 {
+                            break;
+                        }
+                        let mut lanes = [0.0f32; 4];
+                        for (lane, b) in lanes.iter_mut().zip(group.chunks_exact(self.unpack_lane_bytes)) {
+                            let v = match (b.len(), self.unpack_usn) {
+                                (1, true)  => b[0] as u32,
+                                (1, false) => b[0] as i8 as i32 as u32,
+                                (2, true)  => u16::from_le_bytes([b[0], b[1]]) as u32,
+                                (2, false) => i16::from_le_bytes([b[0], b[1]]) as i32 as u32,
+                                _          => u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                            };
+                            *lane = f32::from_bits(v);
+                        }
+                        if self.unpack_lanes == 1 {
+                            lanes = [lanes[0]; 4];
+                        }
+                        self.write_unpacked(vu_mem, lanes);
+                    }
+                } else {
+                    // V4-32: four f32 values, little-endian.
+                    let x = f32::from_le_bytes(bytes[0..4].try_into().unwrap());
+                    let y = f32::from_le_bytes(bytes[4..8].try_into().unwrap());
+                    let z = f32::from_le_bytes(bytes[8..12].try_into().unwrap());
+                    let w = f32::from_le_bytes(bytes[12..16].try_into().unwrap());
+                    self.write_unpacked(vu_mem, [x, y, z, w]);
                 }
             } else {
                 // Parse VIF tag from the low 32 bits of the QW.
@@ -55,36 +111,189 @@ impl Vif1 {
 
                 match cmd {
                     0x01 => {
-                        // STCYCL: bits [15:8] = wl, bits [7:0] = cl
+                        // STCYCL: bits [15:8] = wl, bits [7:0] = cl. With CL > WL, UNPACK
+                        // writes WL QWs then skips CL - WL (skipping write); filling
+                        // write (WL > CL) is not implemented and writes contiguously
                         self.wl = ((tag >> 8) & 0xFF) as u8;
                         self.cl = (tag & 0xFF) as u8;
                     }
-                    0x6C => {
-                        // UNPACK V4-32
+                    0x02 => {
+                        // OFFSET: bits [9:0] = size of a double buffer; resets DBF so
+                        // TOPS points back at BASE
+                        self.offset = (tag & 0x3FF) as u16;
+                        self.dbf    = false;
+                        self.tops   = self.base;
+                    }
+                    0x03 => {
+                        // BASE: bits [9:0] = start of the first double buffer
+                        self.base = (tag & 0x3FF) as u16;
+                    }
+                    0x04 => {
+                        // ITOP: bits [9:0] = ITOPS, handed to VU1 (XITOP) at the next start
+                        self.itops = (tag & 0x3FF) as u16;
+                    }
+                    0x60..=0x62 | 0x6C..=0x6E => {
+                        // UNPACK S-32 (0x60) / S-16 (0x61) / S-8 (0x62) and V4-32 (0x6C) /
+                        // V4-16 (0x6D) / V4-8 (0x6E): cmd[3:2] = VN, cmd[1:0] = VL
                         // bits [23:16] = NUM (number of QWs to write)
+                        // bit  [15]    = FLG (ADDR is relative to TOPS)
+                        // bit  [14]    = USN (zero-extend; only affects 8/16-bit formats)
                         // bits [9:0]   = ADDR (VU datamem destination in QWs)
                         let num  = ((tag >> 16) & 0xFF) as u16;
-                        let addr = (tag & 0x3FF) as u16;
+                        let flg  = (tag >> 15) & 1 != 0;
+                        let mut addr = (tag & 0x3FF) as u16;
+                        if flg {
+                            // Double-buffered stream: land in the half selected by TOPS
+                            addr = addr.wrapping_add(self.tops) & 0x3FF;
+                        }
                         if num > 0 {
                             self.unpack_active = true;
                             self.unpack_addr   = addr;
                             self.unpack_count  = num;
+                            self.unpack_cycle  = 0;
+                            self.unpack_lane_bytes = 4 >> (cmd & 0x3);
+                            self.unpack_lanes  = if cmd & 0xC == 0 { 1 } else { 4 };
+                            self.unpack_usn    = (tag >> 14) & 1 != 0;
                         }
                     }
                     0x14 => {
                         // MSCAL: start VU micro-program at exec_addr
                         // bits [15:0] = execaddr
                         let exec_addr = (tag & 0xFFFF) as u16;
-                        self.mscal_addr = Some(exec_addr);
+                        self.vu_start = Some(VuStart::Call(exec_addr));
+                        self.latch_tops();
+                        self.flip_double_buffer();
+                        return;
+                    }
+                    0x17 => {
+                        // MSCNT: continue the VU micro-program where it stopped
+                        self.vu_start = Some(VuStart::Continue);
+                        self.latch_tops();
+                        self.flip_double_buffer();
+                        return;
                     }
                     0x11 => {
                         // FLUSH: wait for VIF/VU to finish — we're synchronous, no-op
                     }
+                    0x00 => {
+                        // NOP
+                    }
                     _ => {
-                        // Unknown/NOP — ignore
+                        // Unknown — ignore, but report it
+                        self.log.push(format!("VIF1: unsupported command 0x{cmd:02X} (tag 0x{tag:08X}) ignored"));
                     }
                 }
             }
         }
+
+        if self.unpack_active {
+            self.log.push(format!(
+                "VIF1: packet ended mid-UNPACK ({} QWs still expected at VU addr {})",
+                self.unpack_count, self.unpack_addr,
+            ));
+        }
+    }
+
+    /// Starting VU1 latches TOP = TOPS and ITOP = ITOPS: the buffer just filled, which
+    /// the micro-program reads back with XTOP, and the ITOP value it reads with XITOP.
+    fn latch_tops(&mut self) {
+        self.top  = self.tops;
+        self.itop = self.itops;
+    }
+
+    /// Starting VU1 hands it the buffer just filled; further TOPS-relative UNPACKs go to
+    /// the other one: TOPS = BASE + (DBF ? OFFSET : 0).
+    fn flip_double_buffer(&mut self) {
+        self.dbf  = !self.dbf;
+        self.tops = (self.base + if self.dbf { self.offset } else { 0 }) & 0x3FF;
+    }
+
+    /// Store one unpacked QW at the UNPACK write pointer and advance it, past the skipped
+    /// QWs at the end of each WL-long block.
+    fn write_unpacked(&mut self, vu_mem: &mut [[f32; 4]; 1024], qw: [f32; 4]) {
+        if (self.unpack_addr as usize) < 1024 {
+            vu_mem[self.unpack_addr as usize] = qw;
+        }
+        self.unpack_addr  = self.unpack_addr.wrapping_add(1);
+        self.unpack_count -= 1;
+
+        if self.wl > 0 && self.cl > self.wl {
+            self.unpack_cycle += 1;
+            if self.unpack_cycle == self.wl {
+                self.unpack_cycle = 0;
+                self.unpack_addr = self.unpack_addr.wrapping_add((self.cl - self.wl) as u16);
+            }
+        }
+
+        if self.unpack_count == 0 {
+            self.unpack_active = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A QW from four little-endian words, word 0 lowest (where VIF tags sit).
+    fn qw(w: [u32; 4]) -> u128 {
+        w.iter().rev().fold(0, |acc, &x| (acc << 32) | x as u128)
+    }
+
+    fn unpack_v4_32(num: u32, addr: u32, flg: bool) -> u128 {
+        qw([(0x6C << 24) | (num << 16) | (u32::from(flg) << 15) | addr, 0, 0, 0])
+    }
+
+    fn data(v: [f32; 4]) -> u128 {
+        qw(v.map(f32::to_bits))
+    }
+
+    #[test]
+    fn tops_relative_unpack_lands_above_tops() {
+        let mut vif = Vif1::new();
+        let mut mem = Box::new([[0.0; 4]; 1024]);
+        vif.tops = 512;
+        vif.fifo.extend([unpack_v4_32(1, 5, true), data([1.0, 2.0, 3.0, 4.0])]);
+        vif.fifo.extend([unpack_v4_32(1, 5, false), data([5.0, 6.0, 7.0, 8.0])]);
+        vif.process(&mut mem);
+        assert_eq!(mem[517], [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(mem[5], [5.0, 6.0, 7.0, 8.0]);
+    }
+
+    #[test]
+    fn base_and_offset_place_the_two_buffers() {
+        let mut vif = Vif1::new();
+        let mut mem = Box::new([[0.0; 4]; 1024]);
+        vif.fifo.extend([
+            qw([(0x03 << 24) | 512, 0, 0, 0]), // BASE
+            qw([(0x02 << 24) | 256, 0, 0, 0]), // OFFSET
+            unpack_v4_32(1, 0, true), data([1.0; 4]),
+            qw([0x14 << 24, 0, 0, 0]),         // MSCAL: flips to the second buffer
+            unpack_v4_32(1, 0, true), data([2.0; 4]),
+        ]);
+        vif.process(&mut mem);
+        assert_eq!(vif.top, 512);
+        vif.process(&mut mem);
+        assert_eq!(mem[512], [1.0; 4]);
+        assert_eq!(mem[768], [2.0; 4]);
+    }
+
+    #[test]
+    fn packed_normal_extremes_decode_to_plus_and_minus_one() {
+        use crate::vu1::Vu1;
+        use crate::vu1_program::{i, l_xgkick, u_itof15, DEST_XYZW};
+
+        // One V4-16 signed element: x = 0x7FFF, y = 0x8000, z = w = 0
+        let mut vif = Vif1::new();
+        let mut vu = Vu1::new();
+        vif.fifo.extend([qw([(0x6D << 24) | (1 << 16) | 7, 0, 0, 0]), qw([0x8000_7FFF, 0, 0, 0])]);
+        vif.process(&mut vu.data_mem);
+        assert_eq!(vu.data_mem[7].map(|f| f.to_bits() as i32), [32767, -32768, 0, 0]);
+
+        vu.code_mem[0] = i(u_itof15(DEST_XYZW, 2, 1), l_xgkick(0));
+        vu.vf[1] = vu.data_mem[7];
+        vu.run_until_xgkick();
+        assert!((vu.vf[2][0] - 1.0).abs() < 1e-4);
+        assert_eq!(vu.vf[2][1], -1.0);
     }
 }
@@ -11,19 +11,90 @@
 //   VF00  hardwired [0.0, 0.0, 0.0, 1.0]   (never written)
 //   VI00  hardwired 0                        (never written)
 //   ACC   accumulator for MULA/MADDA/MADD chain
-//   Q     result of DIV instruction (available after div_busy reaches 0)
+//   Q     result of DIV/SQRT/RSQRT (available after div_busy reaches 0)
+//   I     float immediate loaded by LOI, read by ADDi/SUBi/MULi
+//   P     result of EFU ops ELENG/ERLENG/EEXP: reads (MFP) see the previous value until
+//         p_busy reaches 0
+//   R     23-bit LFSR random mantissa, stored as a float in [1,2) (0x3F80_0000 | bits)
+//   MAC   16-bit flags of the last FMAC result: [15:12] O, [11:8] U, [7:4] S, [3:0] Z,
+//         one bit per lane (x highest)
+//   STATUS 12-bit status flags: [5:0] = D I O U S Z, [11:6] = sticky copies
+
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
 
 use crate::vu1_program::VU1_MICRO;
 
+/// Status flag bits (non-sticky; the sticky copy sits 6 bits higher).
+pub const STATUS_Z: u16 = 1 << 0; // zero
+pub const STATUS_S: u16 = 1 << 1; // sign
+pub const STATUS_U: u16 = 1 << 2; // underflow
+pub const STATUS_O: u16 = 1 << 3; // overflow
+pub const STATUS_I: u16 = 1 << 4; // invalid (0/0)
+pub const STATUS_D: u16 = 1 << 5; // divide by zero
+pub const STATUS_STICKY_SHIFT: u16 = 6;
+
+/// Upper-slot I bit: the lower 32 bits are a float loaded into I (LOI), not an instruction.
+pub const UPPER_I_BIT: u32 = 1 << 31;
+
+/// Histogram key offset for lower-slot opcodes (upper-slot keys are the raw op9).
+#[cfg(debug_assertions)]
+pub const LOWER_KEY: u32 = 0x1000;
+
+/// What `run_until_xgkick` did: why it stopped, and how long it ran.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunResult {
+    pub stop:      Stop,
+    /// Instruction pairs executed by this call, each counted as one VU cycle, plus
+    /// `stalls`.
+    pub cycles:    u32,
+    /// Cycles spent stalled on WAITQ / WAITP until the Q / P result was ready.
+    pub stalls:    u32,
+    /// The safety cycle limit cut the run short; `stop` is then `Aborted`.
+    pub hit_limit: bool,
+}
+
+/// Why `run_until_xgkick` returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// XGKICK executed: GIF packet base address (in data_mem QWs).
+    XgKick(u16),
+    /// Stopped before executing the instruction at this breakpoint PC; all state is
+    /// kept, and calling `run_until_xgkick` again resumes from it.
+    Breakpoint(u16),
+    /// Cut short by the cycle limit or a PC past the end of the micro-program, with no
+    /// XGKICK: data memory holds no GIF packet to parse.
+    Aborted,
+}
+
 pub struct Vu1 {
     pub vf:        [[f32; 4]; 32],
     pub vi:        [i16; 16],
     pub acc:       [f32; 4],
     pub q:         f32,
+    pub p_reg:     f32,
+    pub i_reg:     f32,
+    pub r_reg:     u32,
+    pub status_flags: u16,
+    mac_flags:     u16,
     pub pc:        u16,
+    pub top:       u16,           // VIF1_TOP at the last VU start, read by XTOP
+    pub itop:      u16,           // VIF1_ITOP at the last VU start, read by XITOP
     pub div_busy:  u8,
+    pub p_busy:    u8,
+    p_next:        f32,           // EFU result in flight, moved into p_reg when p_busy hits 0
     pub data_mem:  Box<[[f32; 4]; 1024]>,
     pub code_mem:  Box<[u64; 512]>,
+    pub log:       Vec<String>,   // diagnostics for EmulatorCore::drain_log
+    clip_flags:    u32,           // 4 CLIP judgments of 6 bits, newest in bits [5:0]
+    breakpoints:   HashSet<u16>,
+    write_protect: Option<Range<usize>>, // datamem QWs SQI may not write
+    stopped_at:    Option<u16>,   // breakpoint PC the last run halted at; runs first on resume
+    /// Debug builds only: executions per decoded opcode (op9, or LOWER_KEY | op6).
+    #[cfg(debug_assertions)]
+    opcode_hist:   HashMap<u32, u64>,
 }
 
 impl Vu1 {
@@ -33,10 +104,26 @@ impl Vu1 {
             vi:       [0i16; 16],
             acc:      [0.0; 4],
             q:        1.0,
+            p_reg:    0.0,
+            i_reg:    0.0,
+            r_reg:    0x3F80_0000,
+            status_flags: 0,
+            mac_flags: 0,
             pc:       0,
+            top:      0,
+            itop:     0,
             div_busy: 0,
+            p_busy:   0,
+            p_next:   0.0,
             data_mem: Box::new([[0.0; 4]; 1024]),
             code_mem: Box::new([0u64; 512]),
+            log:      Vec::new(),
+            clip_flags: 0,
+            breakpoints: HashSet::new(),
+            write_protect: None,
+            stopped_at: None,
+            #[cfg(debug_assertions)]
+            opcode_hist: HashMap::new(),
         };
 
         // Load micro-program into code_mem
@@ -49,16 +136,19 @@ impl Vu1 {
         vu
     }
 
+    /// Instruction-coverage histogram: how often each decoded opcode has executed.
+    /// Upper-slot ops are keyed by op9, lower-slot ops by `LOWER_KEY | op6`.
+    #[cfg(debug_assertions)]
+    pub fn opcode_histogram(&self) -> &HashMap<u32, u64> {
+        &self.opcode_hist
+    }
+
     // ---- VF register helpers ----
 
     fn vf_get(&self, reg: usize) -> [f32; 4] {
         if reg == 0 { [0.0, 0.0, 0.0, 1.0] } else { self.vf[reg] }
     }
 
-    fn vf_component(&self, reg: usize, comp: usize) -> f32 {
-        self.vf_get(reg)[comp]
-    }
-
     fn vf_set(&mut self, reg: usize, dest: u32, val: [f32; 4]) {
         if reg == 0 { return; }
         if dest & 0x8 != 0 { self.vf[reg][0] = val[0]; } // X
@@ -67,11 +157,23 @@ impl Vu1 {
         if dest & 0x1 != 0 { self.vf[reg][3] = val[3]; } // W
     }
 
+    /// Write an FMAC result to ACC; like a VF result, it sets the MAC and status flags.
     fn acc_set(&mut self, dest: u32, val: [f32; 4]) {
         if dest & 0x8 != 0 { self.acc[0] = val[0]; }
         if dest & 0x4 != 0 { self.acc[1] = val[1]; }
         if dest & 0x2 != 0 { self.acc[2] = val[2]; }
         if dest & 0x1 != 0 { self.acc[3] = val[3]; }
+        self.set_fmac_flags(dest, val);
+    }
+
+    // ---- R register (PRNG) ----
+
+    /// Advance the R-register LFSR one step: feedback = bit4 ^ bit22, 23-bit mantissa
+    /// kept under a fixed 1.0 exponent.
+    fn r_advance(&mut self) {
+        let x = (self.r_reg >> 4) & 1;
+        let y = (self.r_reg >> 22) & 1;
+        self.r_reg = ((self.r_reg << 1) ^ x ^ y) & 0x007F_FFFF | 0x3F80_0000;
     }
 
     // ---- VI register helpers ----
@@ -86,8 +188,7 @@ impl Vu1 {
 
     // ---- Execute upper slot ----
     // Returns: Option<(fd, dest_mask, result_vec)> — staged write committed after lower slot.
-    fn exec_upper(&mut self, upper: u32) -> Option<(usize, u32, [f32; 4])> {
-        let op9  = (upper & 0x1FF) as u32;
+    fn exec_upper(&mut self, op: UpperOp, upper: u32) -> Option<(usize, u32, [f32; 4])> {
         let fd   = ((upper >> 9)  & 0x1F) as usize;
         let fs   = ((upper >> 14) & 0x1F) as usize;
         let ft   = ((upper >> 19) & 0x1F) as usize;
@@ -96,115 +197,282 @@ impl Vu1 {
         // Read source registers BEFORE any writes (for hazard correctness)
         let vfs  = self.vf_get(fs);
         let vft  = self.vf_get(ft);
+        let scalar = vft[(upper & 3) as usize]; // VFft.bc, for the bc-flavored ops
 
-        match op9 {
-            0x1FF => None, // NOP
+        match op {
+            // ---- CLIPw.xyz ----
+            // Shares op9 0x1FF with NOP; the xyz dest mask tells it apart
+            UpperOp::ClipW => {
+                // Judge VFfs.xyz against ±|VFft.w|: bits x>+w, x<-w, y>+w, y<-w, z>+w, z<-w
+                let w = vft[3].abs();
+                let judgment = (0..3).fold(0, |bits, i| {
+                    bits | ((vfs[i] > w) as u32) << (2 * i) | ((vfs[i] < -w) as u32) << (2 * i + 1)
+                });
+                self.clip_flags = ((self.clip_flags << 6) | judgment) & 0xFF_FFFF;
+                None
+            }
+            UpperOp::Nop => None,
 
             // ---- DIV ----
-            0x070 => {
+            UpperOp::Div => {
                 // fd_enc = (upper >> 9) & 0x1F:  fsf = fd_enc[3:2], ftf = fd_enc[1:0]
                 let fd_enc = fd as u32;
                 let fsf = ((fd_enc >> 2) & 0x3) as usize;
                 let ftf = (fd_enc & 0x3) as usize;
                 let num = vfs[fsf];
                 let den = vft[ftf];
-                self.q = if den.abs() < 1e-37 { 0.0 } else { num / den };
+                if den.abs() < 1e-37 {
+                    // 0/0 raises invalid, x/0 divide-by-zero (plus their sticky copies)
+                    let flag = if num == 0.0 { STATUS_I } else { STATUS_D };
+                    self.status_flags = (self.status_flags & !(STATUS_I | STATUS_D))
+                        | flag | (flag << STATUS_STICKY_SHIFT);
+                    self.q = 0.0;
+                } else {
+                    self.status_flags &= !(STATUS_I | STATUS_D);
+                    self.q = num / den;
+                }
                 self.div_busy = 7;
                 None
             }
 
+            // ---- SQRT ----
+            UpperOp::Sqrt => {
+                // Q = sqrt(|VFft.ftf|), ftf in the same fd_enc[1:0] slot as DIV's;
+                // a negative operand raises invalid (plus its sticky copy)
+                let ftf = (fd as u32 & 0x3) as usize;
+                let x = vft[ftf];
+                self.status_flags &= !(STATUS_I | STATUS_D);
+                if x < 0.0 {
+                    self.status_flags |= STATUS_I | (STATUS_I << STATUS_STICKY_SHIFT);
+                }
+                self.q = x.abs().sqrt();
+                self.div_busy = 7;
+                None
+            }
+
+            // ---- RSQRT ----
+            UpperOp::Rsqrt => {
+                // Q = VFfs.fsf / sqrt(|VFft.ftf|), fields packed as for DIV; a negative
+                // radicand raises invalid, a zero one divide-by-zero (plus sticky copies)
+                let fd_enc = fd as u32;
+                let fsf = ((fd_enc >> 2) & 0x3) as usize;
+                let ftf = (fd_enc & 0x3) as usize;
+                let (num, x) = (vfs[fsf], vft[ftf]);
+                self.status_flags &= !(STATUS_I | STATUS_D);
+                if x < 0.0 {
+                    self.status_flags |= STATUS_I | (STATUS_I << STATUS_STICKY_SHIFT);
+                }
+                let root = x.abs().sqrt();
+                if root < 1e-37 {
+                    self.status_flags |= STATUS_D | (STATUS_D << STATUS_STICKY_SHIFT);
+                    self.q = 0.0;
+                } else {
+                    self.q = num / root;
+                }
+                self.div_busy = 13;
+                None
+            }
+
             // ---- WAITQ ----
-            0x073 => {
-                // Spin — in our synchronous interpreter we already have Q ready
-                // (div was executed earlier in the same frame).
-                // In hardware this stalls; here we just ensure div_busy=0.
-                self.div_busy = 0;
+            // Only reached once div_busy is 0: run_until_xgkick stalls on it until then
+            UpperOp::WaitQ => None,
+
+            // ---- EFU: ELENG / ERLENG / EEXP ----
+            // P = |VFfs.xyz|,  P = 1/|VFfs.xyz|,  P = exp(-VFfs.fsf)
+            UpperOp::Eleng => {
+                self.p_next = (vfs[0]*vfs[0] + vfs[1]*vfs[1] + vfs[2]*vfs[2]).sqrt();
+                self.p_busy = 18;
+                None
+            }
+            UpperOp::Erleng => {
+                let len = (vfs[0]*vfs[0] + vfs[1]*vfs[1] + vfs[2]*vfs[2]).sqrt();
+                self.p_next = if len < 1e-37 { 0.0 } else { 1.0 / len };
+                self.p_busy = 24;
+                None
+            }
+            UpperOp::Eexp => {
+                let fsf = ((fd as u32 >> 2) & 0x3) as usize;
+                self.p_next = (-vfs[fsf]).exp();
+                self.p_busy = 44;
                 None
             }
 
+            // ---- WAITP ----
+            // Like WAITQ: only reached once p_busy is 0
+            UpperOp::WaitP => None,
+
+            // ---- MFP ----
+            UpperOp::Mfp => {
+                // VFfd.dest = P (broadcast); the old P while an EFU op is in flight
+                let p = self.p_reg;
+                Some((fd, dest, [p, p, p, p]))
+            }
+
+            // ---- MADD (full vector) ----
+            UpperOp::Madd => {
+                // VFfd.dest = ACC.dest + VFfs.dest * VFft.dest
+                let res = [
+                    self.acc[0] + vfs[0]*vft[0],
+                    self.acc[1] + vfs[1]*vft[1],
+                    self.acc[2] + vfs[2]*vft[2],
+                    self.acc[3] + vfs[3]*vft[3],
+                ];
+                Some((fd, dest, res))
+            }
+
+            // ---- MSUB (full vector) ----
+            UpperOp::Msub => {
+                // VFfd.dest = ACC.dest - VFfs.dest * VFft.dest
+                let res = [
+                    self.acc[0] - vfs[0]*vft[0],
+                    self.acc[1] - vfs[1]*vft[1],
+                    self.acc[2] - vfs[2]*vft[2],
+                    self.acc[3] - vfs[3]*vft[3],
+                ];
+                Some((fd, dest, res))
+            }
+
+            // ---- OPMULA / OPMSUB (outer product) ----
+            // OPMULA.xyz ACC, a, b then OPMSUB.xyz d, b, a leaves d = a × b. Both write
+            // xyz only, whatever the dest mask says about w.
+            UpperOp::OpMula => {
+                // ACC.xyz = [fs.y*ft.z, fs.z*ft.x, fs.x*ft.y]
+                let res = [vfs[1]*vft[2], vfs[2]*vft[0], vfs[0]*vft[1], 0.0];
+                self.acc_set(dest & 0b1110, res);
+                None
+            }
+            UpperOp::OpMsub => {
+                // VFfd.xyz = ACC.xyz - [fs.y*ft.z, fs.z*ft.x, fs.x*ft.y]
+                let res = [
+                    self.acc[0] - vfs[1]*vft[2],
+                    self.acc[1] - vfs[2]*vft[0],
+                    self.acc[2] - vfs[0]*vft[1],
+                    0.0,
+                ];
+                Some((fd, dest & 0b1110, res))
+            }
+
+            // ---- ADDi / SUBi / MULi ----
+            // VFfd.dest = VFfs.dest (+ - *) I
+            UpperOp::AddI => Some((fd, dest, vfs.map(|f| f + self.i_reg))),
+            UpperOp::SubI => Some((fd, dest, vfs.map(|f| f - self.i_reg))),
+            UpperOp::MulI => Some((fd, dest, vfs.map(|f| f * self.i_reg))),
+
             // ---- MULq ----
-            0x01C => {
+            UpperOp::MulQ => {
                 // VFfd.dest = VFfs.dest * Q
                 let q = self.q;
                 let res = [vfs[0] * q, vfs[1] * q, vfs[2] * q, vfs[3] * q];
                 Some((fd, dest, res))
             }
 
-            // ---- FTOI4 ----
-            0x17C => {
-                // VFfd[i] = round(VFfs[i] * 16) as i32, bit-cast back to f32
+            // ---- FTOI4 / FTOI0 / FTOI12 / FTOI15 ----
+            UpperOp::FtoI(frac) => {
+                // VFfd[i] = round(VFfs[i] * 2^frac) as i32, bit-cast back to f32
+                let scale = (1 << frac) as f32;
                 let mut res = [0.0f32; 4];
                 for i in 0..4 {
-                    let fixed = (vfs[i] * 16.0).round() as i32;
+                    let fixed = (vfs[i] * scale).round() as i32;
                     res[i] = f32::from_bits(fixed as u32);
                 }
                 Some((fd, dest, res))
             }
 
-            // ---- bc-flavored ops ----
-            _ => {
-                let bc  = (op9 & 3) as usize;
-                let base = op9 & !3;
-                let scalar = vft[bc];
-
-                match base {
-                    // ADDbc: VFfd.dest = VFfs.dest + VFft.bc
-                    0x000 => {
-                        let res = [vfs[0]+scalar, vfs[1]+scalar, vfs[2]+scalar, vfs[3]+scalar];
-                        Some((fd, dest, res))
-                    }
-                    // SUBbc: VFfd.dest = VFfs.dest - VFft.bc
-                    0x004 => {
-                        let res = [vfs[0]-scalar, vfs[1]-scalar, vfs[2]-scalar, vfs[3]-scalar];
-                        Some((fd, dest, res))
-                    }
-                    // MADDbc: VFfd.dest = ACC.dest + VFfs.dest * VFft.bc
-                    0x008 => {
-                        let res = [
-                            self.acc[0] + vfs[0]*scalar,
-                            self.acc[1] + vfs[1]*scalar,
-                            self.acc[2] + vfs[2]*scalar,
-                            self.acc[3] + vfs[3]*scalar,
-                        ];
-                        Some((fd, dest, res))
-                    }
-                    // MAXbc: VFfd.dest = max(VFfs.dest, VFft.bc)
-                    0x010 => {
-                        let res = [vfs[0].max(scalar), vfs[1].max(scalar),
-                                   vfs[2].max(scalar), vfs[3].max(scalar)];
-                        Some((fd, dest, res))
-                    }
-                    // MINIbc: VFfd.dest = min(VFfs.dest, VFft.bc)
-                    0x014 => {
-                        let res = [vfs[0].min(scalar), vfs[1].min(scalar),
-                                   vfs[2].min(scalar), vfs[3].min(scalar)];
-                        Some((fd, dest, res))
-                    }
-                    // MULbc: VFfd.dest = VFfs.dest * VFft.bc
-                    0x018 => {
-                        let res = [vfs[0]*scalar, vfs[1]*scalar, vfs[2]*scalar, vfs[3]*scalar];
-                        Some((fd, dest, res))
-                    }
-                    // MULAbc: ACC.dest = VFfs.dest * VFft.bc  (fd unused, writes ACC)
-                    0x020 => {
-                        let res = [vfs[0]*scalar, vfs[1]*scalar, vfs[2]*scalar, vfs[3]*scalar];
-                        self.acc_set(dest, res);
-                        None
-                    }
-                    // MADDAbc: ACC.dest += VFfs.dest * VFft.bc
-                    0x038 => {
-                        let res = [
-                            self.acc[0] + vfs[0]*scalar,
-                            self.acc[1] + vfs[1]*scalar,
-                            self.acc[2] + vfs[2]*scalar,
-                            self.acc[3] + vfs[3]*scalar,
-                        ];
-                        self.acc_set(dest, res);
-                        None
-                    }
-                    _ => None, // unknown extended op
-                }
+            // ---- ITOF4 / ITOF0 / ITOF12 / ITOF15 ----
+            UpperOp::ItoF(frac) => {
+                // VFfd[i] = VFfs[i] bit-cast to i32, converted to float, divided by 2^frac
+                // (ITOF15 reads 1.15 fixed point, e.g. packed normals)
+                let scale = (1 << frac) as f32;
+                let res = vfs.map(|f| f.to_bits() as i32 as f32 / scale);
+                Some((fd, dest, res))
+            }
+
+            // ---- ABS ----
+            UpperOp::Abs => {
+                // VFfd.dest = |VFfs.dest|
+                let res = [vfs[0].abs(), vfs[1].abs(), vfs[2].abs(), vfs[3].abs()];
+                Some((fd, dest, res))
+            }
+
+            // ---- bc-flavored ops: VFft.bc broadcast ----
+            // ADDbc: VFfd.dest = VFfs.dest + VFft.bc
+            UpperOp::AddBc => {
+                let res = [vfs[0]+scalar, vfs[1]+scalar, vfs[2]+scalar, vfs[3]+scalar];
+                Some((fd, dest, res))
+            }
+            // SUBbc: VFfd.dest = VFfs.dest - VFft.bc
+            UpperOp::SubBc => {
+                let res = [vfs[0]-scalar, vfs[1]-scalar, vfs[2]-scalar, vfs[3]-scalar];
+                Some((fd, dest, res))
+            }
+            // MADDbc: VFfd.dest = ACC.dest + VFfs.dest * VFft.bc
+            UpperOp::MaddBc => {
+                let res = [
+                    self.acc[0] + vfs[0]*scalar,
+                    self.acc[1] + vfs[1]*scalar,
+                    self.acc[2] + vfs[2]*scalar,
+                    self.acc[3] + vfs[3]*scalar,
+                ];
+                Some((fd, dest, res))
+            }
+            // MSUBbc: VFfd.dest = ACC.dest - VFfs.dest * VFft.bc
+            UpperOp::MsubBc => {
+                let res = [
+                    self.acc[0] - vfs[0]*scalar,
+                    self.acc[1] - vfs[1]*scalar,
+                    self.acc[2] - vfs[2]*scalar,
+                    self.acc[3] - vfs[3]*scalar,
+                ];
+                Some((fd, dest, res))
+            }
+            // MAXbc: VFfd.dest = max(VFfs.dest, VFft.bc)
+            UpperOp::MaxBc => {
+                let res = [vfs[0].max(scalar), vfs[1].max(scalar),
+                           vfs[2].max(scalar), vfs[3].max(scalar)];
+                Some((fd, dest, res))
+            }
+            // MINIbc: VFfd.dest = min(VFfs.dest, VFft.bc)
+            UpperOp::MiniBc => {
+                let res = [vfs[0].min(scalar), vfs[1].min(scalar),
+                           vfs[2].min(scalar), vfs[3].min(scalar)];
+                Some((fd, dest, res))
+            }
+            // MULbc: VFfd.dest = VFfs.dest * VFft.bc
+            UpperOp::MulBc => {
+                let res = [vfs[0]*scalar, vfs[1]*scalar, vfs[2]*scalar, vfs[3]*scalar];
+                Some((fd, dest, res))
+            }
+            // MULAbc: ACC.dest = VFfs.dest * VFft.bc  (fd unused, writes ACC)
+            UpperOp::MulaBc => {
+                let res = [vfs[0]*scalar, vfs[1]*scalar, vfs[2]*scalar, vfs[3]*scalar];
+                self.acc_set(dest, res);
+                None
+            }
+            // MADDAbc: ACC.dest += VFfs.dest * VFft.bc
+            UpperOp::MaddaBc => {
+                let res = [
+                    self.acc[0] + vfs[0]*scalar,
+                    self.acc[1] + vfs[1]*scalar,
+                    self.acc[2] + vfs[2]*scalar,
+                    self.acc[3] + vfs[3]*scalar,
+                ];
+                self.acc_set(dest, res);
+                None
             }
+            // MSUBAbc: ACC.dest -= VFfs.dest * VFft.bc
+            UpperOp::MsubaBc => {
+                let res = [
+                    self.acc[0] - vfs[0]*scalar,
+                    self.acc[1] - vfs[1]*scalar,
+                    self.acc[2] - vfs[2]*scalar,
+                    self.acc[3] - vfs[3]*scalar,
+                ];
+                self.acc_set(dest, res);
+                None
+            }
+
+            // Decoded, but not executed: a no-op
+            UpperOp::Unimplemented(_) => None,
         }
     }
 
@@ -213,15 +481,28 @@ impl Vu1 {
     //   None      → advance PC normally
     //   Branch(n) → set PC = n (after commit)
     //   XgKick(a) → end of program, return a
-    fn exec_lower(&mut self, lower: u32) -> LowerEffect {
-        let op6 = (lower >> 26) & 0x3F;
+    fn exec_lower(&mut self, op: LowerOp, lower: u32) -> LowerEffect {
+        match op {
+            LowerOp::Nop => LowerEffect::None,
 
-        match op6 {
-            // NOP
-            0x20 => LowerEffect::None,
+            // IADD/ISUB/IAND/IOR VI[id], VI[is], VI[it] — it [20:16], is [15:11], id [10:6]
+            LowerOp::IAdd | LowerOp::ISub | LowerOp::IAnd | LowerOp::IOr => {
+                let it = ((lower >> 16) & 0xF) as usize;
+                let is = ((lower >> 11) & 0xF) as usize;
+                let id = ((lower >> 6) & 0xF) as usize;
+                let (a, b) = (self.vi_get(is), self.vi_get(it));
+                let val = match op {
+                    LowerOp::IAdd => a.wrapping_add(b),
+                    LowerOp::ISub => a.wrapping_sub(b),
+                    LowerOp::IAnd => a & b,
+                    _             => a | b,
+                };
+                self.vi_set(id, val);
+                LowerEffect::None
+            }
 
             // LQI VF[ft],(VI[is]++): VF[ft] = data_mem[VI[is]]; VI[is]++
-            0x3A => {
+            LowerOp::Lqi => {
                 let ft = ((lower >> 21) & 0x1F) as usize;
                 let is = ((lower >> 16) & 0xF) as usize;
                 let addr = self.vi_get(is) as usize;
@@ -233,21 +514,72 @@ impl Vu1 {
                 LowerEffect::None
             }
 
+            // LQ VF[ft],imm11(VI[is]): VF[ft] = data_mem[VI[is] + sext(imm11)]
+            LowerOp::Lq => {
+                let ft = ((lower >> 21) & 0x1F) as usize;
+                let is = ((lower >> 16) & 0xF) as usize;
+                let addr = self.vi_get(is).wrapping_add(sext11(lower)) as u16 as usize;
+                if ft != 0 && addr < 1024 {
+                    self.vf[ft] = self.data_mem[addr];
+                }
+                LowerEffect::None
+            }
+
+            // ILW.x VI[it],imm11(VI[is]): VI[it] = low 16 bits of data_mem[VI[is] + sext(imm11)].x
+            LowerOp::Ilw => {
+                let it = ((lower >> 21) & 0xF) as usize;
+                let is = ((lower >> 16) & 0xF) as usize;
+                let addr = self.vi_get(is).wrapping_add(sext11(lower)) as u16 as usize;
+                if addr < 1024 {
+                    self.vi_set(it, self.data_mem[addr][0].to_bits() as i16);
+                }
+                LowerEffect::None
+            }
+
+            // LQD VF[ft],(--VI[is]): VI[is]--; VF[ft] = data_mem[VI[is]]
+            LowerOp::Lqd => {
+                let ft = ((lower >> 21) & 0x1F) as usize;
+                let is = ((lower >> 16) & 0xF) as usize;
+                let new_is = self.vi_get(is).wrapping_sub(1);
+                self.vi_set(is, new_is);
+                let addr = self.vi_get(is) as u16 as usize;
+                if ft != 0 && addr < 1024 {
+                    self.vf[ft] = self.data_mem[addr];
+                }
+                LowerEffect::None
+            }
+
             // SQI VF[fs],(VI[it]++): data_mem[VI[it]] = VF[fs]; VI[it]++
-            0x3E => {
+            LowerOp::Sqi => {
                 let fs = ((lower >> 21) & 0x1F) as usize;
                 let it = ((lower >> 11) & 0xF) as usize;
-                let addr = self.vi_get(it) as usize;
-                if addr < 1024 {
-                    self.data_mem[addr] = self.vf_get(fs);
-                }
+                self.store_qw("SQI", self.vi_get(it) as usize, fs);
                 let new_it = self.vi_get(it).wrapping_add(1);
                 self.vi_set(it, new_it);
                 LowerEffect::None
             }
 
+            // SQD VF[fs],(--VI[it]): VI[it]--; data_mem[VI[it]] = VF[fs]
+            LowerOp::Sqd => {
+                let fs = ((lower >> 21) & 0x1F) as usize;
+                let it = ((lower >> 11) & 0xF) as usize;
+                let new_it = self.vi_get(it).wrapping_sub(1);
+                self.vi_set(it, new_it);
+                self.store_qw("SQD", self.vi_get(it) as u16 as usize, fs);
+                LowerEffect::None
+            }
+
+            // SQ VF[fs],imm11(VI[it]): data_mem[VI[it] + sext(imm11)] = VF[fs]
+            LowerOp::Sq => {
+                let fs = ((lower >> 21) & 0x1F) as usize;
+                let it = ((lower >> 16) & 0xF) as usize;
+                let addr = self.vi_get(it).wrapping_add(sext11(lower)) as u16 as usize;
+                self.store_qw("SQ", addr, fs);
+                LowerEffect::None
+            }
+
             // IADDIU VI[vt],VI[vs],imm15
-            0x27 => {
+            LowerOp::IAddIu => {
                 let vt   = ((lower >> 21) & 0xF) as usize;
                 let vs   = ((lower >> 16) & 0xF) as usize;
                 let imm15 = (lower & 0x7FFF) as i16;
@@ -262,70 +594,362 @@ impl Vu1 {
                 LowerEffect::None
             }
 
-            // IBNE VI[vs],VI[vt],off11 — branch if not equal
-            0x23 => {
-                let vs   = ((lower >> 21) & 0xF) as usize;
-                let vt   = ((lower >> 16) & 0xF) as usize;
-                let off11 = (lower & 0x7FF) as i16;
-                // Sign-extend 11-bit offset
-                let off = if off11 & 0x400 != 0 {
-                    off11 | (0xFFFF_u16 as i16 & !0x7FF)
-                } else {
-                    off11
+            // ISUBIU VI[vt],VI[vs],imm15 — zero-extended immediate
+            LowerOp::ISubIu => {
+                let vt = ((lower >> 21) & 0xF) as usize;
+                let vs = ((lower >> 16) & 0xF) as usize;
+                let imm = (lower & 0x7FFF) as i16; // 15 bits: never negative
+                let val = self.vi_get(vs).wrapping_sub(imm);
+                self.vi_set(vt, val);
+                LowerEffect::None
+            }
+
+            // IADDI VI[it],VI[is],imm5 — 5-bit signed immediate in bits [10:6]
+            LowerOp::IAddI => {
+                let it = ((lower >> 21) & 0xF) as usize;
+                let is = ((lower >> 16) & 0xF) as usize;
+                let imm = ((((lower >> 6) & 0x1F) as i16) << 11) >> 11;
+                let val = self.vi_get(is).wrapping_add(imm);
+                self.vi_set(it, val);
+                LowerEffect::None
+            }
+
+            // XTOP VI[it] / XITOP VI[it] — read the VIF1 TOP / ITOP register
+            LowerOp::XTop | LowerOp::XITop => {
+                let it = ((lower >> 21) & 0xF) as usize;
+                let val = if op == LowerOp::XTop { self.top } else { self.itop };
+                self.vi_set(it, val as i16);
+                LowerEffect::None
+            }
+
+            // IBEQ / IBNE VI[vs],VI[vt],off11 — branch if equal / not equal
+            LowerOp::IbEq | LowerOp::IbNe => {
+                let vs = ((lower >> 21) & 0xF) as usize;
+                let vt = ((lower >> 16) & 0xF) as usize;
+                let equal = self.vi_get(vs) == self.vi_get(vt);
+                self.branch_if(equal == (op == LowerOp::IbEq), lower)
+            }
+
+            // IBLTZ / IBGTZ / IBLEZ / IBGEZ VI[vs],off11 — branch on the sign of VI[vs]
+            LowerOp::IbLtz | LowerOp::IbGtz | LowerOp::IbLez | LowerOp::IbGez => {
+                let v = self.vi_get(((lower >> 21) & 0xF) as usize);
+                let taken = match op {
+                    LowerOp::IbLtz => v < 0,
+                    LowerOp::IbGtz => v > 0,
+                    LowerOp::IbLez => v <= 0,
+                    _              => v >= 0,
                 };
-                if self.vi_get(vs) != self.vi_get(vt) {
-                    let target = (self.pc as i32 + 1 + off as i32) as u16;
-                    LowerEffect::Branch(target)
-                } else {
-                    LowerEffect::None
-                }
+                self.branch_if(taken, lower)
+            }
+
+            // B off11 — unconditional branch to PC+1+sext(off11)
+            LowerOp::B => self.branch_if(true, lower),
+
+            // BAL VI[it],off11 — VI[it] = return address, then branch like B. There are
+            // no delay slots here, so execution resumes right after the BAL: PC+1.
+            LowerOp::Bal => {
+                let it = ((lower >> 21) & 0xF) as usize;
+                self.vi_set(it, self.pc as i16 + 1);
+                self.branch_if(true, lower)
+            }
+
+            // JR VI[is] — jump to the address held in VI[is]
+            LowerOp::Jr => {
+                let is = ((lower >> 16) & 0xF) as usize;
+                LowerEffect::Branch(self.vi_get(is) as u16)
+            }
+
+            // JALR VI[it],VI[is] — VI[it] = PC+1, then jump to VI[is] (read first, so
+            // it == is still jumps to the old value)
+            LowerOp::Jalr => {
+                let it = ((lower >> 21) & 0xF) as usize;
+                let is = ((lower >> 16) & 0xF) as usize;
+                let target = self.vi_get(is) as u16;
+                self.vi_set(it, self.pc as i16 + 1);
+                LowerEffect::Branch(target)
+            }
+
+            // FSSET imm12 — write the sticky status bits [11:6]
+            LowerOp::FsSet => {
+                let imm12 = (lower & 0xFFF) as u16;
+                self.status_flags = (self.status_flags & 0x3F) | (imm12 & 0xFC0);
+                LowerEffect::None
+            }
+
+            // FSAND VI[it], imm12 — VI[it] = STATUS & imm12
+            LowerOp::FsAnd => {
+                let it = ((lower >> 21) & 0xF) as usize;
+                let imm12 = (lower & 0xFFF) as u16;
+                self.vi_set(it, (self.status_flags & imm12) as i16);
+                LowerEffect::None
+            }
+
+            // FMEQ / FMAND / FMOR VI[it], VI[is] — compare / AND / OR against MAC
+            LowerOp::FmEq | LowerOp::FmAnd | LowerOp::FmOr => {
+                let it  = ((lower >> 21) & 0xF) as usize;
+                let is  = ((lower >> 16) & 0xF) as usize;
+                let v   = self.vi_get(is) as u16;
+                let mac = self.mac_flags;
+                let val = match op {
+                    LowerOp::FmEq  => (v == mac) as u16,
+                    LowerOp::FmAnd => v & mac,
+                    _              => v | mac,
+                };
+                self.vi_set(it, val as i16);
+                LowerEffect::None
+            }
+
+            // FSOR VI[it], imm12 — VI[it] = STATUS | imm12
+            LowerOp::FsOr => {
+                let it = ((lower >> 21) & 0xF) as usize;
+                let imm12 = (lower & 0xFFF) as u16;
+                self.vi_set(it, (self.status_flags | imm12) as i16);
+                LowerEffect::None
+            }
+
+            // RINIT R, VF[fs].fsf — seed R from the component's mantissa bits
+            LowerOp::RInit => {
+                let fs  = ((lower >> 11) & 0x1F) as usize;
+                let fsf = ((lower >> 21) & 0x3) as usize;
+                let bits = self.vf_get(fs)[fsf].to_bits();
+                self.r_reg = 0x3F80_0000 | (bits & 0x007F_FFFF);
+                LowerEffect::None
+            }
+
+            // RGET.dest VF[ft] — VF[ft].dest = R
+            // RNEXT.dest VF[ft] — advance LFSR, then VF[ft].dest = R
+            LowerOp::RGet | LowerOp::RNext => {
+                let dest = (lower >> 21) & 0xF;
+                let ft   = ((lower >> 16) & 0x1F) as usize;
+                if op == LowerOp::RNext { self.r_advance(); }
+                let r = f32::from_bits(self.r_reg);
+                self.vf_set(ft, dest, [r, r, r, r]);
+                LowerEffect::None
+            }
+
+            // RXOR R, VF[fs].fsf — R ^= component mantissa bits
+            LowerOp::RXor => {
+                let fs  = ((lower >> 11) & 0x1F) as usize;
+                let fsf = ((lower >> 21) & 0x3) as usize;
+                let bits = self.vf_get(fs)[fsf].to_bits();
+                self.r_reg = 0x3F80_0000 | ((self.r_reg ^ bits) & 0x007F_FFFF);
+                LowerEffect::None
+            }
+
+            // MOVE.dest VF[ft], VF[fs] — VF[ft].dest = VF[fs]
+            // MR32.dest VF[ft], VF[fs] — VF[ft].dest = VF[fs].yzwx (rotate one lane)
+            LowerOp::Move | LowerOp::Mr32 => {
+                let dest = (lower >> 21) & 0xF;
+                let ft   = ((lower >> 16) & 0x1F) as usize;
+                let v    = self.vf_get(((lower >> 11) & 0x1F) as usize);
+                let v    = if op == LowerOp::Mr32 { [v[1], v[2], v[3], v[0]] } else { v };
+                self.vf_set(ft, dest, v);
+                LowerEffect::None
+            }
+
+            // MFIR.dest VF[ft], VI[is] — VF[ft].dest = sext(VI[is]), as integer bits
+            LowerOp::Mfir => {
+                let dest = (lower >> 21) & 0xF;
+                let ft   = ((lower >> 16) & 0x1F) as usize;
+                let is   = ((lower >> 11) & 0xF) as usize;
+                let bits = f32::from_bits(self.vi_get(is) as i32 as u32);
+                self.vf_set(ft, dest, [bits; 4]);
+                LowerEffect::None
+            }
+
+            // MTIR VI[it], VF[fs].fsf — VI[it] = low 16 bits of the component
+            LowerOp::Mtir => {
+                let fsf = ((lower >> 21) & 0x3) as usize;
+                let it  = ((lower >> 16) & 0xF) as usize;
+                let fs  = ((lower >> 11) & 0x1F) as usize;
+                self.vi_set(it, self.vf_get(fs)[fsf].to_bits() as i16);
+                LowerEffect::None
             }
 
             // XGKICK VI[is] — end micro-program, return GIF buffer base
-            0x32 => {
+            LowerOp::XgKick => {
                 let is = ((lower >> 16) & 0xF) as usize;
                 LowerEffect::XgKick(self.vi_get(is) as u16)
             }
+        }
+    }
+
+    /// Quadword store shared by SQ/SQI/SQD: data_mem[addr] = VF[fs], unless `addr` is out
+    /// of range or write-protected (which is logged).
+    fn store_qw(&mut self, op: &str, addr: usize, fs: usize) {
+        if self.write_protect.as_ref().is_some_and(|r| r.contains(&addr)) {
+            self.log.push(format!(
+                "VU1: {op} at PC {} to write-protected datamem[{addr}]; dropped", self.pc,
+            ));
+        } else if addr < 1024 {
+            self.data_mem[addr] = self.vf_get(fs);
+        }
+    }
 
-            _ => LowerEffect::None,
+    /// Conditional branch shared by the IB* family: if taken, PC = PC+1+sext(off11).
+    fn branch_if(&self, taken: bool, lower: u32) -> LowerEffect {
+        if taken {
+            LowerEffect::Branch((self.pc as i32 + 1 + sext11(lower) as i32) as u16)
+        } else {
+            LowerEffect::None
         }
     }
 
     // ---- Commit staged upper-slot VF write ----
-    fn commit_upper(&mut self, staged: Option<(usize, u32, [f32; 4])>) {
-        if let Some((fd, dest, val)) = staged {
-            self.vf_set(fd, dest, val);
+    // FMAC results (`sets_flags`) also update MAC and the Z/S/U/O status bits from the
+    // lanes in `dest`; the rest read as clear.
+    fn commit_upper(&mut self, staged: Option<(usize, u32, [f32; 4])>, sets_flags: bool) {
+        let Some((fd, dest, val)) = staged else { return };
+        self.vf_set(fd, dest, val);
+        if sets_flags {
+            self.set_fmac_flags(dest, val);
         }
     }
 
-    /// Run the micro-program until XGKICK; returns the GIF base address (in data_mem QWs).
-    /// Safety: exits after MAX_CYCLES to prevent infinite loops in case of program bugs.
-    pub fn run_until_xgkick(&mut self) -> u16 {
+    /// MAC flags for the lanes of an FMAC result `val` under `dest`, and the status
+    /// Z/S/U/O bits (with their sticky copies) they sum up to.
+    fn set_fmac_flags(&mut self, dest: u32, val: [f32; 4]) {
+        let mut mac = 0u16;
+        for (lane, &v) in val.iter().enumerate() {
+            if dest & (0x8 >> lane) == 0 {
+                continue;
+            }
+            let bit = 0x8 >> lane;
+            if v == 0.0 { mac |= bit; }
+            if v < 0.0 { mac |= bit << 4; }
+            if v != 0.0 && v.abs() < f32::MIN_POSITIVE { mac |= bit << 8; }
+            if v.is_infinite() { mac |= bit << 12; }
+        }
+        self.mac_flags = mac;
+        let status = [STATUS_Z, STATUS_S, STATUS_U, STATUS_O].iter().enumerate()
+            .filter(|&(g, _)| (mac >> (4 * g)) & 0xF != 0)
+            .fold(0, |acc, (_, &flag)| acc | flag);
+        self.status_flags = (self.status_flags & !0xF) | status | (status << STATUS_STICKY_SHIFT);
+    }
+
+    /// The MAC flag register: per-lane zero/sign/underflow/overflow of the last FMAC
+    /// result, in groups of 4 bits (Z [3:0], S [7:4], U [11:8], O [15:12]), x highest.
+    pub fn mac_flags(&self) -> u16 {
+        self.mac_flags
+    }
+
+    /// The clipping flag register: the last four CLIP judgments, 6 bits each, the newest
+    /// in bits [5:0] (x>+w, x<-w, y>+w, y<-w, z>+w, z<-w from bit 0 up).
+    pub fn clip_flags(&self) -> u32 {
+        self.clip_flags
+    }
+
+    /// Halt `run_until_xgkick` before the instruction at `pc` executes.
+    pub fn set_breakpoint(&mut self, pc: u16) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn clear_breakpoint(&mut self, pc: u16) {
+        self.breakpoints.remove(&pc);
+    }
+
+    /// Remove every breakpoint and return them, e.g. to run a frame that must not halt
+    /// and then put them back with `set_breakpoints`.
+    pub fn take_breakpoints(&mut self) -> HashSet<u16> {
+        std::mem::take(&mut self.breakpoints)
+    }
+
+    pub fn set_breakpoints(&mut self, breakpoints: HashSet<u16>) {
+        self.breakpoints = breakpoints;
+    }
+
+    /// Refuse and log micro-program stores (SQI) into the datamem QWs `range`, e.g. to
+    /// catch an output loop overrunning onto the GIF tag. None lifts the protection.
+    pub fn set_write_protect(&mut self, range: Option<Range<usize>>) {
+        self.write_protect = range;
+    }
+
+    /// Run the micro-program until XGKICK or a breakpoint. After a breakpoint, the next
+    /// call executes the instruction it stopped at rather than halting on it again.
+    /// Safety: aborts after MAX_CYCLES to prevent infinite loops in case of program bugs.
+    pub fn run_until_xgkick(&mut self) -> RunResult {
         const MAX_CYCLES: u32 = 100_000;
         let mut cycles = 0u32;
+        let mut stalls = 0u32;
+        let mut stalling = false; // re-running a WAIT: don't halt on its breakpoint again
+        let resume_pc = self.stopped_at.take();
 
         loop {
-            if cycles >= MAX_CYCLES { return 109; } // fallback: return known GIF base
-            cycles += 1;
+            if cycles >= MAX_CYCLES {
+                self.log.push(format!("VU1: no XGKICK after {MAX_CYCLES} cycles (PC={}); aborted", self.pc));
+                return RunResult { stop: Stop::Aborted, cycles, stalls, hit_limit: true };
+            }
 
             let pc = self.pc as usize;
-            if pc >= VU1_MICRO.len() { return 109; }
+            if pc >= VU1_MICRO.len() {
+                self.log.push(format!("VU1: PC {pc} ran past the end of the micro-program; aborted"));
+                return RunResult { stop: Stop::Aborted, cycles, stalls, hit_limit: false };
+            }
+
+            if self.breakpoints.contains(&self.pc) && !stalling
+                && !(cycles == 0 && resume_pc == Some(self.pc))
+            {
+                self.stopped_at = Some(self.pc);
+                let stop = Stop::Breakpoint(self.pc);
+                return RunResult { stop, cycles, stalls, hit_limit: false };
+            }
+            cycles += 1;
 
             let instr  = self.code_mem[pc];
             let upper  = (instr >> 32) as u32;
             let lower  = (instr & 0xFFFF_FFFF) as u32;
 
-            // Decrement DIV countdown
+            // Decrement DIV / EFU countdowns; P takes the EFU result once it completes
             if self.div_busy > 0 { self.div_busy -= 1; }
+            if self.p_busy > 0 {
+                self.p_busy -= 1;
+                if self.p_busy == 0 { self.p_reg = self.p_next; }
+            }
+
+            let upper_op = decode_upper(upper);
+            let lower_op = if upper & UPPER_I_BIT == 0 { decode_lower(lower) } else { None };
+
+            // WAITQ / WAITP with the Q / P result still in flight: stall, re-running this
+            // PC next cycle
+            stalling = match upper_op {
+                Some(UpperOp::WaitQ) => self.div_busy > 0,
+                Some(UpperOp::WaitP) => self.p_busy > 0,
+                _                    => false,
+            };
+            if stalling {
+                stalls += 1;
+                continue;
+            }
+
+            #[cfg(debug_assertions)]
+            {
+                *self.opcode_hist.entry(upper & 0x1FF).or_insert(0) += 1;
+                if upper & UPPER_I_BIT == 0 {
+                    *self.opcode_hist.entry(LOWER_KEY | (lower >> 26)).or_insert(0) += 1;
+                }
+            }
+
+            // 0. LOI: with the I bit set the lower word is a float for I, not an instruction;
+            // the paired upper op already sees it
+            let loi = upper & UPPER_I_BIT != 0;
+            if loi {
+                self.i_reg = f32::from_bits(lower);
+            }
 
-            // 1. Compute upper-slot result (don't commit yet)
-            let staged = self.exec_upper(upper);
+            // 1. Compute upper-slot result (don't commit yet); opcodes with no instruction
+            // do nothing
+            let staged = upper_op.and_then(|op| self.exec_upper(op, upper));
 
             // 2. Execute lower slot — lower reads current VF (pre-commit)
-            let effect = self.exec_lower(lower);
+            let effect = match lower_op {
+                Some(op) => self.exec_lower(op, lower),
+                None     => LowerEffect::None,
+            };
 
-            // 3. Commit upper-slot result
-            self.commit_upper(staged);
+            // 3. Commit upper-slot result; conversions and ABS leave the flags alone
+            let sets_flags =
+                !matches!(upper_op, Some(UpperOp::FtoI(_) | UpperOp::ItoF(_) | UpperOp::Abs));
+            self.commit_upper(staged, sets_flags);
 
             // 4. Apply lower-slot effect
             match effect {
@@ -337,15 +961,804 @@ impl Vu1 {
                 }
                 LowerEffect::XgKick(base) => {
                     self.pc += 1;
-                    return base;
+                    return RunResult { stop: Stop::XgKick(base), cycles, stalls, hit_limit: false };
                 }
             }
         }
     }
 }
 
+// ---- Decode ----
+// The one opcode table: exec_upper / exec_lower match on what these return, and
+// unsupported_ops reports what they don't recognise, so the two cannot drift apart.
+
+/// Upper-slot instructions in this interpreter's encoding (see vu1_program.rs).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UpperOp {
+    Nop, ClipW,
+    Div, Sqrt, Rsqrt, WaitQ,
+    Eleng, Erleng, Eexp, WaitP, Mfp,
+    Madd, Msub, OpMula, OpMsub,
+    AddI, SubI, MulI, MulQ,
+    /// FTOI0/4/12/15 and ITOF0/4/12/15: the number of fraction bits
+    FtoI(u8), ItoF(u8),
+    Abs,
+    AddBc, SubBc, MaddBc, MsubBc, MaxBc, MiniBc, MulBc, MulaBc, MaddaBc, MsubaBc,
+    /// A hardware FMAC op at its hardware op9, which this interpreter does not run
+    Unimplemented(&'static str),
+}
+
+/// Decode an upper-slot word; None for an op9 no VU1 instruction has here.
+fn decode_upper(upper: u32) -> Option<UpperOp> {
+    let op = match upper & 0x1FF {
+        0x1FF if (upper >> 24) & 0xF == 0b1110 => UpperOp::ClipW,
+        0x1FF => UpperOp::Nop,
+        0x070 => UpperOp::Div,
+        0x071 => UpperOp::Sqrt,
+        0x072 => UpperOp::Rsqrt,
+        0x073 => UpperOp::WaitQ,
+        0x078 => UpperOp::Eleng,
+        0x079 => UpperOp::Erleng,
+        0x07A => UpperOp::Eexp,
+        0x07B => UpperOp::WaitP,
+        0x07C => UpperOp::Mfp,
+        0x029 => UpperOp::Madd,
+        0x02D => UpperOp::Msub,
+        0x0BC => UpperOp::OpMula,
+        0x0FE => UpperOp::OpMsub,
+        0x024 => UpperOp::AddI,
+        0x026 => UpperOp::SubI,
+        0x01E => UpperOp::MulI,
+        0x01C => UpperOp::MulQ,
+        0x17C => UpperOp::FtoI(4),
+        0x17D => UpperOp::FtoI(0),
+        0x17E => UpperOp::FtoI(12),
+        0x17F => UpperOp::FtoI(15),
+        0x13C => UpperOp::ItoF(4),
+        0x13D => UpperOp::ItoF(0),
+        0x13E => UpperOp::ItoF(12),
+        0x13F => UpperOp::ItoF(15),
+        0x1FD => UpperOp::Abs,
+        // Hardware FMAC ops whose op9 this encoding leaves free
+        0x01D => UpperOp::Unimplemented("MAXi"),
+        0x01F => UpperOp::Unimplemented("MINIi"),
+        0x025 => UpperOp::Unimplemented("MSUBq"),
+        0x027 => UpperOp::Unimplemented("MSUBi"),
+        0x028 => UpperOp::Unimplemented("ADD"),
+        0x02A => UpperOp::Unimplemented("MUL"),
+        0x02B => UpperOp::Unimplemented("MAX"),
+        0x02C => UpperOp::Unimplemented("SUB"),
+        0x02F => UpperOp::Unimplemented("MINI"),
+        op9 => match op9 & !3 {
+            0x000 => UpperOp::AddBc,
+            0x004 => UpperOp::SubBc,
+            0x008 => UpperOp::MaddBc,
+            0x00C => UpperOp::MsubBc,
+            0x010 => UpperOp::MaxBc,
+            0x014 => UpperOp::MiniBc,
+            0x018 => UpperOp::MulBc,
+            0x020 => UpperOp::MulaBc,
+            0x038 => UpperOp::MaddaBc,
+            0x03C => UpperOp::MsubaBc,
+            _ => return None,
+        },
+    };
+    Some(op)
+}
+
+/// Lower-slot instructions in this interpreter's encoding (see vu1_program.rs).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LowerOp {
+    Nop, IAdd, ISub, IAnd, IOr,
+    Lq, Ilw, Lqi, Lqd, Sq, Sqi, Sqd,
+    IAddIu, ISubIu, IAddI, XTop, XITop,
+    IbEq, IbNe, IbLtz, IbGtz, IbLez, IbGez, B, Bal, Jr, Jalr,
+    XgKick,
+    FsSet, FsAnd, FsOr, FmEq, FmAnd, FmOr,
+    RInit, RGet, RNext, RXor,
+    Move, Mr32, Mfir, Mtir,
+}
+
+/// Decode a lower-slot word; None for an op6 with no instruction.
+fn decode_lower(lower: u32) -> Option<LowerOp> {
+    let op = match (lower >> 26) & 0x3F {
+        // Special group: funct [5:0] picks the integer ALU ops, anything else (the
+        // canonical NOP included) does nothing
+        0x20 => match lower & 0x3F {
+            0x30 => LowerOp::IAdd,
+            0x31 => LowerOp::ISub,
+            0x34 => LowerOp::IAnd,
+            0x35 => LowerOp::IOr,
+            _    => LowerOp::Nop,
+        },
+        0x00 => LowerOp::Lq,
+        0x02 => LowerOp::Ilw,
+        0x3A => LowerOp::Lqi,
+        0x3B => LowerOp::Lqd,
+        0x01 => LowerOp::Sq,
+        0x3E => LowerOp::Sqi,
+        0x3F => LowerOp::Sqd,
+        0x27 => LowerOp::IAddIu,
+        0x28 => LowerOp::ISubIu,
+        0x25 => LowerOp::IAddI,
+        0x2A => LowerOp::XTop,
+        0x2B => LowerOp::XITop,
+        0x22 => LowerOp::IbEq,
+        0x23 => LowerOp::IbNe,
+        0x2C => LowerOp::IbLtz,
+        0x2D => LowerOp::IbGtz,
+        0x2E => LowerOp::IbLez,
+        0x2F => LowerOp::IbGez,
+        0x21 => LowerOp::B,
+        0x29 => LowerOp::Bal,
+        0x24 => LowerOp::Jr,
+        0x26 => LowerOp::Jalr,
+        0x32 => LowerOp::XgKick,
+        0x08 => LowerOp::FsSet,
+        0x09 => LowerOp::FsAnd,
+        0x0A => LowerOp::FsOr,
+        0x0C => LowerOp::FmEq,
+        0x0D => LowerOp::FmAnd,
+        0x0E => LowerOp::FmOr,
+        0x10 => LowerOp::RInit,
+        0x11 => LowerOp::RGet,
+        0x12 => LowerOp::RNext,
+        0x13 => LowerOp::RXor,
+        0x14 => LowerOp::Move,
+        0x15 => LowerOp::Mr32,
+        0x16 => LowerOp::Mfir,
+        0x17 => LowerOp::Mtir,
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// Scan a micro-program and list (PC, mnemonic) for every instruction slot the
+/// interpreter does not run — e.g. to vet an MPG-uploaded program before running it.
+/// Hardware ops it knows but doesn't implement are named ("MAXi"); opcodes it can't
+/// decode at all come out as "unknown upper op9=0x…" / "unknown lower op6=0x…".
+pub fn unsupported_ops(program: &[u64]) -> Vec<(u16, String)> {
+    let mut out = Vec::new();
+    for (pc, &instr) in program.iter().enumerate() {
+        let upper = (instr >> 32) as u32;
+        let lower = (instr & 0xFFFF_FFFF) as u32;
+        match decode_upper(upper) {
+            Some(UpperOp::Unimplemented(name)) => out.push((pc as u16, name.to_string())),
+            Some(_) => {}
+            None => out.push((pc as u16, format!("unknown upper op9=0x{:03X}", upper & 0x1FF))),
+        }
+        if upper & UPPER_I_BIT == 0 && decode_lower(lower).is_none() {
+            out.push((pc as u16, format!("unknown lower op6=0x{:02X}", (lower >> 26) & 0x3F)));
+        }
+    }
+    out
+}
+
+/// Sign-extend the 11-bit immediate / branch offset in bits [10:0] of a lower instruction.
+fn sext11(lower: u32) -> i16 {
+    (((lower & 0x7FF) as i16) << 5) >> 5
+}
+
 enum LowerEffect {
     None,
     Branch(u16),
     XgKick(u16),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vu1_program::*;
+
+    /// A VU1 with `program` loaded at PC 0 in place of the micro-program.
+    fn vu_with(program: &[u64]) -> Vu1 {
+        let mut vu = Vu1::new();
+        vu.code_mem[..program.len()].copy_from_slice(program);
+        vu
+    }
+
+    #[test]
+    fn erleng_lands_in_p_after_its_latency() {
+        let mut vu = vu_with(&[
+            i(u_erleng(1), l_nop()),
+            i(u_mfp(DEST_X, 2), l_nop()),  // still in flight: the old P
+            i(u_waitp(), l_nop()),
+            i(u_mfp(DEST_X, 3), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [3.0, 4.0, 0.0, 0.0];
+        let run = vu.run_until_xgkick();
+        assert_eq!(vu.vf[2][0], 0.0);
+        assert!((vu.vf[3][0] - 0.2).abs() < 1e-6);
+        // 24 cycles from ERLENG: MFP used 1, WAITP waits out 22 and runs on the last
+        assert_eq!(run.stalls, 22);
+        assert_eq!(run.cycles, 4 + 22);
+    }
+
+    #[test]
+    fn eleng_and_eexp() {
+        let mut vu = vu_with(&[
+            i(u_eleng(1), l_nop()),
+            i(u_waitp(), l_nop()),
+            i(u_mfp(DEST_X, 2), l_nop()),
+            i(u_eexp(1, Z), l_nop()),
+            i(u_waitp(), l_nop()),
+            i(u_mfp(DEST_X, 3), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [3.0, 4.0, 2.0, 0.0];
+        vu.run_until_xgkick();
+        assert!((vu.vf[2][0] - 29f32.sqrt()).abs() < 1e-5);
+        assert!((vu.vf[3][0] - (-2f32).exp()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn msub_and_madd_forms() {
+        let mut vu = vu_with(&[
+            i(u_msubbc(DEST_XYW, 3, 1, 2, Y), l_nop()),
+            i(u_madd(DEST_XYZW, 4, 1, 2), l_nop()),
+            i(u_msub(DEST_XYZW, 5, 1, 2), l_nop()),
+            i(u_msubabc(DEST_XYZW, 1, 2, Y), l_nop()),
+            i(u_msubbc(DEST_XYZW, 6, 0, 0, X), l_xgkick(0)), // VF06 = ACC - 0
+        ]);
+        vu.acc   = [10.0, 20.0, 30.0, 40.0];
+        vu.vf[1] = [1.0, 2.0, 3.0, 4.0];
+        vu.vf[2] = [0.0, 2.0, 0.0, 0.0];
+        vu.vf[3] = [9.0; 4];
+        vu.run_until_xgkick();
+        assert_eq!(vu.vf[3], [8.0, 16.0, 9.0, 32.0]); // z masked off
+        assert_eq!(vu.vf[4], [10.0, 24.0, 30.0, 40.0]);
+        assert_eq!(vu.vf[5], [10.0, 16.0, 30.0, 40.0]);
+        assert_eq!(vu.vf[6], [8.0, 16.0, 24.0, 32.0]);
+    }
+
+    #[test]
+    fn abs_clears_signs_under_the_dest_mask() {
+        let mut vu = vu_with(&[
+            i(u_abs(DEST_XYZW, 2, 1), l_nop()),
+            i(u_abs(DEST_XY, 3, 1), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [-1.5, 2.0, -0.0, -4.0];
+        vu.vf[3] = [9.0; 4];
+        vu.run_until_xgkick();
+        assert_eq!(vu.vf[2], [1.5, 2.0, 0.0, 4.0]);
+        assert!(vu.vf[2].iter().all(|c| c.is_sign_positive()));
+        assert_eq!(vu.vf[3], [1.5, 2.0, 9.0, 9.0]);
+    }
+
+    #[test]
+    fn support_check_names_what_the_decode_does_not_run() {
+        let program = [
+            i(uvec(DEST_XYZW, 1, 2, 3, 0x028), l_nop()),   // hardware ADD, not run here
+            i(uvec(DEST_XYZW, 1, 2, 3, 0x040), 0x30 << 26), // no instruction at either op
+            i(u_madd(DEST_XYZW, 1, 2, 3), l_xgkick(0)),
+        ];
+        assert_eq!(unsupported_ops(&program), [
+            (0, "ADD".to_string()),
+            (1, "unknown upper op9=0x040".to_string()),
+            (1, "unknown lower op6=0x30".to_string()),
+        ]);
+        assert_eq!(decode_upper((program[2] >> 32) as u32), Some(UpperOp::Madd));
+        assert_eq!(decode_lower(program[2] as u32), Some(LowerOp::XgKick));
+        assert!(unsupported_ops(VU1_MICRO).is_empty());
+    }
+
+    #[test]
+    fn fsand_masks_the_divide_by_zero_flag_into_vi() {
+        let d = STATUS_D as u32;
+        let sticky_d = d << STATUS_STICKY_SHIFT;
+        let mut vu = vu_with(&[
+            i(u_nop(), l_fsand(2, d)),                    // before the divide: clear
+            i(u_div(1, X, 0, X), l_nop()),                // 2 / 0
+            i(u_nop(), l_fsand(3, d)),                    // after: D set
+            i(u_nop(), l_fsand(4, STATUS_I as u32)),      // only D, not I
+            i(u_nop(), l_fsset(0)),                       // clear the sticky bits
+            i(u_nop(), l_fsand(5, sticky_d)),
+            i(u_nop(), l_fsor(6, 0x800)),
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [2.0, 0.0, 0.0, 0.0];
+        vu.run_until_xgkick();
+        assert_eq!(vu.vi[2], 0);
+        assert_eq!(vu.vi[3], d as i16);
+        assert_eq!(vu.vi[4], 0);
+        assert_eq!(vu.vi[5], 0);
+        assert_eq!(vu.vi[6], (0x800 | d) as i16);
+    }
+
+    #[test]
+    fn rnext_steps_the_ps2_lfsr_from_the_seed() {
+        let mut vu = vu_with(&[
+            i(u_nop(), l_rinit(1, Y)),        // seed from the mantissa only
+            i(u_nop(), l_rget(DEST_X, 2)),
+            i(u_nop(), l_rnext(DEST_Y, 2)),
+            i(u_nop(), l_rnext(DEST_W, 2)),
+            i(u_nop(), l_rxor(1, Y)),
+            i(u_nop(), l_rget(DEST_X, 3)),
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [0.0, f32::from_bits(0x4001_2345), 0.0, 0.0];
+        vu.vf[2] = [0.0; 4];
+        vu.run_until_xgkick();
+        assert_eq!(vu.vf[2].map(f32::to_bits), [0x3F81_2345, 0x3F82_468A, 0, 0x3F84_8D14]);
+        assert_eq!(vu.vf[3][0].to_bits(), 0x3F85_AE51);
+    }
+
+    #[test]
+    fn acc_holds_the_partial_transform_before_the_closing_madd() {
+        let mut vu = vu_with(&[
+            i(ubc(DEST_XYZW, 0, 10, 1, 0x020, X), l_nop()),   // MULAx  ACC = c0·v.x
+            i(ubc(DEST_XYZW, 0, 11, 1, 0x038, Y), l_nop()),   // MADDAy ACC += c1·v.y
+            i(ubc(DEST_XYZW, 0, 12, 1, 0x038, Z), l_nop()),   // MADDAz ACC += c2·v.z
+            i(u_mracc(DEST_XYZW, 3), l_nop()),
+            i(ubc(DEST_XYZW, 2, 13, 1, 0x008, W), l_xgkick(0)), // MADDw VF2 = ACC + c3·v.w
+        ]);
+        vu.vf[1] = [1.0, 2.0, 3.0, 1.0];
+        vu.vf[10] = [1.0, 0.0, 0.0, 0.0];
+        vu.vf[11] = [0.0, 1.0, 0.0, 0.5];
+        vu.vf[12] = [0.0, 0.0, 1.0, -1.0];
+        vu.vf[13] = [0.5, 0.25, -3.0, 4.0];
+        vu.run_until_xgkick();
+        assert_eq!(vu.vf[3], [1.0, 2.0, 3.0, -2.0]);
+        assert_eq!(vu.acc, [1.0, 2.0, 3.0, -2.0]);
+        assert_eq!(vu.vf[2], [1.5, 2.25, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn breakpoint_at_the_loop_body_halts_after_the_preamble() {
+        let mut vu = Vu1::new();
+        vu.set_breakpoint(13);
+        let run = vu.run_until_xgkick();
+        assert_eq!(run.stop, Stop::Breakpoint(13));
+        assert_eq!(run.cycles, 13);
+        // Pointers set, loop counter full, six constants loaded through VI04
+        assert_eq!(vu.vi[1..=7], [0, 109, 36, 188, 108, 36, 72]);
+
+        // Resuming runs one iteration and halts at the loop head again
+        assert_eq!(vu.run_until_xgkick().stop, Stop::Breakpoint(13));
+        assert_eq!((vu.vi[1], vu.vi[3]), (1, 35));
+        vu.clear_breakpoint(13);
+        assert_eq!(vu.run_until_xgkick().stop, Stop::XgKick(108));
+        assert_eq!(vu.vi[3], 0);
+    }
+
+    #[test]
+    fn cube_program_reports_its_cycles_and_no_limit() {
+        let mut vu = Vu1::new();
+        let run = vu.run_until_xgkick();
+        assert_eq!(run.stop, Stop::XgKick(108));
+        assert!(!run.hit_limit);
+        // 13 setup instructions, 36 iterations of the 26-instruction body plus its IBNE,
+        // then the XGKICK; the DIV is done before its WAITQ, so nothing stalls
+        assert_eq!(run.stalls, 0);
+        assert_eq!(run.cycles, 13 + 36 * 27 + 1);
+    }
+
+    #[test]
+    fn runaway_programs_abort_without_a_kick() {
+        let mut vu = vu_with(&[i(u_nop(), l_b(-1))]);
+        let run = vu.run_until_xgkick();
+        assert_eq!(run.stop, Stop::Aborted);
+        assert!(run.hit_limit);
+
+        vu.pc = VU1_MICRO.len() as u16;
+        let run = vu.run_until_xgkick();
+        assert_eq!(run.stop, Stop::Aborted);
+        assert!(!run.hit_limit);
+    }
+
+    #[test]
+    fn fog_coefficient_runs_from_255_at_the_start_to_0_at_the_end() {
+        let (start, end) = (2.0, 10.0);
+        let mut vu = Vu1::new();
+        // An MVP that leaves clip.w = pos.z, the view-space distance
+        vu.data_mem[182..186].copy_from_slice(&[
+            [1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0], [0.0; 4],
+        ]);
+        vu.data_mem[FOG_ADDR as usize] = [0.0, 255.0, 255.0 / (end - start), end];
+        for (v, z) in [start, end, (start + end) / 2.0, end + 5.0, 1.0].into_iter().enumerate() {
+            vu.data_mem[v] = [0.0, 0.0, z, 1.0];
+        }
+        for v in 5..36 {
+            vu.data_mem[v] = [0.0, 0.0, start, 1.0];
+        }
+        vu.pc = FOG_ENTRY;
+        assert_eq!(vu.run_until_xgkick().stop, Stop::XgKick(108));
+
+        // F sits in bits [11:4] of XYZF2's last word
+        let fog = |v: usize| vu.data_mem[109 + 2 * v + 1][3].to_bits() >> 4;
+        assert_eq!(fog(0), 255);
+        assert_eq!(fog(1), 0);
+        assert!(fog(2).abs_diff(128) <= 1, "{}", fog(2));
+        // Clamped beyond either end
+        assert_eq!(fog(3), 0);
+        assert_eq!(fog(4), 255);
+    }
+
+    #[test]
+    fn shared_palette_index_resolves_to_one_color() {
+        let mut vu = Vu1::new();
+        for e in 0..5 {
+            vu.data_mem[PALETTE_ADDR as usize + e] = [e as f32 / 4.0, 0.5, 1.0 - e as f32 / 4.0, 1.0];
+        }
+        // Indices as UNPACK S-8 (USN) leaves them: the byte zero-extended into every lane
+        for v in 0..36 {
+            let index = if v == 5 || v == 30 { 3 } else { v as u32 % 3 };
+            vu.data_mem[72 + v] = [f32::from_bits(index); 4];
+        }
+        // Halt where the lookup hands over to the main program
+        vu.set_breakpoint(0);
+        vu.pc = PALETTE_ENTRY;
+        assert_eq!(vu.run_until_xgkick().stop, Stop::Breakpoint(0));
+
+        let palette3 = vu.data_mem[PALETTE_ADDR as usize + 3];
+        assert_eq!(vu.data_mem[72 + 5], palette3);
+        assert_eq!(vu.data_mem[72 + 30], palette3);
+        assert_eq!(vu.data_mem[72 + 4], vu.data_mem[PALETTE_ADDR as usize + 1]);
+    }
+
+    #[test]
+    fn immediate_widths_extend_as_their_ops_say() {
+        let mut vu = vu_with(&[
+            i(u_nop(), l_iaddi(1, 0, -16)),         // 5-bit signed: the most negative
+            i(u_nop(), l_iaddi(2, 0, 15)),          // and the most positive
+            i(u_nop(), l_isubiu(3, 0, 0x7FFF)),     // 15-bit unsigned: never negative
+            i(u_nop(), l_isubiu(4, 5, 0x4000)),     // bit 14 set, still a subtraction
+            i(u_nop(), l_iaddiu(6, 0, -0x4000)),    // 15-bit signed: bit 14 is the sign
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        vu.vi[5] = 0x4001;
+        vu.run_until_xgkick();
+        assert_eq!(vu.vi[1..=6], [-16, 15, -0x7FFF, 1, 0x4001, -0x4000]);
+    }
+
+    #[test]
+    fn gif_tag_guard_catches_a_store_onto_the_tag() {
+        // An output loop starting one QW early (VI02 = 108) against the correct 109
+        let run = |out: i16| {
+            let mut vu = vu_with(&[
+                i(u_nop(), l_iaddiu(2, 0, out)),
+                i(u_nop(), l_sqi(1, 2)),
+                i(u_nop(), l_sqi(1, 2)),
+                i(u_nop(), l_xgkick(0)),
+            ]);
+            vu.set_write_protect(Some(108..109));
+            vu.vf[1] = [1.0, 2.0, 3.0, 4.0];
+            vu.data_mem[108] = [9.0; 4];
+            vu.run_until_xgkick();
+            vu
+        };
+
+        let bad = run(108);
+        assert_eq!(bad.data_mem[108], [9.0; 4]);
+        assert_eq!(bad.data_mem[109], [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(bad.log.len(), 1);
+        assert!(bad.log[0].contains("write-protected datamem[108]"), "{}", bad.log[0]);
+
+        let good = run(109);
+        assert_eq!(good.data_mem[108], [9.0; 4]);
+        assert_eq!(good.data_mem[109..111], [[1.0, 2.0, 3.0, 4.0]; 2]);
+        assert!(good.log.is_empty());
+    }
+
+    #[test]
+    fn opmula_then_opmsub_is_the_cross_product() {
+        let mut vu = vu_with(&[
+            i(u_opmula(1, 2), l_nop()),
+            i(u_opmsub(3, 2, 1), l_xgkick(0)),
+        ]);
+        vu.acc   = [0.0, 0.0, 0.0, 8.0];
+        vu.vf[1] = [1.0, 2.0, 3.0, 7.0];
+        vu.vf[2] = [4.0, 5.0, 6.0, 9.0];
+        vu.vf[3] = [0.0, 0.0, 0.0, 42.0];
+        vu.run_until_xgkick();
+        // [1,2,3] × [4,5,6]; neither op writes w
+        assert_eq!(vu.vf[3], [-3.0, 6.0, -3.0, 42.0]);
+        assert_eq!(vu.acc, [12.0, 12.0, 5.0, 8.0]);
+    }
+
+    #[test]
+    fn clip_flags_an_off_screen_vertex_and_shift_on_the_next() {
+        const MULA: u32 = 0x020;
+        const MADDA: u32 = 0x038;
+        const MADD: u32 = 0x008;
+        let mut vu = vu_with(&[
+            // VF15 = MVP × VF10, as the main program transforms a vertex
+            i(ubc(DEST_XYZW, 0, 4, 10, MULA, W), l_nop()),
+            i(ubc(DEST_XYZW, 0, 1, 10, MADDA, X), l_nop()),
+            i(ubc(DEST_XYZW, 0, 2, 10, MADDA, Y), l_nop()),
+            i(ubc(DEST_XYZW, 15, 3, 10, MADD, Z), l_nop()),
+            i(u_clipw(15, 15), l_nop()),
+            i(u_clipw(16, 16), l_xgkick(0)),
+        ]);
+        // Doubled x, and y moved down by 3
+        vu.vf[1] = [2.0, 0.0, 0.0, 0.0];
+        vu.vf[2] = [0.0, 1.0, 0.0, 0.0];
+        vu.vf[3] = [0.0, 0.0, 1.0, 0.0];
+        vu.vf[4] = [0.0, -3.0, 0.0, 1.0];
+        vu.vf[10] = [1.0, 0.5, 0.0, 1.0];
+        vu.vf[16] = [0.5, -0.5, 0.0, 1.0];
+        vu.clip_flags = 0xFC_0000;
+        vu.run_until_xgkick();
+        assert_eq!(vu.vf[15], [2.0, -2.5, 0.0, 1.0]);
+
+        // First judgment x > +w (bit 0) and y < -w (bit 3), then shifted up 6 by the
+        // in-frustum vertex's zero judgment; the older judgments fall off past bit 23
+        assert_eq!(vu.clip_flags(), 0b001001 << 6);
+    }
+
+    #[test]
+    fn ftoi_scales_then_rounds_into_integer_bits() {
+        let mut vu = vu_with(&[
+            i(u_ftoi0(DEST_XYZW, 2, 1), l_nop()),
+            i(u_ftoi12(DEST_XYZW, 3, 1), l_nop()),
+            i(u_ftoi15(DEST_XYZW, 4, 1), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [0.75, -1.25, 3.0, 0.5];
+        vu.run_until_xgkick();
+        let ints = |r: usize| vu.vf[r].map(|f| f.to_bits() as i32);
+        // Halves round away from zero
+        assert_eq!(ints(2), [1, -1, 3, 1]);
+        assert_eq!(ints(3), [3072, -5120, 12288, 2048]);
+        assert_eq!(ints(4), [24576, -40960, 98304, 16384]);
+    }
+
+    #[test]
+    fn itof4_recovers_what_ftoi4_stored() {
+        let mut vu = vu_with(&[
+            i(u_ftoi4(DEST_XYZW, 2, 1), l_nop()),
+            i(u_itof4(0b1110, 3, 2), l_nop()),     // xyz: w masked off
+            i(u_ftoi12(DEST_XYZW, 4, 1), l_nop()),
+            i(u_itof12(DEST_XYZW, 5, 4), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [123.4567, -0.3, 2047.9375, 1.0];
+        vu.vf[3] = [9.0; 4];
+        vu.run_until_xgkick();
+        for c in 0..3 {
+            // Within half a step of each format
+            assert!((vu.vf[3][c] - vu.vf[1][c]).abs() <= 1.0 / 32.0, "{:?}", vu.vf[3]);
+            assert!((vu.vf[5][c] - vu.vf[1][c]).abs() <= 1.0 / 8192.0, "{:?}", vu.vf[5]);
+        }
+        assert_eq!(vu.vf[3][3], 9.0);
+        assert_eq!(vu.vf[3][2], 2047.9375); // exactly representable in 12.4
+    }
+
+    #[test]
+    fn rsqrt_waitq_mulq_normalizes_a_vector() {
+        const MULA: u32 = 0x020;
+        const MADDA: u32 = 0x038;
+        const MADD: u32 = 0x008;
+        let mut vu = vu_with(&[
+            // VF02.x = |VF01.xyz|², rotating y and z into x to square them
+            i(u_nop(), l_mr32(DEST_XYZW, 5, 1)),   // VF05 = VF01.yzwx
+            i(u_nop(), l_mr32(DEST_XYZW, 6, 5)),   // VF06 = VF01.zwxy
+            i(ubc(DEST_X, 0, 1, 1, MULA, X), l_nop()),
+            i(ubc(DEST_X, 0, 5, 5, MADDA, X), l_nop()),
+            i(ubc(DEST_X, 2, 6, 6, MADD, X), l_nop()),
+            i(u_rsqrt(0, W, 2, X), l_nop()),       // Q = VF00.w / sqrt(VF02.x)
+            i(u_waitq(), l_nop()),
+            i(u_mulq(0b1110, 3, 1), l_xgkick(0)),  // VF03.xyz = VF01.xyz * Q
+        ]);
+        vu.vf[1] = [3.0, 4.0, 12.0, 0.0];
+        let run = vu.run_until_xgkick();
+        assert_eq!(vu.vf[2][0], 169.0);
+        let len = vu.vf[3][..3].iter().map(|c| c * c).sum::<f32>().sqrt();
+        assert!((len - 1.0).abs() < 1e-6, "{:?}", vu.vf[3]);
+        assert!((vu.vf[3][2] - 12.0 / 13.0).abs() < 1e-6);
+        // WAITQ right behind RSQRT waits out the rest of its 13 cycles
+        assert_eq!(run.stalls, 12);
+    }
+
+    #[test]
+    fn integer_alu_ops_combine_two_registers() {
+        let mut vu = vu_with(&[
+            i(u_nop(), l_iaddiu(1, 0, 0b1100_1010)),
+            i(u_nop(), l_iaddiu(2, 0, 0b0110_0110)),
+            i(u_nop(), l_iand(3, 1, 2)),
+            i(u_nop(), l_ior(4, 1, 2)),
+            i(u_nop(), l_iadd(5, 1, 2)),
+            i(u_nop(), l_isub(6, 2, 1)),
+            i(u_nop(), l_ior(0, 1, 2)),   // VI00 stays 0
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        vu.run_until_xgkick();
+        assert_eq!(vu.vi[3], 0b0100_0010);
+        assert_eq!(vu.vi[4], 0b1110_1110);
+        assert_eq!(vu.vi[5], 0xCA + 0x66);
+        assert_eq!(vu.vi[6], 0x66 - 0xCA);
+        assert_eq!(vu.vi[0], 0);
+    }
+
+    #[test]
+    fn each_branch_loops_as_its_condition_says() {
+        // VI02 counts trips of a loop stepping VI01 by `step`, closed by `branch` to PC 1
+        let trips = |start: i16, step: i16, branch: u32| {
+            let mut vu = vu_with(&[
+                i(u_nop(), l_iaddiu(1, 0, start)),
+                i(u_nop(), l_iaddiu(2, 2, 1)),
+                i(u_nop(), l_iaddiu(1, 1, step)),
+                i(u_nop(), branch),
+                i(u_nop(), l_xgkick(0)),
+            ]);
+            assert!(!vu.run_until_xgkick().hit_limit);
+            vu.vi[2]
+        };
+        assert_eq!(trips(3, -1, l_ibne(1, 0, -3)), 3);
+        assert_eq!(trips(3, -1, l_ibgtz(1, -3)), 3);
+        assert_eq!(trips(3, -1, l_ibgez(1, -3)), 4);   // runs once more, at 0
+        assert_eq!(trips(-3, 1, l_ibltz(1, -3)), 3);
+        assert_eq!(trips(-3, 1, l_iblez(1, -3)), 4);
+
+        // IBEQ exits a loop closed by an always-taken IBGEZ VI00
+        let mut vu = vu_with(&[
+            i(u_nop(), l_iaddiu(1, 0, 3)),
+            i(u_nop(), l_iaddiu(2, 2, 1)),
+            i(u_nop(), l_iaddiu(1, 1, -1)),
+            i(u_nop(), l_ibeq(1, 0, 1)),      // to the XGKICK
+            i(u_nop(), l_ibgez(0, -4)),       // back to PC 1
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        vu.run_until_xgkick();
+        assert_eq!(vu.vi[2], 3);
+    }
+
+    #[test]
+    fn bal_calls_a_subroutine_that_jr_returns_from() {
+        let mut vu = vu_with(&[
+            i(u_nop(), l_iaddiu(1, 0, 5)),
+            i(u_nop(), l_bal(15, 6)),          // call PC 8, linking PC 2
+            i(u_nop(), l_iaddiu(2, 0, 1)),
+            i(u_nop(), l_b(1)),                // over PC 4
+            i(u_nop(), l_iaddiu(3, 0, 99)),
+            i(u_nop(), l_iaddiu(4, 0, 8)),
+            i(u_nop(), l_jalr(15, 4)),         // call it again through VI04, linking PC 7
+            i(u_nop(), l_xgkick(0)),
+            // Subroutine: VI01 += 10, then return through the link
+            i(u_nop(), l_iaddiu(1, 1, 10)),
+            i(u_nop(), l_jr(15)),
+        ]);
+        let run = vu.run_until_xgkick();
+        assert_eq!(vu.vi[1..=3], [25, 1, 0]);
+        assert_eq!(vu.vi[15], 7);
+        // PCs 0 1 8 9 2 3 5 6 8 9 7, stopping just past the XGKICK
+        assert_eq!(run.cycles, 11);
+        assert_eq!(vu.pc, 8);
+    }
+
+    #[test]
+    fn sq_at_an_offset_reads_back_through_lqd() {
+        let mut vu = vu_with(&[
+            i(u_nop(), l_iaddiu(1, 0, 100)),
+            i(u_nop(), l_sq(1, 1, 3)),          // datamem[103]
+            i(u_nop(), l_sq(1, 1, -2)),         // datamem[98]
+            i(u_nop(), l_iaddiu(2, 0, 104)),
+            i(u_nop(), l_lqd(2, 2)),            // --VI02 = 103
+            i(u_nop(), l_iaddiu(3, 0, 201)),
+            i(u_nop(), l_sqd(2, 3)),            // --VI03 = 200
+            i(u_nop(), l_iaddiu(4, 0, 1020)),
+            i(u_nop(), l_sq(1, 4, 10)),         // datamem[1030]: out of range, dropped
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        let q = [1.0, -2.0, 3.5, 4.0];
+        vu.vf[1] = q;
+        vu.run_until_xgkick();
+        assert_eq!((vu.data_mem[103], vu.data_mem[98]), (q, q));
+        assert_eq!(vu.vf[2], q);
+        assert_eq!(vu.data_mem[200], q);
+        assert_eq!((vu.vi[2], vu.vi[3]), (103, 200));
+        assert_eq!(vu.data_mem.iter().filter(|&&m| m == q).count(), 3);
+    }
+
+    #[test]
+    fn mtir_index_addresses_an_lq() {
+        let mut vu = vu_with(&[
+            i(u_nop(), l_mtir(2, 1, Y)),          // VI02 = vertex index 7
+            i(u_nop(), l_lq(3, 2, 100)),          // VF03 = datamem[107]
+            i(u_nop(), l_iaddiu(5, 0, -3)),
+            i(u_nop(), l_mfir(0b1001, 4, 5)),     // VF04.xw = sext(VI05)
+            i(u_nop(), l_move(0b0110, 6, 3)),     // VF06.yz = VF03.yz
+            i(u_nop(), l_move(DEST_XYZW, 0, 3)),  // VF00 stays [0, 0, 0, 1]
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [0.0, f32::from_bits(7), 0.0, 0.0];
+        vu.data_mem[107] = [1.0, 2.0, 3.0, 4.0];
+        vu.run_until_xgkick();
+        assert_eq!(vu.vi[2], 7);
+        assert_eq!(vu.vf[3], [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(vu.vf[4].map(|f| f.to_bits() as i32), [-3, 0, 0, -3]);
+        assert_eq!(vu.vf[6], [0.0, 2.0, 3.0, 0.0]);
+        assert_eq!((vu.vf[0], vu.vf_get(0)), ([0.0; 4], [0.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn madd_and_msub_matrix_over_every_broadcast_lane() {
+        let acc = [10.0, 20.0, 30.0, 40.0];
+        let (fs, ft) = ([1.0, -2.0, 3.0, 0.5], [2.0, 3.0, -4.0, 5.0]);
+        for bc in [X, Y, Z, W] {
+            let run = |program: &[u64]| {
+                let mut vu = vu_with(program);
+                vu.acc = acc;
+                vu.vf[1] = fs;
+                vu.vf[2] = ft;
+                vu.run_until_xgkick();
+                vu
+            };
+            // To VF (ACC untouched), and to ACC, read back through VF04
+            let madd = run(&[i(ubc(DEST_XYZW, 3, 1, 2, 0x008, bc), l_xgkick(0))]);
+            let msub = run(&[i(u_msubbc(DEST_XYZW, 3, 1, 2, bc), l_xgkick(0))]);
+            let madda = run(&[
+                i(ubc(DEST_XYZW, 0, 1, 2, 0x038, bc), l_nop()),
+                i(u_mracc(DEST_XYZW, 4), l_xgkick(0)),
+            ]);
+            let msuba = run(&[
+                i(u_msubabc(DEST_XYZW, 1, 2, bc), l_nop()),
+                i(u_mracc(DEST_XYZW, 4), l_xgkick(0)),
+            ]);
+            let b = ft[bc as usize];
+            let plus: [f32; 4] = std::array::from_fn(|c| acc[c] + fs[c] * b);
+            let minus: [f32; 4] = std::array::from_fn(|c| acc[c] - fs[c] * b);
+            assert_eq!((madd.vf[3], madd.acc), (plus, acc), "bc {bc}");
+            assert_eq!((msub.vf[3], msub.acc), (minus, acc), "bc {bc}");
+            assert_eq!((madda.vf[4], madda.acc), (plus, plus), "bc {bc}");
+            assert_eq!((msuba.vf[4], msuba.acc), (minus, minus), "bc {bc}");
+        }
+    }
+
+    #[test]
+    fn loi_half_scales_a_vector_through_muli() {
+        let mut vu = vu_with(&[
+            loi(u_muli(DEST_XYZW, 2, 1), 0.5),   // MULi sees the I loaded in its own pair
+            loi(u_addi(0b1110, 3, 1), -1.0),      // xyz
+            i(u_subi(DEST_X, 4, 1), l_xgkick(0)), // I is still -1.0
+        ]);
+        vu.vf[1] = [2.0, -4.0, 3.0, 1.0];
+        vu.vf[4] = [9.0; 4];
+        vu.run_until_xgkick();
+        assert_eq!(vu.vf[2], [1.0, -2.0, 1.5, 0.5]);
+        assert_eq!(vu.vf[3], [1.0, -5.0, 2.0, 0.0]);
+        assert_eq!(vu.vf[4], [3.0, 9.0, 9.0, 9.0]);
+        assert_eq!(vu.i_reg, -1.0);
+    }
+
+    #[test]
+    fn acc_and_vf_results_set_the_zero_and_sign_flags() {
+        const MULA: u32 = 0x020;
+        let mut vu = vu_with(&[
+            i(ubc(DEST_XYZW, 0, 1, 2, MULA, X), l_nop()),     // ACC = [0, -3, 6, 0]
+            i(u_nop(), l_fmand(1, 2)),                        // VI01 = MAC & 0xFFF
+            i(u_nop(), l_fmeq(3, 1)),                         // VI03 = (VI01 == MAC)
+            i(u_msubbc(DEST_XYZW, 3, 1, 2, X), l_nop()),      // VF03 = ACC - ACC = 0
+            i(u_nop(), l_fmor(4, 0)),                         // VI04 = MAC
+            i(u_nop(), l_xgkick(0)),
+        ]);
+        vu.vf[1] = [0.0, -1.0, 2.0, 0.0];
+        vu.vf[2] = [3.0, 0.0, 0.0, 0.0];
+        vu.vi[2] = 0xFFF;
+        vu.run_until_xgkick();
+        // An ACC write flags like any FMAC result: Z for x and w, S for y
+        assert_eq!(vu.vi[1], 0b0100_1001);
+        assert_eq!(vu.vi[3], 1);
+        // Then the all-zero VF result: Z in every lane, S clear
+        assert_eq!((vu.vi[4], vu.mac_flags()), (0b1111, 0b1111));
+        assert_eq!(vu.status_flags & 0xF, STATUS_Z);
+        assert_ne!(vu.status_flags & (STATUS_S << STATUS_STICKY_SHIFT), 0);
+    }
+
+    #[test]
+    fn waitq_stalls_out_the_rest_of_the_div() {
+        // DIV then WAITQ with `gap` instructions between them
+        let run = |gap: usize| {
+            let mut program = vec![i(u_div(0, W, 1, X), l_nop())];
+            program.extend(std::iter::repeat_n(i(u_nop(), l_nop()), gap));
+            program.push(i(u_waitq(), l_nop()));
+            program.push(i(u_nop(), l_xgkick(0)));
+            let mut vu = vu_with(&program);
+            vu.vf[1] = [4.0, 0.0, 0.0, 0.0];
+            let run = vu.run_until_xgkick();
+            assert_eq!(vu.q, 0.25);
+            (run.stalls, run.cycles)
+        };
+        // 7 cycles of latency: the WAITQ right behind waits 6, and runs on the 7th
+        assert_eq!(run(0), (6, 3 + 6));
+        assert_eq!(run(4), (2, 7 + 2));
+        assert_eq!(run(6), (0, 9));
+    }
+}
@@ -4,6 +4,7 @@
 // Each u64 = one VU1 instruction: upper[63:32] | lower[31:0]
 //
 // Upper slot (bits [63:32]) — FPU/VF operations:
+//   [31]    = I-bit (LOI: lower[31:0] is a float for the I register)
 //   [30]    = E-bit (end program after next instruction)
 //   [27:24] = dest mask  (xyzw)
 //   [23:19] = ft  (5 bits)
@@ -15,101 +16,554 @@
 //   0x000+bc  ADDbc    VFfd.dest = VFfs.dest + VFft.bc
 //   0x004+bc  SUBbc    VFfd.dest = VFfs.dest - VFft.bc
 //   0x008+bc  MADDbc   VFfd.dest = ACC.dest + VFfs.dest * VFft.bc
+//   0x00C+bc  MSUBbc   VFfd.dest = ACC.dest - VFfs.dest * VFft.bc
 //   0x010+bc  MAXbc    VFfd.dest = max(VFfs.dest, VFft.bc)
 //   0x014+bc  MINIbc   VFfd.dest = min(VFfs.dest, VFft.bc)
 //   0x018+bc  MULbc    VFfd.dest = VFfs.dest * VFft.bc
 //   0x01C     MULq     VFfd.dest = VFfs.dest * Q
 //   0x020+bc  MULAbc   ACC.dest = VFfs.dest * VFft.bc
+//   0x029     MADD     VFfd.dest = ACC.dest + VFfs.dest * VFft.dest
+//   0x02D     MSUB     VFfd.dest = ACC.dest - VFfs.dest * VFft.dest
 //   0x038+bc  MADDAbc  ACC.dest += VFfs.dest * VFft.bc
+//   0x03C+bc  MSUBAbc  ACC.dest -= VFfs.dest * VFft.bc
+//   0x0BC     OPMULA   ACC.xyz = VFfs.yzx * VFft.zxy          (outer product; w untouched)
+//   0x0FE     OPMSUB   VFfd.xyz = ACC.xyz - VFfs.yzx * VFft.zxy
+//   0x024     ADDi     VFfd.dest = VFfs.dest + I  (hardware's 0x022 is MULAz here)
+//   0x026     SUBi     VFfd.dest = VFfs.dest - I
+//   0x01E     MULi     VFfd.dest = VFfs.dest * I
 //   0x070     DIV      Q = VFfs.fsf / VFft.ftf (fd[3:2]=fsf, fd[1:0]=ftf)
+//   0x071     SQRT     Q = sqrt(|VFft.ftf|)  (ftf in fd[1:0], as for DIV)
+//   0x072     RSQRT    Q = VFfs.fsf / sqrt(|VFft.ftf|)  (fields as for DIV)
 //   0x073     WAITQ    stall until Q ready
+//   0x078     ELENG    P = |VFfs.xyz|                 (18-cycle latency)
+//   0x079     ERLENG   P = 1 / |VFfs.xyz|             (24-cycle latency)
+//   0x07A     EEXP     P = exp(-VFfs.fsf) (fd[3:2]=fsf, 44-cycle latency)
+//   0x07B     WAITP    stall until P ready
+//   0x07C     MFP      VFfd.dest = P
+//   0x13C     ITOF4    VFfd[i] = (VFfs[i] bit-cast to i32) as f32 / 16
+//   0x13D     ITOF0    VFfd[i] = (VFfs[i] bit-cast to i32) as f32
+//   0x13E     ITOF12   VFfd[i] = (VFfs[i] bit-cast to i32) as f32 / 4096
+//   0x13F     ITOF15   VFfd[i] = (VFfs[i] bit-cast to i32) as f32 / 32768
 //   0x17C     FTOI4    VFfd[i] = round(VFfs[i]*16) as i32 (bit-cast to f32)
+//   0x17D     FTOI0    as FTOI4, scaled by 1
+//   0x17E     FTOI12   as FTOI4, scaled by 4096
+//   0x17F     FTOI15   as FTOI4, scaled by 32768
+//   0x1FD     ABS      VFfd.dest = |VFfs.dest|
 //   0x1FF     NOP
+//   0x1FF     CLIPw    (dest = xyz) CLIP = CLIP << 6 | judge VFfs.xyz against ±|VFft.w|
+//   MADDx VFfd, VF00, VF00 reads ACC back (ACC + 0*0), see u_mracc
 //
 // Lower slot (bits [31:0]) — integer/memory/branch:
 //   op6 = [31:26]
-//   0x20 (0b100000) NOP  (canonical: 0x8000_0000)
+//   0x08 (0b001000) FSSET imm12:          STATUS[11:6] = imm12[11:6] (sticky bits)
+//   0x09 (0b001001) FSAND it,imm12:       VI[it] = STATUS & imm12
+//   0x0A (0b001010) FSOR  it,imm12:       VI[it] = STATUS | imm12
+//   0x0C (0b001100) FMEQ  it,is:          VI[it] = (VI[is] == MAC) as 0/1
+//   0x0D (0b001101) FMAND it,is:          VI[it] = VI[is] & MAC
+//   0x0E (0b001110) FMOR  it,is:          VI[it] = VI[is] | MAC
+//   0x10 (0b010000) RINIT fs.fsf:         R = 1.0 | mantissa(VF[fs].fsf)
+//   0x11 (0b010001) RGET  dest,ft:        VF[ft].dest = R
+//   0x12 (0b010010) RNEXT dest,ft:        advance LFSR; VF[ft].dest = R
+//   0x13 (0b010011) RXOR  fs.fsf:         R = 1.0 | (mantissa(R) ^ mantissa(VF[fs].fsf))
+//   0x14 (0b010100) MOVE  dest,ft,fs:     VF[ft].dest = VF[fs]
+//   0x15 (0b010101) MR32  dest,ft,fs:     VF[ft].dest = VF[fs].yzwx
+//   0x16 (0b010110) MFIR  dest,ft,is:     VF[ft].dest = sext(VI[is]) (integer bits)
+//   0x17 (0b010111) MTIR  it,fs.fsf:      VI[it] = low 16 bits of VF[fs].fsf
+//   R-unit / move field layout: dest [24:21] (fsf [22:21]), ft/it [20:16], fs/is [15:11]
+//   0x20 (0b100000) NOP  (canonical: 0x8000_0000), or by funct [5:0] (id [10:6], is [15:11],
+//                   it [20:16]):
+//     funct 0x30    IADD id,is,it:        VI[id] = VI[is] + VI[it]
+//     funct 0x31    ISUB id,is,it:        VI[id] = VI[is] - VI[it]
+//     funct 0x34    IAND id,is,it:        VI[id] = VI[is] & VI[it]
+//     funct 0x35    IOR  id,is,it:        VI[id] = VI[is] | VI[it]
 //   0x27 (0b100111) IADDIU vt,vs,imm15:   VI[vt] = VI[vs] + sext(imm15)
+//   0x28 (0b101000) ISUBIU vt,vs,imm15:   VI[vt] = VI[vs] - zext(imm15)
+//   0x25 (0b100101) IADDI it,is,imm5:     VI[it] = VI[is] + sext(imm5), imm5 in [10:6]
+//   0x2A (0b101010) XTOP  it:             VI[it] = VIF1 TOP (double buffer VU1 was started on)
+//   0x2B (0b101011) XITOP it:             VI[it] = VIF1 ITOP
+//   0x22 (0b100010) IBEQ  vs,vt,off11:    if VI[vs]==VI[vt]: PC = PC+1+sext(off11)
 //   0x23 (0b100011) IBNE  vs,vt,off11:    if VI[vs]!=VI[vt]: PC = PC+1+sext(off11)
+//   0x2C (0b101100) IBLTZ vs,off11:       if VI[vs] < 0:  PC = PC+1+sext(off11)
+//   0x2D (0b101101) IBGTZ vs,off11:       if VI[vs] > 0:  (likewise)
+//   0x2E (0b101110) IBLEZ vs,off11:       if VI[vs] <= 0: (likewise)
+//   0x2F (0b101111) IBGEZ vs,off11:       if VI[vs] >= 0: (likewise)
+//   0x21 (0b100001) B     off11:          PC = PC+1+sext(off11)
+//   0x29 (0b101001) BAL   it,off11:       VI[it] = PC+1 (no delay slot); PC = PC+1+sext(off11)
+//   0x24 (0b100100) JR    is:             PC = VI[is]
+//   0x26 (0b100110) JALR  it,is:          VI[it] = PC+1; PC = VI[is]
 //   0x32 (0b110010) XGKICK is:            return VI[is] (end micro-program)
+//   0x00 (0b000000) LQ   ft,imm11(is):    VF[ft] = data_mem[VI[is] + sext(imm11)]
+//   0x02 (0b000010) ILW.x it,imm11(is):   VI[it] = data_mem[VI[is] + sext(imm11)].x (low 16 bits)
+//   0x01 (0b000001) SQ   fs,imm11(it):    data_mem[VI[it] + sext(imm11)] = VF[fs]
 //   0x3A (0b111010) LQI  ft,(is++):       VF[ft] = data_mem[VI[is]]; VI[is]++
+//   0x3B (0b111011) LQD  ft,(--is):       VI[is]--; VF[ft] = data_mem[VI[is]]
 //   0x3E (0b111110) SQI  fs,(it++):       data_mem[VI[it]] = VF[fs]; VI[it]++
+//   0x3F (0b111111) SQD  fs,(--it):       VI[it]--; data_mem[VI[it]] = VF[fs]
+
+// The constants and encoders below are pub(crate) so the interpreter tests in vu1.rs can
+// assemble their own programs.
 
 // ---- Broadcast component constants ----
-const X: u32 = 0;
-const Y: u32 = 1;
-const Z: u32 = 2;
-const W: u32 = 3;
+pub(crate) const X: u32 = 0;
+pub(crate) const Y: u32 = 1;
+pub(crate) const Z: u32 = 2;
+pub(crate) const W: u32 = 3;
 
 // ---- Destination mask ----
-const DEST_XYZW: u32 = 0xF;
-const DEST_XY:   u32 = 0b1100;
-const DEST_X:    u32 = 0b1000;
-const DEST_Y:    u32 = 0b0100;
+pub(crate) const DEST_XYZW: u32 = 0xF;
+pub(crate) const DEST_XY:   u32 = 0b1100;
+pub(crate) const DEST_X:    u32 = 0b1000;
+pub(crate) const DEST_Y:    u32 = 0b0100;
+pub(crate) const DEST_W:    u32 = 0b0001;
+pub(crate) const DEST_XYW:  u32 = 0b1101;
 
 // ---- Upper slot encoding ----
 
 /// Generic bc-flavored upper op: op9 = op_base | bc
-const fn ubc(dest: u32, fd: u32, fs: u32, ft: u32, op_base: u32, bc: u32) -> u32 {
+pub(crate) const fn ubc(dest: u32, fd: u32, fs: u32, ft: u32, op_base: u32, bc: u32) -> u32 {
     let op9 = op_base | bc;
     (dest << 24) | (ft << 19) | (fs << 14) | (fd << 9) | op9
 }
 
-const fn u_nop() -> u32 { 0x0000_01FF }  // op9=0x1FF, all regs 0
+pub(crate) const fn u_nop() -> u32 { 0x0000_01FF }  // op9=0x1FF, all regs 0
+
+/// Full-vector upper op (no broadcast): op9 used as-is
+#[cfg(test)]
+pub(crate) const fn uvec(dest: u32, fd: u32, fs: u32, ft: u32, op9: u32) -> u32 {
+    (dest << 24) | (ft << 19) | (fs << 14) | (fd << 9) | op9
+}
+
+/// Read-back pseudo-op: VFfd.dest = ACC.dest, encoded as MADDx.dest VFfd, VF00, VF00
+/// (VF00.x is hardwired 0, so this adds nothing to ACC). The usual way VU code spills
+/// the accumulator, e.g. to inspect a MULA/MADDA chain before its closing MADD.
+#[cfg(test)]
+pub(crate) const fn u_mracc(dest: u32, fd: u32) -> u32 {
+    ubc(dest, fd, 0, 0, 0x008, X)
+}
+
+/// MADD.dest VFfd, VFfs, VFft — ACC + VFfs * VFft
+#[cfg(test)]
+pub(crate) const fn u_madd(dest: u32, fd: u32, fs: u32, ft: u32) -> u32 {
+    uvec(dest, fd, fs, ft, 0x029)
+}
+
+/// MSUB.dest VFfd, VFfs, VFft — ACC - VFfs * VFft
+#[cfg(test)]
+pub(crate) const fn u_msub(dest: u32, fd: u32, fs: u32, ft: u32) -> u32 {
+    uvec(dest, fd, fs, ft, 0x02D)
+}
+
+/// MSUBbc.dest VFfd, VFfs, VFft.bc — ACC - VFfs * VFft.bc
+#[cfg(test)]
+pub(crate) const fn u_msubbc(dest: u32, fd: u32, fs: u32, ft: u32, bc: u32) -> u32 {
+    ubc(dest, fd, fs, ft, 0x00C, bc)
+}
+
+/// MSUBAbc.dest ACC, VFfs, VFft.bc — ACC -= VFfs * VFft.bc
+#[cfg(test)]
+pub(crate) const fn u_msubabc(dest: u32, fs: u32, ft: u32, bc: u32) -> u32 {
+    ubc(dest, 0, fs, ft, 0x03C, bc)
+}
+
+/// OPMULA.xyz ACC, VFfs, VFft — first half of the cross product VFfs × VFft
+#[cfg(test)]
+pub(crate) const fn u_opmula(fs: u32, ft: u32) -> u32 {
+    uvec(0b1110, 0, fs, ft, 0x0BC)
+}
+
+/// OPMSUB.xyz VFfd, VFfs, VFft — `u_opmula(a, b)` then `u_opmsub(d, b, a)` gives
+/// VFd.xyz = VFa × VFb
+#[cfg(test)]
+pub(crate) const fn u_opmsub(fd: u32, fs: u32, ft: u32) -> u32 {
+    uvec(0b1110, fd, fs, ft, 0x0FE)
+}
+
+/// CLIPw.xyz VFfs, VFft — judge VFfs.xyz against ±|VFft.w| into the clip flag register
+#[cfg(test)]
+pub(crate) const fn u_clipw(fs: u32, ft: u32) -> u32 {
+    uvec(0b1110, 0, fs, ft, 0x1FF)
+}
 
 /// DIV Q, VFfs.fsf / VFft.ftf
 /// op9=0x70, fd field encodes fsf[1:0] in bits [10:9] and ftf[1:0] in bits [12:11]
 /// We use: fd[3:2]=fsf, fd[1:0]=ftf packed in the 5-bit fd field
-const fn u_div(fs: u32, fsf: u32, ft: u32, ftf: u32) -> u32 {
+pub(crate) const fn u_div(fs: u32, fsf: u32, ft: u32, ftf: u32) -> u32 {
     let fd_enc = (fsf << 2) | ftf;
     (ft << 19) | (fs << 14) | (fd_enc << 9) | 0x070
 }
 
+/// SQRT Q, VFft.ftf — same ftf field as DIV
+pub(crate) const fn u_sqrt(ft: u32, ftf: u32) -> u32 {
+    (ft << 19) | (ftf << 9) | 0x071
+}
+
+/// RSQRT Q, VFfs.fsf / sqrt(VFft.ftf) — fsf/ftf packed into fd like DIV
+#[cfg(test)]
+pub(crate) const fn u_rsqrt(fs: u32, fsf: u32, ft: u32, ftf: u32) -> u32 {
+    let fd_enc = (fsf << 2) | ftf;
+    (ft << 19) | (fs << 14) | (fd_enc << 9) | 0x072
+}
+
+/// ADDi.dest VFfd, VFfs, I
+#[cfg(test)]
+pub(crate) const fn u_addi(dest: u32, fd: u32, fs: u32) -> u32 {
+    uvec(dest, fd, fs, 0, 0x024)
+}
+
+/// SUBi.dest VFfd, VFfs, I
+#[cfg(test)]
+pub(crate) const fn u_subi(dest: u32, fd: u32, fs: u32) -> u32 {
+    uvec(dest, fd, fs, 0, 0x026)
+}
+
+/// MULi.dest VFfd, VFfs, I
+#[cfg(test)]
+pub(crate) const fn u_muli(dest: u32, fd: u32, fs: u32) -> u32 {
+    uvec(dest, fd, fs, 0, 0x01E)
+}
+
 /// MULq.dest VFfd, VFfs  (ft=0 implicit Q)
-const fn u_mulq(dest: u32, fd: u32, fs: u32) -> u32 {
+pub(crate) const fn u_mulq(dest: u32, fd: u32, fs: u32) -> u32 {
     (dest << 24) | (fs << 14) | (fd << 9) | 0x01C
 }
 
-const fn u_waitq() -> u32 { 0x073 }  // WAITQ: no registers, op9=0x73
+pub(crate) const fn u_waitq() -> u32 { 0x073 }  // WAITQ: no registers, op9=0x73
+
+/// ELENG P, VFfs — vector length of VFfs.xyz
+#[cfg(test)]
+pub(crate) const fn u_eleng(fs: u32) -> u32 {
+    (fs << 14) | 0x078
+}
+
+/// ERLENG P, VFfs — reciprocal vector length of VFfs.xyz
+#[cfg(test)]
+pub(crate) const fn u_erleng(fs: u32) -> u32 {
+    (fs << 14) | 0x079
+}
+
+/// EEXP P, VFfs.fsf — exp(-x); fsf packed into fd[3:2] like DIV
+#[cfg(test)]
+pub(crate) const fn u_eexp(fs: u32, fsf: u32) -> u32 {
+    (fs << 14) | ((fsf << 2) << 9) | 0x07A
+}
+
+#[cfg(test)]
+pub(crate) const fn u_waitp() -> u32 { 0x07B }  // WAITP: no registers
+
+/// MFP.dest VFfd, P
+#[cfg(test)]
+pub(crate) const fn u_mfp(dest: u32, fd: u32) -> u32 {
+    (dest << 24) | (fd << 9) | 0x07C
+}
 
 /// FTOI4.dest VFfd, VFfs
-const fn u_ftoi4(dest: u32, fd: u32, fs: u32) -> u32 {
+pub(crate) const fn u_ftoi4(dest: u32, fd: u32, fs: u32) -> u32 {
     (dest << 24) | (fs << 14) | (fd << 9) | 0x17C
 }
 
+/// FTOI0.dest VFfd, VFfs
+#[cfg(test)]
+pub(crate) const fn u_ftoi0(dest: u32, fd: u32, fs: u32) -> u32 {
+    uvec(dest, fd, fs, 0, 0x17D)
+}
+
+/// FTOI12.dest VFfd, VFfs
+#[cfg(test)]
+pub(crate) const fn u_ftoi12(dest: u32, fd: u32, fs: u32) -> u32 {
+    uvec(dest, fd, fs, 0, 0x17E)
+}
+
+/// FTOI15.dest VFfd, VFfs
+#[cfg(test)]
+pub(crate) const fn u_ftoi15(dest: u32, fd: u32, fs: u32) -> u32 {
+    uvec(dest, fd, fs, 0, 0x17F)
+}
+
+/// ITOF0.dest VFfd, VFfs
+pub(crate) const fn u_itof0(dest: u32, fd: u32, fs: u32) -> u32 {
+    (dest << 24) | (fs << 14) | (fd << 9) | 0x13D
+}
+
+/// ITOF4.dest VFfd, VFfs
+#[cfg(test)]
+pub(crate) const fn u_itof4(dest: u32, fd: u32, fs: u32) -> u32 {
+    uvec(dest, fd, fs, 0, 0x13C)
+}
+
+/// ITOF12.dest VFfd, VFfs
+#[cfg(test)]
+pub(crate) const fn u_itof12(dest: u32, fd: u32, fs: u32) -> u32 {
+    uvec(dest, fd, fs, 0, 0x13E)
+}
+
+/// ITOF15.dest VFfd, VFfs
+pub(crate) const fn u_itof15(dest: u32, fd: u32, fs: u32) -> u32 {
+    (dest << 24) | (fs << 14) | (fd << 9) | 0x13F
+}
+
+/// ABS.dest VFfd, VFfs
+#[cfg(test)]
+pub(crate) const fn u_abs(dest: u32, fd: u32, fs: u32) -> u32 {
+    (dest << 24) | (fs << 14) | (fd << 9) | 0x1FD
+}
+
 // ---- Lower slot encoding ----
 
-const fn l_nop() -> u32 { 0x8000_0000 }
+pub(crate) const fn l_nop() -> u32 { 0x8000_0000 }
+
+/// Integer ALU op in the NOP group: VI[id] = VI[is] <funct> VI[it]
+#[cfg(test)]
+pub(crate) const fn l_ialu(id: u32, is: u32, it: u32, funct: u32) -> u32 {
+    (0x20 << 26) | (it << 16) | (is << 11) | (id << 6) | funct
+}
+
+/// IADD VI[id], VI[is], VI[it]
+#[cfg(test)]
+pub(crate) const fn l_iadd(id: u32, is: u32, it: u32) -> u32 { l_ialu(id, is, it, 0x30) }
+
+/// ISUB VI[id], VI[is], VI[it]
+#[cfg(test)]
+pub(crate) const fn l_isub(id: u32, is: u32, it: u32) -> u32 { l_ialu(id, is, it, 0x31) }
+
+/// IAND VI[id], VI[is], VI[it]
+#[cfg(test)]
+pub(crate) const fn l_iand(id: u32, is: u32, it: u32) -> u32 { l_ialu(id, is, it, 0x34) }
+
+/// IOR VI[id], VI[is], VI[it]
+#[cfg(test)]
+pub(crate) const fn l_ior(id: u32, is: u32, it: u32) -> u32 { l_ialu(id, is, it, 0x35) }
 
 /// IADDIU VI[vt], VI[vs], imm15 (signed 15-bit immediate)
-const fn l_iaddiu(vt: u32, vs: u32, imm: i16) -> u32 {
+pub(crate) const fn l_iaddiu(vt: u32, vs: u32, imm: i16) -> u32 {
     let imm15 = (imm as u32) & 0x7FFF;
     (0x27 << 26) | (vt << 21) | (vs << 16) | imm15
 }
 
+/// ISUBIU VI[vt], VI[vs], imm15 (unsigned 15-bit immediate)
+#[cfg(test)]
+pub(crate) const fn l_isubiu(vt: u32, vs: u32, imm: u16) -> u32 {
+    let imm15 = (imm as u32) & 0x7FFF;
+    (0x28 << 26) | (vt << 21) | (vs << 16) | imm15
+}
+
+/// IADDI VI[it], VI[is], imm5 (signed 5-bit immediate, -16..=15)
+#[cfg(test)]
+pub(crate) const fn l_iaddi(it: u32, is: u32, imm: i16) -> u32 {
+    let imm5 = (imm as u32) & 0x1F;
+    (0x25 << 26) | (it << 21) | (is << 16) | (imm5 << 6)
+}
+
+/// XTOP VI[it]
+#[cfg(test)]
+pub(crate) const fn l_xtop(it: u32) -> u32 {
+    (0x2A << 26) | (it << 21)
+}
+
+/// XITOP VI[it]
+#[cfg(test)]
+pub(crate) const fn l_xitop(it: u32) -> u32 {
+    (0x2B << 26) | (it << 21)
+}
+
 /// IBNE VI[vs], VI[vt], off11 — branch if not equal; target = PC+1+sext(off11)
-const fn l_ibne(vs: u32, vt: u32, off: i16) -> u32 {
+pub(crate) const fn l_ibne(vs: u32, vt: u32, off: i16) -> u32 {
     let off11 = (off as u32) & 0x7FF;
     (0x23 << 26) | (vs << 21) | (vt << 16) | off11
 }
 
+/// IBEQ VI[vs], VI[vt], off11 — branch if equal
+#[cfg(test)]
+pub(crate) const fn l_ibeq(vs: u32, vt: u32, off: i16) -> u32 {
+    let off11 = (off as u32) & 0x7FF;
+    (0x22 << 26) | (vs << 21) | (vt << 16) | off11
+}
+
+/// Sign-test branch IBLTZ/IBGTZ/IBLEZ/IBGEZ VI[vs], off11 (op6 0x2C..=0x2F)
+#[cfg(test)]
+pub(crate) const fn l_ibsign(op6: u32, vs: u32, off: i16) -> u32 {
+    let off11 = (off as u32) & 0x7FF;
+    (op6 << 26) | (vs << 21) | off11
+}
+
+/// IBLTZ VI[vs], off11 — branch if VI[vs] < 0
+#[cfg(test)]
+pub(crate) const fn l_ibltz(vs: u32, off: i16) -> u32 { l_ibsign(0x2C, vs, off) }
+
+/// IBGTZ VI[vs], off11 — branch if VI[vs] > 0
+#[cfg(test)]
+pub(crate) const fn l_ibgtz(vs: u32, off: i16) -> u32 { l_ibsign(0x2D, vs, off) }
+
+/// IBLEZ VI[vs], off11 — branch if VI[vs] <= 0
+#[cfg(test)]
+pub(crate) const fn l_iblez(vs: u32, off: i16) -> u32 { l_ibsign(0x2E, vs, off) }
+
+/// IBGEZ VI[vs], off11 — branch if VI[vs] >= 0
+#[cfg(test)]
+pub(crate) const fn l_ibgez(vs: u32, off: i16) -> u32 { l_ibsign(0x2F, vs, off) }
+
+/// FSSET imm12 — set the sticky status flags
+#[cfg(test)]
+pub(crate) const fn l_fsset(imm12: u32) -> u32 {
+    (0x08 << 26) | (imm12 & 0xFFF)
+}
+
+/// FSAND VI[it], imm12
+#[cfg(test)]
+pub(crate) const fn l_fsand(it: u32, imm12: u32) -> u32 {
+    (0x09 << 26) | (it << 21) | (imm12 & 0xFFF)
+}
+
+/// FSOR VI[it], imm12
+#[cfg(test)]
+pub(crate) const fn l_fsor(it: u32, imm12: u32) -> u32 {
+    (0x0A << 26) | (it << 21) | (imm12 & 0xFFF)
+}
+
+/// FMEQ VI[it], VI[is] — VI[it] = 1 if VI[is] equals MAC, else 0
+#[cfg(test)]
+pub(crate) const fn l_fmeq(it: u32, is: u32) -> u32 {
+    (0x0C << 26) | (it << 21) | (is << 16)
+}
+
+/// FMAND VI[it], VI[is] — VI[it] = VI[is] & MAC
+#[cfg(test)]
+pub(crate) const fn l_fmand(it: u32, is: u32) -> u32 {
+    (0x0D << 26) | (it << 21) | (is << 16)
+}
+
+/// FMOR VI[it], VI[is] — VI[it] = VI[is] | MAC
+#[cfg(test)]
+pub(crate) const fn l_fmor(it: u32, is: u32) -> u32 {
+    (0x0E << 26) | (it << 21) | (is << 16)
+}
+
+/// RINIT R, VF[fs].fsf
+#[cfg(test)]
+pub(crate) const fn l_rinit(fs: u32, fsf: u32) -> u32 {
+    (0x10 << 26) | (fsf << 21) | (fs << 11)
+}
+
+/// RGET.dest VF[ft], R
+#[cfg(test)]
+pub(crate) const fn l_rget(dest: u32, ft: u32) -> u32 {
+    (0x11 << 26) | (dest << 21) | (ft << 16)
+}
+
+/// RNEXT.dest VF[ft], R
+#[cfg(test)]
+pub(crate) const fn l_rnext(dest: u32, ft: u32) -> u32 {
+    (0x12 << 26) | (dest << 21) | (ft << 16)
+}
+
+/// RXOR R, VF[fs].fsf
+#[cfg(test)]
+pub(crate) const fn l_rxor(fs: u32, fsf: u32) -> u32 {
+    (0x13 << 26) | (fsf << 21) | (fs << 11)
+}
+
+/// MOVE.dest VF[ft], VF[fs]
+#[cfg(test)]
+pub(crate) const fn l_move(dest: u32, ft: u32, fs: u32) -> u32 {
+    (0x14 << 26) | (dest << 21) | (ft << 16) | (fs << 11)
+}
+
+/// MR32.dest VF[ft], VF[fs] — VF[fs] rotated to yzwx
+#[cfg(test)]
+pub(crate) const fn l_mr32(dest: u32, ft: u32, fs: u32) -> u32 {
+    (0x15 << 26) | (dest << 21) | (ft << 16) | (fs << 11)
+}
+
+/// MFIR.dest VF[ft], VI[is]
+#[cfg(test)]
+pub(crate) const fn l_mfir(dest: u32, ft: u32, is: u32) -> u32 {
+    (0x16 << 26) | (dest << 21) | (ft << 16) | (is << 11)
+}
+
+/// MTIR VI[it], VF[fs].fsf
+#[cfg(test)]
+pub(crate) const fn l_mtir(it: u32, fs: u32, fsf: u32) -> u32 {
+    (0x17 << 26) | (fsf << 21) | (it << 16) | (fs << 11)
+}
+
 /// XGKICK VI[is] — kick GIF, end program
-const fn l_xgkick(is: u32) -> u32 {
+pub(crate) const fn l_xgkick(is: u32) -> u32 {
     (0x32 << 26) | (is << 16)
 }
 
+/// JR VI[is] — jump to the address in VI[is]
+pub(crate) const fn l_jr(is: u32) -> u32 {
+    (0x24 << 26) | (is << 16)
+}
+
+/// B off11 — unconditional branch; target = PC+1+sext(off11)
+#[cfg(test)]
+pub(crate) const fn l_b(off: i16) -> u32 {
+    (0x21 << 26) | ((off as u32) & 0x7FF)
+}
+
+/// BAL VI[it], off11 — VI[it] = PC+1, then branch like B
+#[cfg(test)]
+pub(crate) const fn l_bal(it: u32, off: i16) -> u32 {
+    (0x29 << 26) | (it << 21) | ((off as u32) & 0x7FF)
+}
+
+/// JALR VI[it], VI[is] — VI[it] = PC+1, then PC = VI[is]
+#[cfg(test)]
+pub(crate) const fn l_jalr(it: u32, is: u32) -> u32 {
+    (0x26 << 26) | (it << 21) | (is << 16)
+}
+
+/// LQ VF[ft], imm11(VI[is])
+pub(crate) const fn l_lq(ft: u32, is: u32, imm: i16) -> u32 {
+    let imm11 = (imm as u32) & 0x7FF;
+    (ft << 21) | (is << 16) | imm11
+}
+
+/// SQ VF[fs], imm11(VI[it])
+#[cfg(test)]
+pub(crate) const fn l_sq(fs: u32, it: u32, imm: i16) -> u32 {
+    let imm11 = (imm as u32) & 0x7FF;
+    (0x01 << 26) | (fs << 21) | (it << 16) | imm11
+}
+
+/// ILW.x VI[it], imm11(VI[is])
+pub(crate) const fn l_ilw(it: u32, is: u32, imm: i16) -> u32 {
+    let imm11 = (imm as u32) & 0x7FF;
+    (0x02 << 26) | (it << 21) | (is << 16) | imm11
+}
+
 /// LQI VF[ft], (VI[is]++)
-const fn l_lqi(ft: u32, is: u32) -> u32 {
+pub(crate) const fn l_lqi(ft: u32, is: u32) -> u32 {
     (0x3A << 26) | (ft << 21) | (is << 16)
 }
 
 /// SQI VF[fs], (VI[it]++)
-const fn l_sqi(fs: u32, it: u32) -> u32 {
+pub(crate) const fn l_sqi(fs: u32, it: u32) -> u32 {
     (0x3E << 26) | (fs << 21) | (it << 11)
 }
 
+/// LQD VF[ft], (--VI[is])
+#[cfg(test)]
+pub(crate) const fn l_lqd(ft: u32, is: u32) -> u32 {
+    (0x3B << 26) | (ft << 21) | (is << 16)
+}
+
+/// SQD VF[fs], (--VI[it])
+#[cfg(test)]
+pub(crate) const fn l_sqd(fs: u32, it: u32) -> u32 {
+    (0x3F << 26) | (fs << 21) | (it << 11)
+}
+
 // ---- Assemble u64 instruction ----
-const fn i(upper: u32, lower: u32) -> u64 {
+
+/// Pair `upper` with LOI: set its I bit and carry the float `imm` in the lower slot.
+/// The upper op in the same pair already sees the new I.
+#[cfg(test)]
+pub(crate) const fn loi(upper: u32, imm: f32) -> u64 {
+    i(upper | crate::vu1::UPPER_I_BIT, imm.to_bits())
+}
+
+pub(crate) const fn i(upper: u32, lower: u32) -> u64 {
     ((upper as u64) << 32) | (lower as u64)
 }
 
@@ -120,13 +574,15 @@ const fn i(upper: u32, lower: u32) -> u64 {
 //   VF00  hardwired [0,0,0,1]
 //   VF01-04  MVP columns (loaded from datamem[182..185])
 //   VF05     light dir + ambient [lx,ly,lz,amb]
-//   VF09     viewport scale [5120,3584,0,0]
+//   VF09     viewport scale [w/2,h/2,0,1/255] for a w×h target (.w = 8-bit color scale)
 //   VF10     current vertex position (xyzw)
 //   VF11     current vertex normal  (xyz0)
 //   VF12     current vertex color   (rgba)
 //   VF15     clip pos → NDC → GS subpixel coords
 //   VF16     diffuse lighting intensity
 //   VF17     final modulated color
+//   VF18     fog factor in w (fog entry only)
+//   VF19     fog constants [0,255,255/(end-start),end] (fog entry only)
 //
 // VI register usage:
 //   VI00  hardwired 0
@@ -137,6 +593,9 @@ const fn i(upper: u32, lower: u32) -> u64 {
 //   VI05  XGKICK base ptr (datamem[109], fixed)
 //   VI06  norm input ptr  (datamem[36])
 //   VI07  color input ptr (datamem[72])
+//   VI08  store ptr (conversion entries only)
+//   VI09  continuation address of the 16-bit normal conversion
+//   VI10  palette index (palette entry only)
 //
 // Instruction count by section:
 //   PC  0- 6: preamble (7 instructions)
@@ -144,6 +603,24 @@ const fn i(upper: u32, lower: u32) -> u64 {
 //   PC 13-38: loop body (26 instructions per iteration)
 //   PC 39:    IBNE branch-back (target PC=13, offset=-27)
 //   PC 40:    XGKICK (end of program)
+//   PC 41-52: U8_COLOR_ENTRY — convert V4-8 colors in place, then branch to PC 0
+//   PC 53-54: KICK_ENTRY — XGKICK a GIF packet the EE uploaded ready-made at datamem[108]
+//   PC 55-66: S16_NORMAL_ENTRY / S16_NORMAL_U8_COLOR_ENTRY — convert V4-16 normals in place,
+//             then continue at PC 0 or at U8_COLOR_ENTRY
+//   PC 67-97: UNLIT_ENTRY — transform-only routine: same transform and viewport as the
+//             main program, but no normal fetch and no lighting (colors pass through)
+//   PC 98-140: LINEAR_LIGHT_ENTRY — the main program with gamma-aware shading: the
+//             color is scaled by sqrt(intensity) instead of intensity
+//   PC 141-150: PALETTE_ENTRY — replace 8-bit palette indices with their palette colors
+//             in place, then branch to PC 0
+//   PC 151-152: S16_NORMAL_PALETTE_ENTRY — convert V4-16 normals, then continue at
+//             PALETTE_ENTRY
+//   PC 153-191: INTERLEAVED_ENTRY — the main program for attributes interleaved per
+//             vertex (pos, normal, color at datamem[3i..3i+2]), read through VI01 alone
+//   PC 192-238: FOG_ENTRY — the main program plus a per-vertex fog factor from clip.w
+//             (the view-space distance), emitted in XYZF2's F field
+//   PC 239-249: FACE_COLOR_ENTRY — copy each of 6 face colors over its face's 6 vertex
+//             colors in place, then branch to PC 0
 // ========================================================================
 
 pub const VU1_MICRO: &[u64] = {
@@ -177,7 +654,7 @@ pub const VU1_MICRO: &[u64] = {
         i(u_nop(), l_lqi(3,  4)),   // VF03 = datamem[184] — MVP col2
         i(u_nop(), l_lqi(4,  4)),   // VF04 = datamem[185] — MVP col3
         i(u_nop(), l_lqi(5,  4)),   // VF05 = datamem[186] — light [lx,ly,lz,amb]
-        i(u_nop(), l_lqi(9,  4)),   // VF09 = datamem[187] — viewport [5120,3584,0,0]
+        i(u_nop(), l_lqi(9,  4)),   // VF09 = datamem[187] — viewport [w/2,h/2,0,1/255]
 
         // ----------------------------------------------------------------
         // PC 13-15: Load per-vertex data
@@ -231,15 +708,15 @@ pub const VU1_MICRO: &[u64] = {
 
         // ----------------------------------------------------------------
         // PC 31-34: Viewport transform → GS subpixel coordinates
-        //   gs_x = (ndcx + 1) * 5120  =  ndcx*5120 + 5120
-        //   gs_y = (1 - ndcy) * 3584  =  3584 - ndcy*3584
+        //   gs_x = (ndcx + 1) * w/2  =  ndcx*w/2 + w/2   (w/2 = 320 at 640×448)
+        //   gs_y = (1 - ndcy) * h/2  =  h/2 - ndcy*h/2   (h/2 = 224)
         // ----------------------------------------------------------------
         i(ubc(DEST_X, 0,  15,  9, MULA, X), l_nop()),  // ACC.x = VF15.x * VF09.x
         // MADDw.x VF15, VF09, VF00.w  → VF15.x = ACC.x + VF09.x * 1.0
         i(ubc(DEST_X, 15, 9,   0, MADD, W), l_nop()),  // VF15.x = ACC.x + VF09.x
         i(ubc(DEST_Y, 15, 15,  9, MUL,  Y), l_nop()),  // VF15.y = VF15.y * VF09.y
         // SUBbc.y VF15, VF09, VF15.y  → VF15.y = VF09.y - VF15.y  (Y flip)
-        i(ubc(DEST_Y, 15,  9, 15, SUB,  Y), l_nop()),  // VF15.y = 3584 - ndcy*3584
+        i(ubc(DEST_Y, 15,  9, 15, SUB,  Y), l_nop()),  // VF15.y = h/2 - ndcy*h/2
 
         // ----------------------------------------------------------------
         // PC 35: FTIO4 — convert VF15.xy to GS 12.4 fixed-point integers
@@ -249,8 +726,8 @@ pub const VU1_MICRO: &[u64] = {
         // ----------------------------------------------------------------
         // PC 36-38: Store output, decrement counter
         // ----------------------------------------------------------------
-        i(u_nop(), l_sqi(15, 2)),            // data_mem[VI02++] = VF15 (GS coords)
-        i(u_nop(), l_sqi(17, 2)),            // data_mem[VI02++] = VF17 (color)
+        i(u_nop(), l_sqi(17, 2)),            // data_mem[VI02++] = VF17 (color → RGBAQ)
+        i(u_nop(), l_sqi(15, 2)),            // data_mem[VI02++] = VF15 (GS coords → XYZ2, kicks vertex)
         i(u_nop(), l_iaddiu(3, 3, -1)),      // VI03--
 
         // ----------------------------------------------------------------
@@ -263,5 +740,337 @@ pub const VU1_MICRO: &[u64] = {
         // PC 40: XGKICK — signal GIF DMA start, end micro-program
         // ----------------------------------------------------------------
         i(u_nop(), l_xgkick(5)),
+        // ----------------------------------------------------------------
+        // PC 41-52: 8-bit color entry (MSCAL U8_COLOR_ENTRY)
+        //   The EE uploaded colors with UNPACK V4-8 (unsigned), so datamem[72..107]
+        //   holds integer RGBA 0-255. Rewrite them as floats 0-1 for the main loop.
+        // ----------------------------------------------------------------
+        i(u_nop(), l_iaddiu(7, 0,  72)),   // VI07 = 72   (color load ptr)
+        i(u_nop(), l_iaddiu(8, 0,  72)),   // VI08 = 72   (color store ptr)
+        i(u_nop(), l_iaddiu(3, 0,  36)),   // VI03 = 36   (loop counter)
+        i(u_nop(), l_iaddiu(4, 0, 187)),   // VI04 = 187  (viewport QW, w = 1/255)
+        i(u_nop(), l_lqi(9, 4)),           // VF09 = datamem[187]
+        // CONVERT (PC=46):
+        i(u_nop(), l_lqi(12, 7)),                             // VF12 = [r,g,b,a] as u32  VI07++
+        i(u_itof0(DEST_XYZW, 12, 12), l_nop()),               // VF12 = float(VF12)
+        i(ubc(DEST_XYZW, 12, 12, 9, MUL, W), l_nop()),        // VF12 *= 1/255
+        i(u_nop(), l_sqi(12, 8)),                             // datamem[VI08++] = VF12
+        i(u_nop(), l_iaddiu(3, 3, -1)),                       // VI03--
+        //   offset = 46 - (51+1) = -6
+        i(u_nop(), l_ibne(3, 0, -6)),
+        // Unconditional branch to PC 0 (VI07 = 108 ≠ 0): offset = 0 - (52+1) = -53
+        i(u_nop(), l_ibne(7, 0, -53)),
+        // ----------------------------------------------------------------
+        // PC 53-54: pass-through entry (MSCAL KICK_ENTRY)
+        //   No transform: send the GIF packet already at datamem[108] as-is.
+        // ----------------------------------------------------------------
+        i(u_nop(), l_iaddiu(5, 0, 108)),   // VI05 = 108  (GIF tag addr)
+        i(u_nop(), l_xgkick(5)),
+        // ----------------------------------------------------------------
+        // PC 55-66: 16-bit normal entries
+        //   The EE uploaded normals with UNPACK V4-16 (signed), so datamem[36..71]
+        //   holds 1.15 fixed-point integers. Rewrite them as floats, then JR to VI09.
+        // ----------------------------------------------------------------
+        // S16_NORMAL_U8_COLOR_ENTRY (PC=55): colors are packed too — continue at PC 41
+        i(u_nop(), l_iaddiu(9, 0, 41)),    // VI09 = U8_COLOR_ENTRY
+        i(u_nop(), l_ibne(9, 0, 1)),       // skip the next instruction (VI09 ≠ 0)
+        // S16_NORMAL_ENTRY (PC=57): continue at the main program
+        i(u_nop(), l_iaddiu(9, 0, 0)),     // VI09 = 0
+        i(u_nop(), l_iaddiu(6, 0, 36)),    // VI06 = 36   (normal load ptr)
+        i(u_nop(), l_iaddiu(8, 0, 36)),    // VI08 = 36   (normal store ptr)
+        i(u_nop(), l_iaddiu(3, 0, 36)),    // VI03 = 36   (loop counter)
+        // CONVERT (PC=61):
+        i(u_nop(), l_lqi(11, 6)),                             // VF11 = [nx,ny,nz,0] as i32  VI06++
+        i(u_itof15(DEST_XYZW, 11, 11), l_nop()),              // VF11 = float(VF11) / 32768
+        i(u_nop(), l_sqi(11, 8)),                             // datamem[VI08++] = VF11
+        i(u_nop(), l_iaddiu(3, 3, -1)),                       // VI03--
+        //   offset = 61 - (65+1) = -5
+        i(u_nop(), l_ibne(3, 0, -5)),
+        i(u_nop(), l_jr(9)),                                  // PC = VI09
+        // ----------------------------------------------------------------
+        // PC 67-97: transform-only entry (MSCAL UNLIT_ENTRY)
+        //   The packet carries no normals; VF05 (light) is not loaded and each vertex
+        //   color goes straight to RGBAQ.
+        // ----------------------------------------------------------------
+        i(u_nop(), l_iaddiu(1, 0,   0)),   // VI01 = 0    (pos ptr)
+        i(u_nop(), l_iaddiu(7, 0,  72)),   // VI07 = 72   (color ptr)
+        i(u_nop(), l_iaddiu(2, 0, 109)),   // VI02 = 109  (output write ptr)
+        i(u_nop(), l_iaddiu(5, 0, 108)),   // VI05 = 108  (XGKICK base = GIF tag addr)
+        i(u_nop(), l_iaddiu(3, 0,  36)),   // VI03 = 36   (loop counter)
+        i(u_nop(), l_iaddiu(4, 0, 182)),   // VI04 = 182  (const load ptr)
+        i(u_nop(), l_lqi(1,  4)),          // VF01 = datamem[182] — MVP col0
+        i(u_nop(), l_lqi(2,  4)),          // VF02 = datamem[183] — MVP col1
+        i(u_nop(), l_lqi(3,  4)),          // VF03 = datamem[184] — MVP col2
+        i(u_nop(), l_lqi(4,  4)),          // VF04 = datamem[185] — MVP col3
+        i(u_nop(), l_iaddiu(4, 4, 1)),     // VI04 = 187  (skip the light QW)
+        i(u_nop(), l_lqi(9,  4)),          // VF09 = datamem[187] — viewport
+        // UNLIT LOOP (PC=79):
+        i(u_nop(), l_lqi(10, 1)),                         // VF10 = pos  [x,y,z,1]  VI01++
+        i(u_nop(), l_lqi(17, 7)),                         // VF17 = color [r,g,b,1]  VI07++
+        i(ubc(DEST_XYZW, 0, 4, 10, MULA, W), l_nop()),    // ACC  = VF04 * VF10.w
+        i(ubc(DEST_XYZW, 0, 1, 10, MADDA, X), l_nop()),   // ACC += VF01 * VF10.x
+        i(ubc(DEST_XYZW, 0, 2, 10, MADDA, Y), l_nop()),   // ACC += VF02 * VF10.y
+        i(ubc(DEST_XYZW, 15, 3, 10, MADD, Z), l_nop()),   // VF15 = ACC + VF03*VF10.z
+        i(u_div(0, W, 15, W), l_nop()),                   // Q = VF00.w / VF15.w
+        i(u_waitq(), l_nop()),
+        i(u_mulq(0b1110 /*xyz*/, 15, 15), l_nop()),       // VF15.xyz = VF15.xyz * Q
+        i(ubc(DEST_X, 0,  15,  9, MULA, X), l_nop()),     // ACC.x = VF15.x * VF09.x
+        i(ubc(DEST_X, 15, 9,   0, MADD, W), l_nop()),     // VF15.x = ACC.x + VF09.x
+        i(ubc(DEST_Y, 15, 15,  9, MUL,  Y), l_nop()),     // VF15.y = VF15.y * VF09.y
+        i(ubc(DEST_Y, 15,  9, 15, SUB,  Y), l_nop()),     // VF15.y = VF09.y - VF15.y  (Y flip)
+        i(u_ftoi4(DEST_XY, 15, 15), l_nop()),
+        i(u_nop(), l_sqi(17, 2)),                         // data_mem[VI02++] = VF17 (RGBAQ)
+        i(u_nop(), l_sqi(15, 2)),                         // data_mem[VI02++] = VF15 (XYZ2)
+        i(u_nop(), l_iaddiu(3, 3, -1)),                   // VI03--
+        //   offset = 79 - (96+1) = -18
+        i(u_nop(), l_ibne(3, 0, -18)),
+        i(u_nop(), l_xgkick(5)),
+        // ----------------------------------------------------------------
+        // PC 98-140: linear-light entry (MSCAL LINEAR_LIGHT_ENTRY)
+        //   Lighting in linear space with sRGB approximated as gamma 2.0:
+        //   encode(decode(c) * I) = sqrt(c² * I) = c * sqrt(I), so the sRGB base color is
+        //   scaled by sqrt(I) (SQRT, after the perspective divide has released Q).
+        // ----------------------------------------------------------------
+        i(u_nop(), l_iaddiu(1, 0,   0)),   // VI01 = 0    (pos ptr)
+        i(u_nop(), l_iaddiu(6, 0,  36)),   // VI06 = 36   (norm ptr)
+        i(u_nop(), l_iaddiu(7, 0,  72)),   // VI07 = 72   (color ptr)
+        i(u_nop(), l_iaddiu(2, 0, 109)),   // VI02 = 109  (output write ptr)
+        i(u_nop(), l_iaddiu(5, 0, 108)),   // VI05 = 108  (XGKICK base = GIF tag addr)
+        i(u_nop(), l_iaddiu(3, 0,  36)),   // VI03 = 36   (loop counter)
+        i(u_nop(), l_iaddiu(4, 0, 182)),   // VI04 = 182  (const load ptr)
+        i(u_nop(), l_lqi(1,  4)),          // VF01 = datamem[182] — MVP col0
+        i(u_nop(), l_lqi(2,  4)),          // VF02 = datamem[183] — MVP col1
+        i(u_nop(), l_lqi(3,  4)),          // VF03 = datamem[184] — MVP col2
+        i(u_nop(), l_lqi(4,  4)),          // VF04 = datamem[185] — MVP col3
+        i(u_nop(), l_lqi(5,  4)),          // VF05 = datamem[186] — light [lx,ly,lz,amb]
+        i(u_nop(), l_lqi(9,  4)),          // VF09 = datamem[187] — viewport
+        // LINEAR LOOP (PC=111):
+        i(u_nop(), l_lqi(10, 1)),                         // VF10 = pos  [x,y,z,1]  VI01++
+        i(u_nop(), l_lqi(11, 6)),                         // VF11 = norm [nx,ny,nz,0]  VI06++
+        i(u_nop(), l_lqi(12, 7)),                         // VF12 = color [r,g,b,1]  VI07++
+        i(ubc(DEST_XYZW, 0, 4, 10, MULA, W), l_nop()),    // ACC  = VF04 * VF10.w
+        i(ubc(DEST_XYZW, 0, 1, 10, MADDA, X), l_nop()),   // ACC += VF01 * VF10.x
+        i(ubc(DEST_XYZW, 0, 2, 10, MADDA, Y), l_nop()),   // ACC += VF02 * VF10.y
+        i(ubc(DEST_XYZW, 15, 3, 10, MADD, Z), l_nop()),   // VF15 = ACC + VF03*VF10.z
+        i(u_div(0, W, 15, W), l_nop()),                   // Q = VF00.w / VF15.w
+        i(ubc(DEST_XYZW, 0, 11,  5, MULA,  X), l_nop()),  // ACC  = VF11 * VF05.x
+        i(ubc(DEST_XYZW, 0, 11,  5, MADDA, Y), l_nop()),  // ACC += VF11 * VF05.y
+        i(ubc(DEST_XYZW, 16, 11, 5, MADD,  Z), l_nop()),  // VF16 = ACC + VF11*VF05.z  (dot)
+        i(ubc(DEST_XYZW, 16, 16, 0, MAX,   X), l_nop()),  // VF16 = max(VF16, 0)
+        i(ubc(DEST_XYZW, 16, 16, 0, MINI,  W), l_nop()),  // VF16 = min(VF16, 1)
+        i(ubc(DEST_XYZW, 16, 16, 5, ADD,   W), l_nop()),  // VF16 += ambient
+        i(ubc(DEST_XYZW, 16, 16, 0, MINI,  W), l_nop()),  // VF16 = min(VF16, 1) — intensity I
+        i(u_waitq(), l_nop()),
+        i(u_mulq(0b1110 /*xyz*/, 15, 15), l_nop()),       // VF15.xyz = VF15.xyz * Q
+        i(u_sqrt(16, X), l_nop()),                        // Q = sqrt(I)
+        i(ubc(DEST_X, 0,  15,  9, MULA, X), l_nop()),     // ACC.x = VF15.x * VF09.x
+        i(ubc(DEST_X, 15, 9,   0, MADD, W), l_nop()),     // VF15.x = ACC.x + VF09.x
+        i(ubc(DEST_Y, 15, 15,  9, MUL,  Y), l_nop()),     // VF15.y = VF15.y * VF09.y
+        i(ubc(DEST_Y, 15,  9, 15, SUB,  Y), l_nop()),     // VF15.y = VF09.y - VF15.y  (Y flip)
+        i(u_ftoi4(DEST_XY, 15, 15), l_nop()),
+        i(u_waitq(), l_nop()),
+        i(u_mulq(DEST_XYZW, 17, 12), l_nop()),            // VF17 = VF12 * sqrt(I)
+        i(u_nop(), l_sqi(17, 2)),                         // data_mem[VI02++] = VF17 (RGBAQ)
+        i(u_nop(), l_sqi(15, 2)),                         // data_mem[VI02++] = VF15 (XYZ2)
+        i(u_nop(), l_iaddiu(3, 3, -1)),                   // VI03--
+        //   offset = 111 - (139+1) = -29
+        i(u_nop(), l_ibne(3, 0, -29)),
+        i(u_nop(), l_xgkick(5)),
+        // ----------------------------------------------------------------
+        // PC 141-150: indexed-color entry (MSCAL PALETTE_ENTRY)
+        //   The EE uploaded one 8-bit index per vertex with UNPACK S-8 (USN) into
+        //   datamem[72..107] and the f32 palette at datamem[PALETTE_ADDR..]. Look each
+        //   index up and write the palette color over it for the main loop.
+        // ----------------------------------------------------------------
+        i(u_nop(), l_iaddiu(7, 0,  72)),   // VI07 = 72   (index load ptr)
+        i(u_nop(), l_iaddiu(8, 0,  72)),   // VI08 = 72   (color store ptr)
+        i(u_nop(), l_iaddiu(3, 0,  36)),   // VI03 = 36   (loop counter)
+        // LOOKUP (PC=144):
+        i(u_nop(), l_ilw(10, 7, 0)),                          // VI10 = index
+        i(u_nop(), l_lq(12, 10, PALETTE_ADDR as i16)),        // VF12 = palette[VI10]
+        i(u_nop(), l_sqi(12, 8)),                             // datamem[VI08++] = VF12
+        i(u_nop(), l_iaddiu(7, 7, 1)),                        // VI07++
+        i(u_nop(), l_iaddiu(3, 3, -1)),                       // VI03--
+        //   offset = 144 - (149+1) = -6
+        i(u_nop(), l_ibne(3, 0, -6)),
+        // Unconditional branch to PC 0 (VI07 = 108 ≠ 0): offset = 0 - (150+1) = -151
+        i(u_nop(), l_ibne(7, 0, -151)),
+        // S16_NORMAL_PALETTE_ENTRY (PC=151): convert normals, then continue at PC 141
+        i(u_nop(), l_iaddiu(9, 0, 141)),   // VI09 = PALETTE_ENTRY
+        // Branch past S16_NORMAL_ENTRY's VI09 = 0 (VI09 ≠ 0): offset = 58 - (152+1) = -95
+        i(u_nop(), l_ibne(9, 0, -95)),
+        // ----------------------------------------------------------------
+        // PC 153-191: interleaved-attribute entry (MSCAL INTERLEAVED_ENTRY)
+        //   The EE unpacked with STCYCL CL=3 WL=1 (skipping write), so each vertex's
+        //   position, normal and color sit back to back and one pointer walks all three.
+        // ----------------------------------------------------------------
+        i(u_nop(), l_iaddiu(1, 0,   0)),   // VI01 = 0    (attribute ptr)
+        i(u_nop(), l_iaddiu(2, 0, 109)),   // VI02 = 109  (output write ptr)
+        i(u_nop(), l_iaddiu(5, 0, 108)),   // VI05 = 108  (XGKICK base = GIF tag addr)
+        i(u_nop(), l_iaddiu(3, 0,  36)),   // VI03 = 36   (loop counter)
+        i(u_nop(), l_iaddiu(4, 0, 182)),   // VI04 = 182  (const load ptr)
+        i(u_nop(), l_lqi(1,  4)),          // VF01 = datamem[182] — MVP col0
+        i(u_nop(), l_lqi(2,  4)),          // VF02 = datamem[183] — MVP col1
+        i(u_nop(), l_lqi(3,  4)),          // VF03 = datamem[184] — MVP col2
+        i(u_nop(), l_lqi(4,  4)),          // VF04 = datamem[185] — MVP col3
+        i(u_nop(), l_lqi(5,  4)),          // VF05 = datamem[186] — light [lx,ly,lz,amb]
+        i(u_nop(), l_lqi(9,  4)),          // VF09 = datamem[187] — viewport
+        // INTERLEAVED LOOP (PC=164):
+        i(u_nop(), l_lqi(10, 1)),                         // VF10 = pos  [x,y,z,1]  VI01++
+        i(u_nop(), l_lqi(11, 1)),                         // VF11 = norm [nx,ny,nz,0]  VI01++
+        i(u_nop(), l_lqi(12, 1)),                         // VF12 = color [r,g,b,1]  VI01++
+        i(ubc(DEST_XYZW, 0, 4, 10, MULA, W), l_nop()),    // ACC  = VF04 * VF10.w
+        i(ubc(DEST_XYZW, 0, 1, 10, MADDA, X), l_nop()),   // ACC += VF01 * VF10.x
+        i(ubc(DEST_XYZW, 0, 2, 10, MADDA, Y), l_nop()),   // ACC += VF02 * VF10.y
+        i(ubc(DEST_XYZW, 15, 3, 10, MADD, Z), l_nop()),   // VF15 = ACC + VF03*VF10.z
+        i(u_div(0, W, 15, W), l_nop()),                   // Q = VF00.w / VF15.w
+        i(ubc(DEST_XYZW, 0, 11,  5, MULA,  X), l_nop()),  // ACC  = VF11 * VF05.x
+        i(ubc(DEST_XYZW, 0, 11,  5, MADDA, Y), l_nop()),  // ACC += VF11 * VF05.y
+        i(ubc(DEST_XYZW, 16, 11, 5, MADD,  Z), l_nop()),  // VF16 = ACC + VF11*VF05.z  (dot)
+        i(ubc(DEST_XYZW, 16, 16, 0, MAX,   X), l_nop()),  // VF16 = max(VF16, 0)
+        i(ubc(DEST_XYZW, 16, 16, 0, MINI,  W), l_nop()),  // VF16 = min(VF16, 1)
+        i(ubc(DEST_XYZW, 16, 16, 5, ADD,   W), l_nop()),  // VF16 += ambient
+        i(ubc(DEST_XYZW, 16, 16, 0, MINI,  W), l_nop()),  // VF16 = min(VF16, 1)
+        i(ubc(DEST_XYZW, 17, 12, 16, MUL, X), l_nop()),   // VF17 = VF12 * VF16.x
+        i(u_waitq(), l_nop()),
+        i(u_mulq(0b1110 /*xyz*/, 15, 15), l_nop()),       // VF15.xyz = VF15.xyz * Q
+        i(ubc(DEST_X, 0,  15,  9, MULA, X), l_nop()),     // ACC.x = VF15.x * VF09.x
+        i(ubc(DEST_X, 15, 9,   0, MADD, W), l_nop()),     // VF15.x = ACC.x + VF09.x
+        i(ubc(DEST_Y, 15, 15,  9, MUL,  Y), l_nop()),     // VF15.y = VF15.y * VF09.y
+        i(ubc(DEST_Y, 15,  9, 15, SUB,  Y), l_nop()),     // VF15.y = VF09.y - VF15.y  (Y flip)
+        i(u_ftoi4(DEST_XY, 15, 15), l_nop()),
+        i(u_nop(), l_sqi(17, 2)),                         // data_mem[VI02++] = VF17 (RGBAQ)
+        i(u_nop(), l_sqi(15, 2)),                         // data_mem[VI02++] = VF15 (XYZ2)
+        i(u_nop(), l_iaddiu(3, 3, -1)),                   // VI03--
+        //   offset = 164 - (190+1) = -27
+        i(u_nop(), l_ibne(3, 0, -27)),
+        i(u_nop(), l_xgkick(5)),
+
+        // ----------------------------------------------------------------
+        // PC 192-238: fog entry (MSCAL FOG_ENTRY)
+        //   The main program, plus f = clamp((end - clip.w) * 255/(end-start), 0, 255)
+        //   per vertex: 255 up to the fog start, 0 from the fog end. clip.w is the
+        //   view-space depth. FTOI4 on w puts f in bits [11:4], XYZF2's F field.
+        // ----------------------------------------------------------------
+        i(u_nop(), l_iaddiu(1, 0,   0)),   // VI01 = 0    (pos ptr)
+        i(u_nop(), l_iaddiu(6, 0,  36)),   // VI06 = 36   (norm ptr)
+        i(u_nop(), l_iaddiu(7, 0,  72)),   // VI07 = 72   (color ptr)
+        i(u_nop(), l_iaddiu(2, 0, 109)),   // VI02 = 109  (output write ptr)
+        i(u_nop(), l_iaddiu(5, 0, 108)),   // VI05 = 108  (XGKICK base = GIF tag addr)
+        i(u_nop(), l_iaddiu(3, 0,  36)),   // VI03 = 36   (loop counter)
+        i(u_nop(), l_iaddiu(4, 0, 182)),   // VI04 = 182  (const load ptr)
+        i(u_nop(), l_lqi(1,  4)),          // VF01 = datamem[182] — MVP col0
+        i(u_nop(), l_lqi(2,  4)),          // VF02 = datamem[183] — MVP col1
+        i(u_nop(), l_lqi(3,  4)),          // VF03 = datamem[184] — MVP col2
+        i(u_nop(), l_lqi(4,  4)),          // VF04 = datamem[185] — MVP col3
+        i(u_nop(), l_lqi(5,  4)),          // VF05 = datamem[186] — light [lx,ly,lz,amb]
+        i(u_nop(), l_lqi(9,  4)),          // VF09 = datamem[187] — viewport
+        i(u_nop(), l_lq(19, 0, FOG_ADDR as i16)),         // VF19 = fog constants
+        // FOG LOOP (PC=206):
+        i(u_nop(), l_lqi(10, 1)),                         // VF10 = pos  [x,y,z,1]  VI01++
+        i(u_nop(), l_lqi(11, 6)),                         // VF11 = norm [nx,ny,nz,0]  VI06++
+        i(u_nop(), l_lqi(12, 7)),                         // VF12 = color [r,g,b,1]  VI07++
+        i(ubc(DEST_XYZW, 0, 4, 10, MULA, W), l_nop()),    // ACC  = VF04 * VF10.w
+        i(ubc(DEST_XYZW, 0, 1, 10, MADDA, X), l_nop()),   // ACC += VF01 * VF10.x
+        i(ubc(DEST_XYZW, 0, 2, 10, MADDA, Y), l_nop()),   // ACC += VF02 * VF10.y
+        i(ubc(DEST_XYZW, 15, 3, 10, MADD, Z), l_nop()),   // VF15 = ACC + VF03*VF10.z
+        i(u_div(0, W, 15, W), l_nop()),                   // Q = VF00.w / VF15.w
+        i(ubc(DEST_XYZW, 0, 11,  5, MULA,  X), l_nop()),  // ACC  = VF11 * VF05.x
+        i(ubc(DEST_XYZW, 0, 11,  5, MADDA, Y), l_nop()),  // ACC += VF11 * VF05.y
+        i(ubc(DEST_XYZW, 16, 11, 5, MADD,  Z), l_nop()),  // VF16 = ACC + VF11*VF05.z  (dot)
+        i(ubc(DEST_XYZW, 16, 16, 0, MAX,   X), l_nop()),  // VF16 = max(VF16, 0)
+        i(ubc(DEST_XYZW, 16, 16, 0, MINI,  W), l_nop()),  // VF16 = min(VF16, 1)
+        i(ubc(DEST_XYZW, 16, 16, 5, ADD,   W), l_nop()),  // VF16 += ambient
+        i(ubc(DEST_XYZW, 16, 16, 0, MINI,  W), l_nop()),  // VF16 = min(VF16, 1)
+        i(ubc(DEST_W, 18, 19, 15, SUB,  W), l_nop()),     // VF18.w = end - VF15.w
+        i(ubc(DEST_W, 18, 18, 19, MUL,  Z), l_nop()),     // VF18.w *= 255/(end-start)
+        i(ubc(DEST_W, 18, 18,  0, MAX,  X), l_nop()),     // VF18.w = max(VF18.w, 0)
+        i(ubc(DEST_W, 18, 18, 19, MINI, Y), l_nop()),     // VF18.w = min(VF18.w, 255)
+        i(ubc(DEST_XYZW, 17, 12, 16, MUL, X), l_nop()),   // VF17 = VF12 * VF16.x
+        i(u_waitq(), l_nop()),
+        i(u_mulq(0b1110 /*xyz*/, 15, 15), l_nop()),       // VF15.xyz = VF15.xyz * Q
+        i(ubc(DEST_X, 0,  15,  9, MULA, X), l_nop()),     // ACC.x = VF15.x * VF09.x
+        i(ubc(DEST_X, 15, 9,   0, MADD, W), l_nop()),     // VF15.x = ACC.x + VF09.x
+        i(ubc(DEST_Y, 15, 15,  9, MUL,  Y), l_nop()),     // VF15.y = VF15.y * VF09.y
+        i(ubc(DEST_Y, 15,  9, 15, SUB,  Y), l_nop()),     // VF15.y = VF09.y - VF15.y  (Y flip)
+        i(ubc(DEST_W, 15, 18,  0, ADD,  X), l_nop()),     // VF15.w = VF18.w (fog)
+        i(u_ftoi4(DEST_XYW, 15, 15), l_nop()),
+        i(u_nop(), l_sqi(17, 2)),                         // data_mem[VI02++] = VF17 (RGBAQ)
+        i(u_nop(), l_sqi(15, 2)),                         // data_mem[VI02++] = VF15 (XYZF2)
+        i(u_nop(), l_iaddiu(3, 3, -1)),                   // VI03--
+        //   offset = 206 - (237+1) = -32
+        i(u_nop(), l_ibne(3, 0, -32)),
+        i(u_nop(), l_xgkick(5)),
+
+        // ----------------------------------------------------------------
+        // PC 239-249: per-face color entry (MSCAL FACE_COLOR_ENTRY)
+        //   The EE uploaded one f32 color per 6 vertices (a quad of two triangles) to
+        //   datamem[FACE_COLOR_ADDR..+6]. Repeat each into its 6 vertex colors at
+        //   datamem[72..107] for the main loop.
+        // ----------------------------------------------------------------
+        i(u_nop(), l_iaddiu(4, 0, FACE_COLOR_ADDR as i16)), // VI04 = face color load ptr
+        i(u_nop(), l_iaddiu(8, 0,  72)),   // VI08 = 72   (color store ptr)
+        i(u_nop(), l_iaddiu(10, 0,  6)),   // VI10 = 6    (face counter)
+        // FACE (PC=242):
+        i(u_nop(), l_lqi(12, 4)),          // VF12 = face color  VI04++
+        i(u_nop(), l_iaddiu(3, 0,  6)),    // VI03 = 6    (vertices per face)
+        // FILL (PC=244):
+        i(u_nop(), l_sqi(12, 8)),          // datamem[VI08++] = VF12
+        i(u_nop(), l_iaddiu(3, 3, -1)),    // VI03--
+        //   offset = 244 - (246+1) = -3
+        i(u_nop(), l_ibne(3, 0, -3)),
+        i(u_nop(), l_iaddiu(10, 10, -1)),  // VI10--
+        //   offset = 242 - (248+1) = -7
+        i(u_nop(), l_ibne(10, 0, -7)),
+        // Unconditional branch to PC 0 (VI08 = 108 ≠ 0): offset = 0 - (249+1) = -250
+        i(u_nop(), l_ibne(8, 0, -250)),
     ]
 };
+
+/// Alternate MSCAL address for packets whose colors were unpacked as V4-8.
+pub const U8_COLOR_ENTRY: u16 = 41;
+
+/// MSCAL address that XGKICKs a pre-built GIF packet at datamem[108] untransformed.
+pub const KICK_ENTRY: u16 = 53;
+
+/// MSCAL addresses for packets whose normals were unpacked as V4-16 (1.15 fixed point),
+/// with f32 or with V4-8 colors respectively.
+pub const S16_NORMAL_ENTRY: u16 = 57;
+pub const S16_NORMAL_U8_COLOR_ENTRY: u16 = 55;
+
+/// MSCAL address of the transform-only routine: f32 positions and colors, no normals,
+/// no lighting.
+pub const UNLIT_ENTRY: u16 = 67;
+
+/// MSCAL address of the gamma-aware (linear-space) lighting routine: f32 attributes.
+pub const LINEAR_LIGHT_ENTRY: u16 = 98;
+
+/// MSCAL addresses for packets whose colors are 8-bit palette indices (UNPACK S-8), with
+/// f32 or with V4-16 normals respectively.
+pub const PALETTE_ENTRY: u16 = 141;
+pub const S16_NORMAL_PALETTE_ENTRY: u16 = 151;
+
+/// MSCAL address of the main program for per-vertex interleaved f32 attributes.
+pub const INTERLEAVED_ENTRY: u16 = 153;
+
+/// MSCAL address of the main program with per-vertex fog: f32 attributes, fog constants
+/// at FOG_ADDR, and an XYZF2 GIF tag.
+pub const FOG_ENTRY: u16 = 192;
+
+/// VU datamem QW of the fog constants [0, 255, 255/(end-start), end], just past the
+/// tangents.
+pub const FOG_ADDR: u16 = 224;
+
+/// MSCAL address for packets that send one f32 color per face (6 vertices) instead of
+/// per vertex.
+pub const FACE_COLOR_ENTRY: u16 = 239;
+
+/// VU datamem QW of the first of a batch's 6 face colors, after the fog constants.
+pub const FACE_COLOR_ADDR: u16 = 225;
+
+/// VU datamem QW of each batch's GIF tag, uploaded by the EE; the transformed vertices
+/// follow it from 109 and XGKICK sends the packet from here.
+pub const GIF_TAG_ADDR: u16 = 108;
+
+/// VU datamem QW of palette entry 0; the 256 f32 RGBA entries fill datamem[256..511].
+pub const PALETTE_ADDR: u16 = 256;